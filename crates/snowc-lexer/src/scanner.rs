@@ -1,6 +1,7 @@
 use crate::token::TokenPosition;
 
 use super::{Char, Ctrl, Error, Float, Ident, Int, KeyWord, Op, Span, Str, Token};
+use std::collections::VecDeque;
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -10,6 +11,12 @@ pub struct Scanner<'a> {
     span: Span,
     last_chr_len: usize,
     last_char: char,
+    /// Tokens already scanned but not yet handed out, an interpolated
+    /// string produces several at once.
+    pending: VecDeque<Token>,
+    /// One entry per open `{` hole in an interpolated string, counting the
+    /// braces opened inside it so we know which `}` ends the hole.
+    holes: Vec<usize>,
 }
 
 impl<'a> Scanner<'a> {
@@ -19,9 +26,18 @@ impl<'a> Scanner<'a> {
             span: Span::default(),
             last_chr_len: 0,
             last_char: '\n',
+            pending: VecDeque::new(),
+            holes: Vec::new(),
         }
     }
 
+    /// Scans `src` as source number `file`, every span it gives has it.
+    pub fn in_file(src: &'a str, file: usize) -> Self {
+        let mut scanner = Self::new(src);
+        scanner.span.file = file;
+        scanner
+    }
+
     fn peek_char(&mut self) -> Option<&char> {
         self.src.peek()
     }
@@ -85,7 +101,7 @@ impl<'a> Scanner<'a> {
         let span = self.span();
         let keywords = [
             "enum", "data", "type", "true", "false", "return", "let", "and", "or", "not",
-            "if", "then", "else", "fn", "mod",
+            "if", "then", "else", "fn", "mod", "do", "class", "instance",
         ];
         let pos = self.get_token_position();
         if keywords.contains(&lexme.as_str()) {
@@ -94,16 +110,87 @@ impl<'a> Scanner<'a> {
         Some(Token::Ident(Ident { lexme, pos, span }))
     }
 
+    /// A string without holes is a single `Str`. One with holes,
+    /// `"cell {x} is {show c}"`, is split into
+    /// ```text
+    /// " Str("cell ") { x } Str(" is ") { show c } "
+    /// ```
+    /// where the quotes and braces are `Ctrl` tokens and the tokens inside
+    /// the braces are scanned like any other code.
     fn string(&mut self) -> Option<Token> {
-        let mut lexme = String::new();
-        while let Some(c) = self.next_char_if(|c| c != '"') {
-            lexme.push(c);
+        let open = self.get_token_position();
+        let quote = self.span;
+        self.span.reset(None);
+        let lexme = self.string_part();
+        if !self.matched('{') {
+            self.next_char();
+            let pos = self.get_token_position();
+            let span = Span::from((quote, self.span()));
+            return Some(Token::Str(Str { lexme, pos, span }));
+        }
+        let span = self.span();
+        if !lexme.is_empty() {
+            self.pending.push_back(Token::Str(Str {
+                lexme,
+                pos: TokenPosition::Middle,
+                span,
+            }));
+        }
+        self.hole();
+        Some(Token::Ctrl(Ctrl {
+            lexme: "\"".into(),
+            pos: open,
+            span: quote,
+        }))
+    }
+
+    /// Picks the string back up after the `}` closing a hole.
+    fn string_rest(&mut self) {
+        let lexme = self.string_part();
+        let span = self.span();
+        if !lexme.is_empty() {
+            self.pending.push_back(Token::Str(Str {
+                lexme,
+                pos: TokenPosition::Middle,
+                span,
+            }));
+        }
+        if self.matched('{') {
+            self.hole();
+            return;
         }
         self.next_char();
-        let lexme = lexme.replace("\\n", "\n");
         let pos = self.get_token_position();
         let span = self.span();
-        Some(Token::Str(Str { lexme, pos, span }))
+        self.pending.push_back(Token::Ctrl(Ctrl {
+            lexme: "\"".into(),
+            pos,
+            span,
+        }));
+    }
+
+    fn string_part(&mut self) -> String {
+        let mut lexme = String::new();
+        while let Some(c) = self.next_char_if(|c| c != '"' && c != '{') {
+            match c {
+                '\\' if self.matched('{') => lexme.extend(self.next_char()),
+                c => lexme.push(c),
+            }
+        }
+        lexme.replace("\\n", "\n")
+    }
+
+    fn hole(&mut self) {
+        self.next_char();
+        let span = self.span();
+        self.pending.push_back(Token::Ctrl(Ctrl {
+            lexme: "{".into(),
+            pos: TokenPosition::Middle,
+            span,
+        }));
+        self.holes.push(0);
+        // keeps the first token in the hole from looking like it starts a line
+        self.last_char = '{';
     }
 
     fn chr(&mut self) -> Option<Token> {
@@ -149,7 +236,7 @@ impl<'a> Scanner<'a> {
     fn parse(&mut self, ch: char) -> Option<Token> {
         match ch {
             n @ '0'..='9' => self.number(n),
-            i @ ('a'..='z' | 'A'..='Z') => self.ident(i),
+            i @ ('a'..='z' | 'A'..='Z' | '_') => self.ident(i),
             '"' => self.string(),
             '\'' => self.chr(),
             '-' if self.matched('-') => self.comment(),
@@ -159,6 +246,12 @@ impl<'a> Scanner<'a> {
             '>' if self.matched('=') => {
                 self.token(">=", |lexme, pos, span| Token::Op(Op { lexme, pos, span }))
             }
+            '<' if self.matched('-') => self.token("<-", |lexme, pos, span| {
+                Token::Ctrl(Ctrl { lexme, pos, span })
+            }),
+            '.' if self.matched('.') => {
+                self.token("..", |lexme, pos, span| Token::Op(Op { lexme, pos, span }))
+            }
             '<' if self.matched('=') => {
                 self.token("<=", |lexme, pos, span| Token::Op(Op { lexme, pos, span }))
             }
@@ -194,12 +287,28 @@ impl<'a> Scanner<'a> {
             ')' => self.token(")", |lexme, pos, span| {
                 Token::Ctrl(Ctrl { lexme, pos, span })
             }),
-            '{' => self.token("{", |lexme, pos, span| {
-                Token::Ctrl(Ctrl { lexme, pos, span })
-            }),
-            '}' => self.token("}", |lexme, pos, span| {
-                Token::Ctrl(Ctrl { lexme, pos, span })
-            }),
+            '{' => {
+                if let Some(depth) = self.holes.last_mut() {
+                    *depth += 1;
+                }
+                self.token("{", |lexme, pos, span| Token::Ctrl(Ctrl { lexme, pos, span }))
+            }
+            '}' if self.holes.last() == Some(&0) => {
+                self.holes.pop();
+                let span = self.span();
+                self.string_rest();
+                Some(Token::Ctrl(Ctrl {
+                    lexme: "}".into(),
+                    pos: TokenPosition::Middle,
+                    span,
+                }))
+            }
+            '}' => {
+                if let Some(depth) = self.holes.last_mut() {
+                    *depth -= 1;
+                }
+                self.token("}", |lexme, pos, span| Token::Ctrl(Ctrl { lexme, pos, span }))
+            }
             '[' => self.token("[", |lexme, pos, span| {
                 Token::Ctrl(Ctrl { lexme, pos, span })
             }),
@@ -238,6 +347,9 @@ impl<'a> Scanner<'a> {
 impl<'a> Iterator for Scanner<'a> {
     type Item = Token;
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(token);
+        }
         self.next_char().and_then(|c| self.parse(c))
     }
 }
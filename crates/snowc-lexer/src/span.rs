@@ -1,6 +1,7 @@
 use std::{fmt, ops::Range};
 
 #[derive(Default, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub idx_start: usize,
     pub idx_end: usize,
@@ -8,6 +9,9 @@ pub struct Span {
     pub row_end: usize,
     pub col_start: usize,
     pub col_end: usize,
+    /// Which of several sources the span is in, 0 unless the one who
+    /// scanned it gave a file.
+    pub file: usize,
 }
 
 impl Span {
@@ -26,6 +30,7 @@ impl Span {
             row_end,
             col_start,
             col_end,
+            file: 0,
         }
     }
     pub fn right_shift(&mut self, ch: char) {
@@ -67,7 +72,10 @@ impl From<(Span, Span)> for Span {
         let col_start: usize = x.col_start;
         let row_end: usize = y.row_end;
         let col_end: usize = y.col_end;
-        Self::new(idx_start, idx_end, row_start, row_end, col_start, col_end)
+        Self {
+            file: x.file,
+            ..Self::new(idx_start, idx_end, row_start, row_end, col_start, col_end)
+        }
     }
 }
 
@@ -15,6 +15,7 @@ macro_rules! map_a {
 macro_rules! init_token {
     ($name:ident) => {
         #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name {
             pub lexme: String,
             pub pos: TokenPosition,
@@ -40,6 +41,7 @@ init_token!(Char);
 init_token!(Error);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     KeyWord(KeyWord),
     Ident(Ident),
@@ -110,6 +112,7 @@ impl fmt::Display for Token {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenPosition {
     Start,
     Middle,
@@ -38,3 +38,5 @@ macro_rules! snapshot {
 
 snapshot!(hello_world, "./../../../samples/hello_world.snow");
 snapshot!(rule110, "./../../../samples/rule110.snow");
+snapshot!(ranges, "./../../../samples/ranges.snow");
+snapshot!(interpolation, "./../../../samples/interpolation.snow");
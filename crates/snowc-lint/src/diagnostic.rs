@@ -0,0 +1,78 @@
+use super::Level;
+use annotate_snippets::{
+    display_list::{DisplayList, FormatOptions},
+    snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation},
+};
+use snowc_parse::Span;
+use std::fmt;
+
+/// One thing a lint found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub lint: &'static str,
+    pub level: Level,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    /// Found by `lint`, at the level it runs at once [`crate::lint`] has
+    /// looked it up.
+    pub(crate) fn new(
+        lint: &'static str,
+        message: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        Self {
+            lint,
+            level: Level::Warn,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Rendered the same way compile errors are, with the lint id in the
+    /// title.
+    pub fn report(&self, filename: &str, src: &str) -> String {
+        let annotation_type = match self.level {
+            Level::Deny => AnnotationType::Error,
+            _ => AnnotationType::Warning,
+        };
+        let footer = format!("`--allow {}` turns this lint off", self.lint);
+        let end = self.span.idx_end.min(src.len());
+        let snippet = Snippet {
+            title: Some(Annotation {
+                label: Some(&self.message),
+                id: Some(self.lint),
+                annotation_type,
+            }),
+            footer: vec![Annotation {
+                label: Some(&footer),
+                id: None,
+                annotation_type: AnnotationType::Note,
+            }],
+            slices: vec![Slice {
+                source: src,
+                line_start: 1,
+                origin: Some(filename),
+                fold: true,
+                annotations: vec![SourceAnnotation {
+                    label: "",
+                    annotation_type,
+                    range: (self.span.idx_start.min(end), end),
+                }],
+            }],
+            opt: FormatOptions {
+                color: true,
+                ..Default::default()
+            },
+        };
+        DisplayList::from(snippet).to_string()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.lint, self.message)
+    }
+}
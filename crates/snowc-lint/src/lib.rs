@@ -0,0 +1,118 @@
+//! Lints over the parsed AST, for mistakes that are valid Snow but almost
+//! certainly not what was meant.
+//!
+//! Every lint has an id and a [`Level`]. [`Levels`] starts from the level in
+//! [`LINTS`] and can be changed per lint, `snowc lint --allow unused-variable`
+//! turns one off and `--deny` makes one fail the run.
+mod diagnostic;
+mod rules;
+
+#[cfg(test)]
+mod tests;
+
+pub use diagnostic::Diagnostic;
+
+use snowc_parse::Expr;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    Warn,
+    /// Reported as an error, `snowc lint` exits with a failure.
+    Deny,
+}
+
+/// A lint and the level it has unless told otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct Lint {
+    pub id: &'static str,
+    pub level: Level,
+    pub description: &'static str,
+}
+
+pub const UNUSED_VARIABLE: &str = "unused-variable";
+pub const UNUSED_FUNCTION: &str = "unused-function";
+pub const SHADOWED_NAME: &str = "shadowed-name";
+pub const REDUNDANT_IF: &str = "redundant-if";
+pub const SELF_COMPARISON: &str = "self-comparison";
+pub const NO_BASE_CASE: &str = "no-base-case";
+
+pub const LINTS: &[Lint] = &[
+    Lint {
+        id: UNUSED_VARIABLE,
+        level: Level::Warn,
+        description: "a parameter, pattern variable or `<-` binding that is never used",
+    },
+    Lint {
+        id: UNUSED_FUNCTION,
+        level: Level::Warn,
+        description: "a function `main` never reaches",
+    },
+    Lint {
+        id: SHADOWED_NAME,
+        level: Level::Warn,
+        description: "a name that hides a function or another name in scope",
+    },
+    Lint {
+        id: REDUNDANT_IF,
+        level: Level::Warn,
+        description: "an `if` that picks between `true` and `false`",
+    },
+    Lint {
+        id: SELF_COMPARISON,
+        level: Level::Warn,
+        description: "an expression compared with itself",
+    },
+    Lint {
+        id: NO_BASE_CASE,
+        level: Level::Deny,
+        description: "a function that calls itself on every path and never returns",
+    },
+];
+
+/// The level every lint runs at.
+#[derive(Debug, Clone)]
+pub struct Levels(HashMap<&'static str, Level>);
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self(LINTS.iter().map(|lint| (lint.id, lint.level)).collect())
+    }
+}
+
+impl Levels {
+    pub fn set(&mut self, id: &str, level: Level) -> Result<(), String> {
+        let Some(lint) = LINTS.iter().find(|lint| lint.id == id) else {
+            return Err(format!("unknown lint '{id}'"));
+        };
+        self.0.insert(lint.id, level);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Level {
+        self.0.get(id).copied().unwrap_or(Level::Allow)
+    }
+}
+
+/// Everything the lints find in `ast` that isn't allowed, in source order.
+pub fn lint(ast: &[Expr], levels: &Levels) -> Vec<Diagnostic> {
+    let mut found = vec![];
+    rules::unused_functions(ast, &mut found);
+    rules::bindings(ast, &mut found);
+    rules::redundant(ast, &mut found);
+    rules::no_base_case(ast, &mut found);
+    let mut diagnostics = found
+        .into_iter()
+        .filter_map(|diagnostic| {
+            let level = levels.get(diagnostic.lint);
+            (level != Level::Allow).then_some(Diagnostic {
+                level,
+                ..diagnostic
+            })
+        })
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.idx_start);
+    diagnostics
+}
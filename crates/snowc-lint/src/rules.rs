@@ -0,0 +1,341 @@
+//! The lints themselves. Each one pushes a [`Diagnostic`] for everything it
+//! finds, [`crate::lint`] drops the ones that are allowed.
+use super::{
+    Diagnostic, NO_BASE_CASE, REDUNDANT_IF, SELF_COMPARISON, SHADOWED_NAME,
+    UNUSED_FUNCTION, UNUSED_VARIABLE,
+};
+use snowc_parse::{
+    visit::{walk_binary, walk_if_else, walk_stmt},
+    App, Arm, Atom, Binary, Do, Expr, Op, Pat, Span, Stmt, Visitor,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Every function of the program with its body, instance methods included.
+fn funcs(ast: &[Expr]) -> impl Iterator<Item = (&str, &Expr, Span)> {
+    ast.iter()
+        .flat_map(|expr| match expr {
+            Expr::Instance(instance) => instance.methods.iter(),
+            _ => std::slice::from_ref(expr).iter(),
+        })
+        .filter_map(|expr| match expr {
+            Expr::Func(name, _, body, span) => {
+                Some((name.as_str(), body.as_ref(), *span))
+            }
+            _ => None,
+        })
+}
+
+/// Every name a function body refers to.
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Visitor for Names {
+    fn visit_atom(&mut self, atom: &Atom) {
+        if let Atom::Id(name, ..) = atom {
+            self.0.insert(name.clone());
+        }
+    }
+}
+
+/// Functions `main` can't reach. Instance methods are reached through their
+/// class so they count as used, a program without `main` is a library and
+/// isn't checked.
+pub(crate) fn unused_functions(ast: &[Expr], found: &mut Vec<Diagnostic>) {
+    let calls = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Func(name, _, body, _) => {
+                let mut names = Names::default();
+                names.visit_expr(body);
+                Some((name.as_str(), names.0))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    if !calls.contains_key("main") {
+        return;
+    }
+    let mut reached = HashSet::from(["main"]);
+    let mut todo = vec!["main"];
+    for expr in ast.iter() {
+        if let Expr::Instance(instance) = expr {
+            let mut names = Names::default();
+            instance
+                .methods
+                .iter()
+                .for_each(|method| names.visit_expr(method));
+            for (name, _) in calls.iter() {
+                if names.0.contains(*name) && reached.insert(name) {
+                    todo.push(name);
+                }
+            }
+        }
+    }
+    while let Some(name) = todo.pop() {
+        for callee in calls[name].iter() {
+            if let Some((callee, _)) = calls.get_key_value(callee.as_str()) {
+                if reached.insert(callee) {
+                    todo.push(callee);
+                }
+            }
+        }
+    }
+    for expr in ast.iter() {
+        if let Expr::Func(name, .., span) = expr {
+            if !reached.contains(name.as_str()) {
+                let message = format!("'{name}' is never used");
+                found.push(Diagnostic::new(UNUSED_FUNCTION, message, *span));
+            }
+        }
+    }
+}
+
+/// A name brought into scope and whether anything has referred to it yet.
+struct Binding {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+/// Walks every function body with the names bound around each expression,
+/// for unused and shadowed names. Names starting with `_` are meant to go
+/// unused and the ones starting with `$` are made up by the parser.
+struct Bindings<'a> {
+    globals: HashSet<&'a str>,
+    scope: Vec<Binding>,
+    found: &'a mut Vec<Diagnostic>,
+}
+
+impl Bindings<'_> {
+    fn bind(&mut self, name: &str, span: Span) {
+        if name.starts_with(['_', '$']) {
+            return;
+        }
+        let shadows_local = self.scope.iter().any(|binding| binding.name == name);
+        if shadows_local || self.globals.contains(name) {
+            let message = format!("'{name}' shadows an earlier '{name}'");
+            self.found
+                .push(Diagnostic::new(SHADOWED_NAME, message, span));
+        }
+        self.scope.push(Binding {
+            name: name.into(),
+            span,
+            used: false,
+        });
+    }
+
+    /// Forgets every name bound after the first `len`.
+    fn unbind(&mut self, len: usize) {
+        while self.scope.len() > len {
+            let Some(binding) = self.scope.pop() else {
+                break;
+            };
+            if !binding.used {
+                let message = format!("'{}' is never used", binding.name);
+                self.found
+                    .push(Diagnostic::new(UNUSED_VARIABLE, message, binding.span));
+            }
+        }
+    }
+
+    fn bind_pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Id(name, span) => self.bind(name, *span),
+            Pat::Constructor(_, args, _) => {
+                args.iter().for_each(|arg| self.bind_pat(arg))
+            }
+            Pat::Wildcard(..) | Pat::Lit(..) => {}
+        }
+    }
+}
+
+impl Visitor for Bindings<'_> {
+    fn visit_atom(&mut self, atom: &Atom) {
+        let Atom::Id(name, ..) = atom else {
+            return;
+        };
+        if let Some(binding) = self.scope.iter_mut().rev().find(|b| &b.name == name) {
+            binding.used = true;
+        }
+    }
+
+    fn visit_closure(&mut self, head: &Expr, tail: &Expr, _span: Span) {
+        let Expr::Atom(Atom::Id(name, ..)) = head else {
+            unreachable!("closure parameters are always names");
+        };
+        let len = self.scope.len();
+        self.bind(name, head.span());
+        self.visit_expr(tail);
+        self.unbind(len);
+    }
+
+    fn visit_arm(&mut self, arm: &Arm) {
+        let len = self.scope.len();
+        for pat in arm.pats.iter() {
+            self.bind_pat(pat);
+        }
+        if let Some(guard) = &arm.guard {
+            self.visit_expr(guard);
+        }
+        self.visit_expr(&arm.body);
+        self.unbind(len);
+    }
+
+    /// `name <- expr` is in scope for the lines after it.
+    fn visit_do(&mut self, d: &Do) {
+        let len = self.scope.len();
+        for stmt in d.stmts.iter() {
+            walk_stmt(self, stmt);
+            if let Stmt::Bind(name, _, span) = stmt {
+                self.bind(name, *span);
+            }
+        }
+        self.unbind(len);
+    }
+}
+
+pub(crate) fn bindings(ast: &[Expr], found: &mut Vec<Diagnostic>) {
+    let globals = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Func(name, ..) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut bindings = Bindings {
+        globals,
+        scope: vec![],
+        found,
+    };
+    for (_, body, _) in funcs(ast) {
+        bindings.visit_expr(body);
+    }
+}
+
+/// `if` and comparisons that give back something known without running
+/// them.
+struct Redundant<'a>(&'a mut Vec<Diagnostic>);
+
+impl Visitor for Redundant<'_> {
+    fn visit_if_else(
+        &mut self,
+        condition: &Expr,
+        then: &Expr,
+        r#else: &Expr,
+        span: Span,
+    ) {
+        let branches = match (then, r#else) {
+            (Expr::Atom(Atom::Bool(then, ..)), Expr::Atom(Atom::Bool(r#else, ..))) => {
+                Some((*then, *r#else))
+            }
+            _ => None,
+        };
+        let message = match branches {
+            Some((true, false)) => Some("this `if` gives back its condition".to_string()),
+            Some((false, true)) => {
+                Some("this `if` gives back `not` of its condition".to_string())
+            }
+            Some((b, _)) => Some(format!("both branches of this `if` are `{b}`")),
+            None => None,
+        };
+        if let Some(message) = message {
+            self.0.push(Diagnostic::new(REDUNDANT_IF, message, span));
+        }
+        walk_if_else(self, condition, then, r#else);
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) {
+        let always = match binary.op {
+            Op::Eq | Op::GrtEq | Op::LesEq => Some(true),
+            Op::Neq | Op::Grt | Op::Les => Some(false),
+            _ => None,
+        };
+        // spans differ between the two sides, what they print as doesn't
+        let same = binary.left.to_string() == binary.right.to_string();
+        if let Some(always) = always.filter(|_| same) {
+            let message = format!("comparing a value with itself is always `{always}`");
+            self.0
+                .push(Diagnostic::new(SELF_COMPARISON, message, binary.span));
+        }
+        walk_binary(self, binary);
+    }
+}
+
+pub(crate) fn redundant(ast: &[Expr], found: &mut Vec<Diagnostic>) {
+    let mut redundant = Redundant(found);
+    for (_, body, _) in funcs(ast) {
+        redundant.visit_expr(body);
+    }
+}
+
+/// Whether evaluating `expr` calls `name` no matter which branches are
+/// taken. Only a call with at least `arity` arguments runs the function,
+/// and nothing under a closure runs until the closure is called.
+fn always_calls(expr: &Expr, name: &str, arity: usize) -> bool {
+    let calls = |expr: &Expr| always_calls(expr, name, arity);
+    match expr {
+        Expr::Atom(Atom::Id(id, ..)) => arity == 0 && id == name,
+        Expr::Atom(..) | Expr::Closure(..) | Expr::Error(..) => false,
+        Expr::Unary(unary) => calls(&unary.expr),
+        // the right side of `and` and `or` is only run when it matters
+        Expr::Binary(Binary {
+            op: Op::And | Op::Or,
+            left,
+            ..
+        }) => calls(left),
+        Expr::Binary(binary) => calls(&binary.left) || calls(&binary.right),
+        Expr::App(App {
+            name: func, args, ..
+        }) => {
+            let is_call = matches!(func.as_ref(), Expr::Atom(Atom::Id(id, ..)) if id == name)
+                && args.len() >= arity;
+            is_call || calls(func) || args.iter().any(calls)
+        }
+        Expr::Array(items, ..) => items.iter().any(calls),
+        Expr::IfElse(condition, then, r#else, _) => {
+            calls(condition) || (calls(then) && calls(r#else))
+        }
+        Expr::Match(m) => {
+            m.exprs.iter().any(calls)
+                || m.arms
+                    .iter()
+                    .all(|arm| arm.guard.as_ref().is_some_and(calls) || calls(&arm.body))
+        }
+        // a call on the last line, after actions ran, is a loop that keeps
+        // running them
+        Expr::Do(d) => match d.stmts.split_last() {
+            Some((last, [])) => calls(last.expr()),
+            Some((_, before)) => before.iter().any(|stmt| calls(stmt.expr())),
+            None => false,
+        },
+        Expr::Index(index) => {
+            calls(&index.expr)
+                || calls(&index.index)
+                || index.end.as_deref().is_some_and(calls)
+        }
+        Expr::Concat(concat) => concat.parts.iter().any(calls),
+        Expr::Func(..)
+        | Expr::Alias(..)
+        | Expr::Data(..)
+        | Expr::Class(..)
+        | Expr::Instance(..) => false,
+    }
+}
+
+/// Functions that call themselves on every path, they can only loop
+/// forever.
+pub(crate) fn no_base_case(ast: &[Expr], found: &mut Vec<Diagnostic>) {
+    for (name, body, span) in funcs(ast) {
+        let mut arity = 0;
+        let mut body = body;
+        while let Expr::Closure(_, tail, ..) = body {
+            arity += 1;
+            body = tail;
+        }
+        if always_calls(body, name, arity) {
+            let message =
+                format!("'{name}' calls itself on every path and never returns");
+            found.push(Diagnostic::new(NO_BASE_CASE, message, span));
+        }
+    }
+}
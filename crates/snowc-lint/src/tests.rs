@@ -0,0 +1,174 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+use snowc_parse::parse;
+
+/// The lints that fired on `src` and the source they point at.
+fn found(src: &str) -> Vec<(&'static str, String)> {
+    let ast = parse(src).unwrap();
+    lint(&ast, &Levels::default())
+        .into_iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span;
+            (
+                diagnostic.lint,
+                src[span.idx_start..span.idx_end].to_string(),
+            )
+        })
+        .collect()
+}
+
+macro_rules! lint_test {
+    ($name:ident, $src:expr, [$(($lint:expr, $at:expr)),* $(,)?] $(,)?) => {
+        #[test]
+        fn $name() {
+            let expected: Vec<(&str, String)> = vec![$(($lint, $at.to_string())),*];
+            assert_eq!(found($src), expected);
+        }
+    };
+}
+
+lint_test!(
+    clean_program,
+    r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+size Leaf = 0
+size (Node l _ r) = size l + 1 + size r
+evens xs = [x | x <- xs, x % 2 == 0]
+main = do {
+  name <- read_line;
+  print name (size Leaf) (evens [1, 2])
+}
+"#,
+    [],
+);
+
+lint_test!(
+    unused_parameters_and_patterns,
+    "first x y = x\nleft (Pair l r) = l\nmain = first 1 2",
+    [
+        (UNUSED_VARIABLE, "y"),
+        (UNUSED_FUNCTION, "left (Pair l r) = l"),
+        (UNUSED_VARIABLE, "r"),
+    ],
+);
+
+lint_test!(
+    underscore_names_can_go_unused,
+    "first x _y = x\nmain = first 1 2",
+    [],
+);
+
+lint_test!(
+    unused_do_binding,
+    "main = do {\n  name <- read_line;\n  print 1\n}",
+    [(UNUSED_VARIABLE, "name <- read_line")],
+);
+
+lint_test!(
+    functions_only_used_by_unused_functions,
+    "a = b\nb = 1\nmain = 2",
+    [(UNUSED_FUNCTION, "a = b"), (UNUSED_FUNCTION, "b = 1")],
+);
+
+lint_test!(libraries_have_no_unused_functions, "a = 1", [],);
+
+lint_test!(
+    shadowed_names,
+    "size = 1\nf size = \\size -> size\nmain = f size 2",
+    [
+        (SHADOWED_NAME, "size"),
+        (UNUSED_VARIABLE, "size"),
+        (SHADOWED_NAME, "size"),
+    ],
+);
+
+lint_test!(
+    redundant_if,
+    r#"
+alive cell = if cell then true else false
+dead cell = if cell then false else true
+yes cell = if cell then true else true
+main = [alive true, dead true, yes true]
+"#,
+    [
+        (REDUNDANT_IF, "if cell then true else false"),
+        (REDUNDANT_IF, "if cell then false else true"),
+        (REDUNDANT_IF, "if cell then true else true"),
+    ],
+);
+
+lint_test!(
+    self_comparison,
+    "f x = [x == x, x[0] < x[0], x == x[0]]\nmain = f [1]",
+    [
+        (SELF_COMPARISON, "x == x"),
+        (SELF_COMPARISON, "x[0] < x[0]")
+    ],
+);
+
+lint_test!(
+    no_base_case,
+    r#"
+count n = count (n - 1) + 1
+loop n = if n == 0 then loop 1 else loop n
+spin = spin
+main = [count 1, loop 1, spin]
+"#,
+    [
+        (NO_BASE_CASE, "count n = count (n - 1) + 1"),
+        (NO_BASE_CASE, "loop n = if n == 0 then loop 1 else loop n"),
+        (NO_BASE_CASE, "spin = spin"),
+    ],
+);
+
+lint_test!(
+    recursion_with_a_base_case,
+    r#"
+fact 0 = 1
+fact n = n * fact (n - 1)
+down n = if n == 0 then 0 else down (n - 1)
+later f = \x -> [f x, later f]
+main = [fact 3, down 3, length (later fact 1)]
+"#,
+    [],
+);
+
+lint_test!(
+    io_loops_have_no_base_case,
+    r#"
+tick : IO = do {
+  print "tick";
+  tick
+}
+stuck : IO = do {
+  stuck;
+  print "never"
+}
+main = do {
+  stuck;
+  tick
+}
+"#,
+    [(NO_BASE_CASE, "stuck : IO = do {\n  stuck;\n  print \"never\"\n}")],
+);
+
+#[test]
+fn levels_can_be_changed() {
+    let ast = parse("first x y = x\nmain = first 1 2").unwrap();
+    let mut levels = Levels::default();
+    levels.set(UNUSED_VARIABLE, Level::Allow).unwrap();
+    assert_eq!(lint(&ast, &levels), vec![]);
+    levels.set(UNUSED_VARIABLE, Level::Deny).unwrap();
+    assert_eq!(lint(&ast, &levels)[0].level, Level::Deny);
+    assert!(levels.set("unused", Level::Deny).is_err());
+}
+
+#[test]
+fn report_names_the_lint() {
+    let src = "first x y = x\nmain = first 1 2";
+    let ast = parse(src).unwrap();
+    let report = lint(&ast, &Levels::default())[0].report("main.snow", src);
+    assert!(report.contains("[unused-variable]"), "{report}");
+    assert!(report.contains("'y' is never used"), "{report}");
+}
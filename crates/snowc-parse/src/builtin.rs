@@ -0,0 +1,162 @@
+//! Functions and classes every program can use without defining them. The
+//! type checker and the evaluators both work from these lists so they agree
+//! on what exists and what it takes.
+use crate::{
+    error::Error, parser::type_signature, Class, Expr, Op, Scanner, Token, TypeInfo,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: &'static str,
+    /// Written the way a function signature is, `Array<a> -> a`.
+    pub signature: &'static str,
+    /// Takes any number of arguments, the signature only describes the
+    /// first.
+    pub variadic: bool,
+}
+
+impl Builtin {
+    const fn new(name: &'static str, signature: &'static str) -> Self {
+        Self {
+            name,
+            signature,
+            variadic: false,
+        }
+    }
+
+    /// The signature split on its top level arrows, the same shape
+    /// `Expr::Func` keeps a declared signature in.
+    pub fn type_info(&self) -> Vec<TypeInfo> {
+        let mut tokens = Scanner::new(self.signature).collect::<Vec<Token>>();
+        type_signature(&mut tokens).expect("builtin signatures are valid")
+    }
+
+    /// How many arguments the signature takes.
+    pub fn arity(&self) -> usize {
+        let mut depth = 0;
+        let mut arrows = 0;
+        let mut chars = self.signature.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '-' if chars.peek() == Some(&'>') => {
+                    chars.next();
+                    if depth == 0 {
+                        arrows += 1;
+                    }
+                }
+                '(' | '<' => depth += 1,
+                ')' | '>' => depth -= 1,
+                _ => {}
+            }
+        }
+        arrows
+    }
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "print",
+        signature: "a -> IO a",
+        variadic: true,
+    },
+    Builtin::new("nth", "Array<a> -> Int -> a"),
+    Builtin::new("length", "Array<a> -> Int"),
+    Builtin::new("push", "Array<a> -> a -> Array<a>"),
+    Builtin::new("head", "Array<a> -> a"),
+    Builtin::new("tail", "Array<a> -> Array<a>"),
+    Builtin::new("range", "Int -> Int -> Array<Int>"),
+    Builtin::new("range_then", "Int -> Int -> Int -> Array<Int>"),
+    Builtin::new("concat_map", "(a -> Array<b>) -> Array<a> -> Array<b>"),
+    Builtin::new("read_line", "IO String"),
+    // run their first argument before giving back the second, in lazy mode
+    // `seq` runs it as far as its outermost constructor, `deep_seq` all of it
+    Builtin::new("seq", "a -> b -> b"),
+    Builtin::new("deep_seq", "a -> b -> b"),
+];
+
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// The builtins written in Snow. Their signatures are in [`BUILTINS`] with
+/// the others, so a program is checked without looking at these.
+pub const PRELUDE: &str = "
+-- halves the array so it recurses as deep as the log of its length and
+-- copies each item that many times
+concat_map f xs
+  : (a -> Array<b>) -> Array<a> -> Array<b>
+  = if length xs == 0 then []
+    else if length xs == 1 then f xs[0]
+    else concat_map f xs[0..length xs / 2] + concat_map f xs[length xs / 2..length xs]
+";
+
+pub fn prelude() -> Vec<Expr> {
+    crate::parse(PRELUDE).expect("the prelude is valid")
+}
+
+/// A function the host running a program gives it on top of [`BUILTINS`],
+/// a native an embedder registered. Checking a program that calls one
+/// needs to know it exists and what it takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extern {
+    pub name: String,
+    pub arity: usize,
+    /// Written the way a function signature is. Without one the type
+    /// checker lets it take and give back anything.
+    pub signature: Option<String>,
+}
+
+impl Extern {
+    /// The signature split on its top level arrows, empty when it has none
+    /// the same way it is for a function without a signature.
+    pub fn type_info(&self) -> Result<Vec<TypeInfo>, Error> {
+        let Some(signature) = &self.signature else {
+            return Ok(vec![]);
+        };
+        let mut tokens = Scanner::new(signature).collect::<Vec<Token>>();
+        type_signature(&mut tokens)
+    }
+}
+
+/// The classes every program starts with. The operators call their methods
+/// for `data` types, see [`operator_method`].
+pub const CLASSES: &str = "
+class Eq a { eq : a -> a -> Bool }
+class Ord a { lt : a -> a -> Bool }
+class Add a { add : a -> a -> a }
+class Num a {
+  sub : a -> a -> a;
+  mul : a -> a -> a;
+  div : a -> a -> a;
+  rem : a -> a -> a;
+  neg : a -> a
+}
+class Show a { show : a -> String }
+";
+
+pub fn classes() -> Vec<Class> {
+    crate::parse(CLASSES)
+        .expect("builtin classes are valid")
+        .into_iter()
+        .filter_map(|expr| match expr {
+            Expr::Class(class) => Some(class),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The method a binary operator stands for. `a == b` is `eq a b`,
+/// `a > b` is `lt b a` and `a != b` is `not (eq a b)`. Negation, `-a`, is
+/// `neg a`.
+pub fn operator_method(op: Op) -> Option<&'static str> {
+    Some(match op {
+        Op::Eq | Op::Neq => "eq",
+        Op::Les | Op::Grt | Op::LesEq | Op::GrtEq => "lt",
+        Op::Plus => "add",
+        Op::Minus => "sub",
+        Op::Mult => "mul",
+        Op::Div => "div",
+        Op::Mod => "rem",
+        _ => return None,
+    })
+}
@@ -35,6 +35,36 @@ pub enum Error {
     UnexpectedEndOfInput(Span),
     #[error("unclosed parenthesis {0:?}")]
     UnclosedParen(Span),
+    #[error("expected a pattern {0:?}")]
+    ExpectedPattern(Span),
+    #[error("clauses of '{0}' take a different number of arguments {1:?}")]
+    ClauseArityMismatch(String, Span),
+    /// The name and the clause that gives a second signature.
+    #[error("'{0}' already has a signature, only one of its clauses can give one")]
+    SecondSignature(String, Span),
+    #[error("malformed range, expected [a..b] or [a, b..c] {0:?}")]
+    MalformedRange(Span),
+    #[error("the last line of a do block must be an expression {0:?}")]
+    DoEndsWithBind(Span),
+    #[error("expected a constructor name {0:?}")]
+    ExpectedConstructor(Span),
+    #[error("missing '}}' to close the interpolation")]
+    UnclosedInterpolation(Span),
+    #[error("undefined '{0}' {1:?}")]
+    Undefined(String, Span),
+    /// The name, where it was first defined and where it is defined again.
+    #[error("'{0}' is defined more than once {2:?}")]
+    Duplicate(String, Box<Span>, Span),
+    /// A top level definition with the name of a builtin or of a function
+    /// the host gives the program.
+    #[error("'{0}' is a builtin and can't be defined again")]
+    RedefinedBuiltin(String, Span),
+    #[error("'{0}' takes {1} parameters but its signature only gives {2} {3:?}")]
+    SignatureArity(String, usize, usize, Span),
+    #[error("missing main function")]
+    MissingMain(Span),
+    #[error("'{0}' is not a method of '{1}' {2:?}")]
+    NotAMethod(String, String, Span),
 }
 
 impl Error {
@@ -53,6 +83,19 @@ impl Error {
             | Self::UnexpectedToken(_, _, s)
             | Self::UnexpectedEndOfInput(s)
             | Self::UnclosedParen(s)
+            | Self::ExpectedPattern(s)
+            | Self::ClauseArityMismatch(_, s)
+            | Self::SecondSignature(_, s)
+            | Self::MalformedRange(s)
+            | Self::DoEndsWithBind(s)
+            | Self::ExpectedConstructor(s)
+            | Self::UnclosedInterpolation(s)
+            | Self::Undefined(_, s)
+            | Self::Duplicate(_, _, s)
+            | Self::RedefinedBuiltin(_, s)
+            | Self::SignatureArity(.., s)
+            | Self::MissingMain(s)
+            | Self::NotAMethod(.., s)
             | Self::UnknownOperator(s) => *s,
         }
     }
@@ -89,6 +132,14 @@ impl Error {
             };
             annotations.push(info);
         }
+        if let Self::Duplicate(_, first, _) = self {
+            let info = SourceAnnotation {
+                label: "first defined here",
+                annotation_type: AnnotationType::Info,
+                range: (first.idx_start, first.idx_end),
+            };
+            annotations.push(info);
+        }
         let snippet = Snippet {
             title: Some(Annotation {
                 label: Some(label),
@@ -98,7 +149,8 @@ impl Error {
             footer: vec![],
             slices: vec![Slice {
                 source: src,
-                line_start: span.row_start,
+                // the source is the whole file, its first line is line 1
+                line_start: 1,
                 origin: Some(filename),
                 fold: true,
                 annotations,
@@ -1,4 +1,5 @@
 use super::{Ident, Op, Span, TokenPosition};
+use std::collections::HashMap;
 use std::fmt;
 
 macro_rules! is_expr {
@@ -13,6 +14,7 @@ macro_rules! is_expr {
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Atom {
     Int(i32, TokenPosition, Span),
     Float(String, TokenPosition, Span),
@@ -73,6 +75,7 @@ impl fmt::Display for Atom {
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Unary {
     pub op: Op,
     pub expr: Box<Expr>,
@@ -81,6 +84,7 @@ pub struct Unary {
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Binary {
     pub op: Op,
     pub left: Box<Expr>,
@@ -90,6 +94,7 @@ pub struct Binary {
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct App {
     pub name: Box<Expr>,
     pub args: Vec<Expr>,
@@ -97,7 +102,267 @@ pub struct App {
     pub span: Span,
 }
 
+/// A pattern in parameter position of a function clause.
+/// ```hs
+/// fact 0 = 1
+/// fact n = n * fact (n - 1)
+/// ```
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pat {
+    Wildcard(Span),
+    Id(String, Span),
+    Lit(Atom),
+    Constructor(String, Vec<Self>, Span),
+}
+
+impl Pat {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Wildcard(span) => *span,
+            Self::Id(_, span) => *span,
+            Self::Lit(atom) => atom.span(),
+            Self::Constructor(.., span) => *span,
+        }
+    }
+
+    /// Wildcards and plain names match every value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Self::Wildcard(..) | Self::Id(..))
+    }
+
+    /// Every name this pattern binds, left to right.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Self::Id(name, ..) => vec![name.as_str()],
+            Self::Constructor(_, args, ..) => args.iter().flat_map(Self::names).collect(),
+            Self::Wildcard(..) | Self::Lit(..) => vec![],
+        }
+    }
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wildcard(..) => write!(f, "_"),
+            Self::Id(name, ..) => write!(f, "{name}"),
+            Self::Lit(atom) => write!(f, "{atom:?}"),
+            Self::Constructor(name, args, ..) if args.is_empty() => write!(f, "{name}"),
+            Self::Constructor(name, args, ..) => {
+                write!(f, "({name}")?;
+                for arg in args.iter() {
+                    write!(f, " {arg}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+/// One row of a [`Match`], tried top to bottom.
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arm {
+    pub pats: Vec<Pat>,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+    pub span: Span,
+}
+
+/// All clauses of a function folded into one dispatch over its arguments.
+/// ```hs
+/// sign 0 = 0
+/// sign n
+///   | n < 0 = -1
+///   | true = 1
+/// -- is the same as
+/// sign = (\$0 -> (match $0 | 0 -> 0 | n if (< n 0) -> (- 1) | n if true -> 1))
+/// ```
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Match {
+    pub exprs: Vec<Expr>,
+    pub arms: Vec<Arm>,
+    pub pos: TokenPosition,
+    pub span: Span,
+}
+
+/// One line of a [`Do`] block.
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stmt {
+    /// `name <- expr` runs `expr` and binds its result for the lines below.
+    Bind(String, Expr, Span),
+    Expr(Expr),
+}
+
+impl Stmt {
+    pub fn expr(&self) -> &Expr {
+        match self {
+            Self::Bind(_, expr, ..) | Self::Expr(expr) => expr,
+        }
+    }
+}
+
+/// IO actions run top to bottom, the last line is the value of the block.
+/// ```hs
+/// main = do {
+///   print "name? ";
+///   name <- read_line;
+///   print name
+/// }
+/// ```
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Do {
+    pub stmts: Vec<Stmt>,
+    pub pos: TokenPosition,
+    pub span: Span,
+}
+
+/// `xs[i]`, or the slice `xs[i..j]` from `i` up to but not including `j`.
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Index {
+    pub expr: Box<Expr>,
+    pub index: Box<Expr>,
+    pub end: Option<Box<Expr>>,
+    pub pos: TokenPosition,
+    pub span: Span,
+}
+
+/// An interpolated string, `"cell {x} is {show c}"`. The string parts are
+/// `Atom::String`s and the holes any expression, each one is turned into a
+/// string and the results joined in order.
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Concat {
+    pub parts: Vec<Expr>,
+    pub pos: TokenPosition,
+    pub span: Span,
+}
+
+/// `type Grid = Array<Bool>`
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alias {
+    pub name: String,
+    pub params: Vec<String>,
+    pub type_info: TypeInfo,
+    pub span: Span,
+}
+
+impl Alias {
+    /// The aliased type with `args` put in place of the alias parameters.
+    pub fn apply(&self, args: &[TypeInfo]) -> TypeInfo {
+        let params = self
+            .params
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect();
+        self.type_info.substitute(&params)
+    }
+}
+
+/// `data Tree a = Leaf | Node (Tree a) a (Tree a)`
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Data {
+    pub name: String,
+    pub params: Vec<String>,
+    pub variants: Vec<Variant>,
+    pub span: Span,
+}
+
+/// One constructor of a [`Data`] declaration.
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<TypeInfo>,
+    pub span: Span,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for field in self.fields.iter() {
+            match field {
+                TypeInfo::Apply(..) | TypeInfo::Func(..) => write!(f, " ({field})")?,
+                _ => write!(f, " {field}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A set of functions a type can implement, operators are written in terms
+/// of them.
+/// ```hs
+/// class Eq a { eq : a -> a -> Bool }
+/// ```
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Class {
+    pub name: String,
+    /// The type variable that stands for the instance in the method
+    /// signatures.
+    pub param: String,
+    pub methods: Vec<Method>,
+    pub span: Span,
+}
+
+/// The signature of one function of a [`Class`].
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Method {
+    pub name: String,
+    pub type_info: Vec<TypeInfo>,
+    pub span: Span,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} : ", self.name)?;
+        write_arrows(f, &self.type_info)
+    }
+}
+
+/// The methods of a [`Class`] for one `data` type, each one an `Expr::Func`.
+/// ```hs
+/// instance Eq Color {
+///   eq Red Red = true;
+///   eq _ _ = false
+/// }
+/// ```
+#[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instance {
+    pub class: String,
+    pub type_info: TypeInfo,
+    pub methods: Vec<Expr>,
+    pub span: Span,
+}
+
+impl Instance {
+    /// The `data` type the instance is for, `Tree` in `instance Show (Tree a)`.
+    pub fn type_name(&self) -> Option<&str> {
+        match &self.type_info {
+            TypeInfo::Custom(name) | TypeInfo::Apply(name, ..) => Some(name),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeInfo {
     Int,
     Float,
@@ -106,6 +371,77 @@ pub enum TypeInfo {
     Char,
     Array(Box<Self>),
     Custom(String),
+    /// A lower case type parameter like the `a` in `Tree a`.
+    Var(String),
+    /// A type constructor given arguments, `Tree a`.
+    Apply(String, Vec<Self>),
+    Func(Box<Self>, Box<Self>),
+}
+
+impl TypeInfo {
+    /// Replaces type parameters by name.
+    pub fn substitute(&self, params: &HashMap<String, TypeInfo>) -> Self {
+        match self {
+            Self::Var(name) => params.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Self::Array(inner) => Self::Array(Box::new(inner.substitute(params))),
+            Self::Apply(name, args) => Self::Apply(
+                name.clone(),
+                args.iter().map(|arg| arg.substitute(params)).collect(),
+            ),
+            Self::Func(lhs, rhs) => Self::Func(
+                Box::new(lhs.substitute(params)),
+                Box::new(rhs.substitute(params)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// Rewrites every use of an alias into the type it stands for. An alias
+    /// that refers back to itself is left as is.
+    pub fn expand_aliases(&self, aliases: &HashMap<String, Alias>) -> Self {
+        self.expand_aliases_with(aliases, &mut Vec::new())
+    }
+
+    fn expand_aliases_with<'a>(
+        &self,
+        aliases: &'a HashMap<String, Alias>,
+        expanding: &mut Vec<&'a str>,
+    ) -> Self {
+        let (name, args) = match self {
+            Self::Custom(name) => (name, vec![]),
+            Self::Apply(name, args) => (
+                name,
+                args.iter()
+                    .map(|arg| arg.expand_aliases_with(aliases, expanding))
+                    .collect(),
+            ),
+            Self::Array(inner) => {
+                return Self::Array(Box::new(
+                    inner.expand_aliases_with(aliases, expanding),
+                ))
+            }
+            Self::Func(lhs, rhs) => {
+                return Self::Func(
+                    Box::new(lhs.expand_aliases_with(aliases, expanding)),
+                    Box::new(rhs.expand_aliases_with(aliases, expanding)),
+                )
+            }
+            _ => return self.clone(),
+        };
+        let Some((key, alias)) = aliases.get_key_value(name) else {
+            return match self {
+                Self::Apply(name, _) => Self::Apply(name.clone(), args),
+                _ => self.clone(),
+            };
+        };
+        if expanding.contains(&key.as_str()) {
+            return self.clone();
+        }
+        expanding.push(key);
+        let expanded = alias.apply(&args).expand_aliases_with(aliases, expanding);
+        expanding.pop();
+        expanded
+    }
 }
 
 impl From<Ident> for TypeInfo {
@@ -116,6 +452,9 @@ impl From<Ident> for TypeInfo {
             "Bool" => Self::Bool,
             "String" => Self::String,
             "Char" => Self::Char,
+            name if name.starts_with(|c: char| c.is_lowercase()) => {
+                Self::Var(name.to_string())
+            }
             name => Self::Custom(name.to_string()),
         }
     }
@@ -131,22 +470,45 @@ impl fmt::Display for TypeInfo {
             Self::Char => write!(f, "Char"),
             Self::Array(type_info) => write!(f, "Array<{type_info}>"),
             Self::Custom(name) => write!(f, "{name}"),
+            Self::Var(name) => write!(f, "{name}"),
+            Self::Apply(name, args) => {
+                write!(f, "{name}")?;
+                for arg in args.iter() {
+                    match arg {
+                        Self::Apply(..) | Self::Func(..) => write!(f, " ({arg})")?,
+                        _ => write!(f, " {arg}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Func(lhs, rhs) => match lhs.as_ref() {
+                Self::Func(..) => write!(f, "({lhs}) -> {rhs}"),
+                _ => write!(f, "{lhs} -> {rhs}"),
+            },
         }
     }
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     // App(Box<Self>, Vec<Self>, Span),
+    Alias(Alias),
     App(App),
     Array(Vec<Self>, TokenPosition, Span),
     Atom(Atom),
     Binary(Binary),
+    Class(Class),
     Closure(Box<Self>, Box<Self>, Span),
-    Enum(String, Vec<(String, Vec<String>)>, Span),
+    Concat(Concat),
+    Data(Data),
+    Do(Do),
     Error(Span),
     Func(String, Vec<TypeInfo>, Box<Self>, Span),
     IfElse(Box<Self>, Box<Self>, Box<Self>, Span),
+    Index(Index),
+    Instance(Instance),
+    Match(Match),
     Unary(Unary),
 }
 
@@ -202,7 +564,34 @@ impl Expr {
                 span,
             }),
             Self::Array(array, pos, span) => Self::Array(array, f(pos), span),
-            Self::Enum(name, variants, span) => Self::Enum(name, variants, span),
+            Self::Alias(alias) => Self::Alias(alias),
+            Self::Data(data) => Self::Data(data),
+            Self::Class(class) => Self::Class(class),
+            Self::Instance(instance) => Self::Instance(instance),
+            Self::Match(Match {
+                exprs,
+                arms,
+                pos,
+                span,
+            }) => Self::Match(Match {
+                exprs,
+                arms,
+                pos: f(pos),
+                span,
+            }),
+            Self::Index(index) => Self::Index(Index {
+                pos: f(index.pos),
+                ..index
+            }),
+            Self::Do(Do { stmts, pos, span }) => Self::Do(Do {
+                stmts,
+                pos: f(pos),
+                span,
+            }),
+            Self::Concat(concat) => Self::Concat(Concat {
+                pos: f(concat.pos),
+                ..concat
+            }),
             Self::Error(span) => Self::Error(span),
         }
     }
@@ -217,7 +606,14 @@ impl Expr {
             Self::Func(.., span) => *span,
             Self::App(app) => app.span,
             Self::Array(.., span) => *span,
-            Self::Enum(.., span) => *span,
+            Self::Alias(alias) => alias.span,
+            Self::Data(data) => data.span,
+            Self::Class(class) => class.span,
+            Self::Instance(instance) => instance.span,
+            Self::Match(m) => m.span,
+            Self::Do(d) => d.span,
+            Self::Index(index) => index.span,
+            Self::Concat(concat) => concat.span,
             Self::Error(span) => *span,
         }
     }
@@ -228,8 +624,15 @@ impl Expr {
     is_expr!(is_clouser, Closure);
     is_expr!(is_func, Func);
     is_expr!(is_app, App);
-    is_expr!(is_type, Enum);
+    is_expr!(is_alias, Alias);
+    is_expr!(is_data, Data);
+    is_expr!(is_class, Class);
+    is_expr!(is_instance, Instance);
     is_expr!(is_array, Array);
+    is_expr!(is_match, Match);
+    is_expr!(is_do, Do);
+    is_expr!(is_index, Index);
+    is_expr!(is_concat, Concat);
 
     pub fn is_error(&self) -> bool {
         match self {
@@ -240,6 +643,16 @@ impl Expr {
             Self::Func(_, _, e, ..) => e.is_error(),
             Self::App(app) => app.name.is_error(),
             Self::Array(array, ..) => array.iter().any(|e| e.is_error()),
+            Self::Match(m) => m.arms.iter().any(|arm| {
+                arm.body.is_error() || arm.guard.as_ref().is_some_and(Expr::is_error)
+            }),
+            Self::Do(d) => d.stmts.iter().any(|stmt| stmt.expr().is_error()),
+            Self::Index(index) => {
+                index.expr.is_error()
+                    || index.index.is_error()
+                    || index.end.as_ref().is_some_and(|end| end.is_error())
+            }
+            Self::Concat(concat) => concat.parts.iter().any(Expr::is_error),
             Self::Error(..) => true,
             _ => false,
         }
@@ -275,6 +688,10 @@ impl Expr {
             Self::Closure(_, tail, ..) => tail.position(),
             Self::Array(_, pos, ..) => *pos,
             Self::IfElse(_, _, r#else, ..) => r#else.position(),
+            Self::Match(m) => m.pos,
+            Self::Do(d) => d.pos,
+            Self::Index(index) => index.pos,
+            Self::Concat(concat) => concat.pos,
             _ => unimplemented!("for {self:?}"),
         }
     }
@@ -326,34 +743,120 @@ impl fmt::Display for Expr {
                 a += "]";
                 write!(f, "{a}")
             }
-            Self::Enum(name, args, ..) => {
-                if args.is_empty() {
-                    return write!(f, "<{name}>");
+            Self::Alias(alias) => {
+                write!(f, "<type {}", alias.name)?;
+                for param in alias.params.iter() {
+                    write!(f, " {param}")?;
+                }
+                write!(f, " = {}>", alias.type_info)
+            }
+            Self::Data(data) => {
+                write!(f, "<data {}", data.name)?;
+                for param in data.params.iter() {
+                    write!(f, " {param}")?;
+                }
+                write!(f, " = ")?;
+                for (i, variant) in data.variants.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{variant}")?;
+                }
+                write!(f, ">")
+            }
+            Self::Class(class) => {
+                write!(f, "<class {} {} {{ ", class.name, class.param)?;
+                write_separated(f, &class.methods, "; ", |f, m| write!(f, "{m}"))?;
+                write!(f, " }}>")
+            }
+            Self::Instance(instance) => {
+                write!(f, "<instance {} ", instance.class)?;
+                match &instance.type_info {
+                    TypeInfo::Apply(..) => write!(f, "({})", instance.type_info)?,
+                    type_info => write!(f, "{type_info}")?,
                 }
-                let fstring = args.iter().enumerate().fold(
-                    format!("<{name}: "),
-                    |fstring, (i, (name, type_arg))| {
-                        let targs = type_arg.iter().fold("".to_string(), |fstring, t| {
-                            if fstring.is_empty() {
-                                t.to_string()
-                            } else {
-                                format!("{fstring}, {t}")
-                            }
-                        });
-                        if i < args.len() - 1 {
-                            format!("{fstring}({name}, [{targs}]), ")
-                        } else {
-                            format!("{fstring}({name}, [{targs}])")
+                write!(f, " {{ ")?;
+                write_separated(f, &instance.methods, "; ", |f, m| write!(f, "{m}"))?;
+                write!(f, " }}>")
+            }
+            Self::Match(m) => {
+                write!(f, "(match ")?;
+                write_comma_separated(f, &m.exprs, |f, e| write!(f, "{e}"))?;
+                for arm in m.arms.iter() {
+                    write!(f, " | ")?;
+                    write_comma_separated(f, &arm.pats, |f, p| write!(f, "{p}"))?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {guard}")?;
+                    }
+                    write!(f, " -> {}", arm.body)?;
+                }
+                write!(f, ")")
+            }
+            Self::Index(index) => match &index.end {
+                Some(end) => write!(f, "{}[{}..{end}]", index.expr, index.index),
+                None => write!(f, "{}[{}]", index.expr, index.index),
+            },
+            Self::Do(d) => {
+                write!(f, "(do {{ ")?;
+                for (i, stmt) in d.stmts.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "; ")?;
+                    }
+                    match stmt {
+                        Stmt::Bind(name, expr, ..) => write!(f, "{name} <- {expr}")?,
+                        Stmt::Expr(expr) => write!(f, "{expr}")?,
+                    }
+                }
+                write!(f, " }})")
+            }
+            Self::Concat(concat) => {
+                write!(f, "\"")?;
+                for part in concat.parts.iter() {
+                    match part {
+                        Self::Atom(Atom::String(s, ..)) => {
+                            write!(f, "{}", s.replace('{', "\\{"))?
                         }
-                    },
-                );
-                write!(f, "{fstring}>")
+                        part => write!(f, "{{{part}}}")?,
+                    }
+                }
+                write!(f, "\"")
             }
             Self::Error(..) => write!(f, "Error"),
         }
     }
 }
 
+fn write_comma_separated<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    write_item: impl Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    write_separated(f, items, ", ", write_item)
+}
+
+fn write_separated<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    separator: &str,
+    write_item: impl Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i != 0 {
+            write!(f, "{separator}")?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
+
+/// `Int -> Array<a> -> a` from the parts a signature is kept as.
+fn write_arrows(f: &mut fmt::Formatter<'_>, type_info: &[TypeInfo]) -> fmt::Result {
+    write_separated(f, type_info, " -> ", |f, t| match t {
+        TypeInfo::Func(..) => write!(f, "({t})"),
+        _ => write!(f, "{t}"),
+    })
+}
+
 impl fmt::Debug for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -400,28 +903,48 @@ impl fmt::Debug for Expr {
                 a += "]";
                 write!(f, "{a}")
             }
-            Self::Enum(name, args, ..) => {
-                if args.is_empty() {
-                    return write!(f, "<{name:?}>");
+            Self::Alias(..) | Self::Data(..) | Self::Class(..) | Self::Instance(..) => {
+                write!(f, "{self}")
+            }
+            Self::Match(m) => {
+                write!(f, "(match ")?;
+                write_comma_separated(f, &m.exprs, |f, e| write!(f, "{e:?}"))?;
+                for arm in m.arms.iter() {
+                    write!(f, " | ")?;
+                    write_comma_separated(f, &arm.pats, |f, p| write!(f, "{p:?}"))?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {guard:?}")?;
+                    }
+                    write!(f, " -> {:?}", arm.body)?;
                 }
-                let fstring = args.iter().enumerate().fold(
-                    format!("<{name:?}: "),
-                    |fstring, (i, (name, type_arg))| {
-                        let targs = type_arg.iter().fold(String::new(), |fstring, t| {
-                            if fstring.is_empty() {
-                                t.to_string()
-                            } else {
-                                format!("{fstring}, {t}")
-                            }
-                        });
-                        if i < args.len() - 1 {
-                            format!("{fstring:?}({name:?}, [{targs:?}]), ")
-                        } else {
-                            format!("{fstring:?}({name:?}, [{targs:?}])")
-                        }
-                    },
-                );
-                write!(f, "{fstring}>")
+                write!(f, ")")
+            }
+            Self::Index(index) => match &index.end {
+                Some(end) => write!(f, "{:?}[{:?}..{end:?}]", index.expr, index.index),
+                None => write!(f, "{:?}[{:?}]", index.expr, index.index),
+            },
+            Self::Do(d) => {
+                write!(f, "(do {{ ")?;
+                for (i, stmt) in d.stmts.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "; ")?;
+                    }
+                    match stmt {
+                        Stmt::Bind(name, expr, ..) => write!(f, "{name:?} <- {expr:?}")?,
+                        Stmt::Expr(expr) => write!(f, "{expr:?}")?,
+                    }
+                }
+                write!(f, " }})")
+            }
+            Self::Concat(concat) => {
+                write!(f, "\"")?;
+                for part in concat.parts.iter() {
+                    match part {
+                        Self::Atom(Atom::String(s, ..)) => write!(f, "{s}")?,
+                        part => write!(f, "{{{part:?}}}")?,
+                    }
+                }
+                write!(f, "\"")
             }
             Self::Error(..) => write!(f, "Error"),
         }
@@ -0,0 +1,278 @@
+//! Owned rewriting of the AST.
+//!
+//! A [`Fold`] takes each node by value and hands back its replacement, which
+//! makes it the natural fit for desugaring passes that turn one kind of
+//! `Expr` into another.
+use super::{
+    Alias, App, Arm, Atom, Binary, Class, Concat, Data, Do, Expr, Index, Instance, Match,
+    Pat, Span, Stmt, TokenPosition, TypeInfo, Unary,
+};
+
+pub trait Fold: Sized {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+
+    fn fold_atom(&mut self, atom: Atom) -> Expr {
+        Expr::Atom(atom)
+    }
+
+    fn fold_unary(&mut self, unary: Unary) -> Expr {
+        Expr::Unary(walk_unary(self, unary))
+    }
+
+    fn fold_binary(&mut self, binary: Binary) -> Expr {
+        Expr::Binary(walk_binary(self, binary))
+    }
+
+    fn fold_app(&mut self, app: App) -> Expr {
+        Expr::App(walk_app(self, app))
+    }
+
+    fn fold_array(&mut self, array: Vec<Expr>, pos: TokenPosition, span: Span) -> Expr {
+        Expr::Array(walk_array(self, array), pos, span)
+    }
+
+    fn fold_closure(&mut self, head: Expr, tail: Expr, span: Span) -> Expr {
+        let (head, tail) = walk_closure(self, head, tail);
+        Expr::Closure(Box::new(head), Box::new(tail), span)
+    }
+
+    fn fold_if_else(
+        &mut self,
+        condition: Expr,
+        then: Expr,
+        r#else: Expr,
+        span: Span,
+    ) -> Expr {
+        let (condition, then, r#else) = walk_if_else(self, condition, then, r#else);
+        Expr::IfElse(Box::new(condition), Box::new(then), Box::new(r#else), span)
+    }
+
+    fn fold_func(
+        &mut self,
+        name: String,
+        type_info: Vec<TypeInfo>,
+        body: Expr,
+        span: Span,
+    ) -> Expr {
+        let body = walk_func(self, body);
+        Expr::Func(name, type_info, Box::new(body), span)
+    }
+
+    fn fold_alias(&mut self, alias: Alias) -> Expr {
+        Expr::Alias(alias)
+    }
+
+    fn fold_data(&mut self, data: Data) -> Expr {
+        Expr::Data(data)
+    }
+
+    fn fold_class(&mut self, class: Class) -> Expr {
+        Expr::Class(class)
+    }
+
+    fn fold_instance(&mut self, instance: Instance) -> Expr {
+        Expr::Instance(walk_instance(self, instance))
+    }
+
+    fn fold_match(&mut self, m: Match) -> Expr {
+        Expr::Match(walk_match(self, m))
+    }
+
+    fn fold_arm(&mut self, arm: Arm) -> Arm {
+        walk_arm(self, arm)
+    }
+
+    fn fold_pat(&mut self, pat: Pat) -> Pat {
+        walk_pat(self, pat)
+    }
+
+    fn fold_index(&mut self, index: Index) -> Expr {
+        Expr::Index(walk_index(self, index))
+    }
+
+    fn fold_concat(&mut self, concat: Concat) -> Expr {
+        Expr::Concat(walk_concat(self, concat))
+    }
+
+    fn fold_do(&mut self, d: Do) -> Expr {
+        Expr::Do(walk_do(self, d))
+    }
+
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        walk_stmt(self, stmt)
+    }
+
+    fn fold_error(&mut self, span: Span) -> Expr {
+        Expr::Error(span)
+    }
+}
+
+pub fn walk_expr<F: Fold>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Atom(atom) => folder.fold_atom(atom),
+        Expr::Unary(unary) => folder.fold_unary(unary),
+        Expr::Binary(binary) => folder.fold_binary(binary),
+        Expr::App(app) => folder.fold_app(app),
+        Expr::Array(array, pos, span) => folder.fold_array(array, pos, span),
+        Expr::Closure(head, tail, span) => folder.fold_closure(*head, *tail, span),
+        Expr::IfElse(condition, then, r#else, span) => {
+            folder.fold_if_else(*condition, *then, *r#else, span)
+        }
+        Expr::Func(name, type_info, body, span) => {
+            folder.fold_func(name, type_info, *body, span)
+        }
+        Expr::Alias(alias) => folder.fold_alias(alias),
+        Expr::Data(data) => folder.fold_data(data),
+        Expr::Class(class) => folder.fold_class(class),
+        Expr::Instance(instance) => folder.fold_instance(instance),
+        Expr::Match(m) => folder.fold_match(m),
+        Expr::Do(d) => folder.fold_do(d),
+        Expr::Index(index) => folder.fold_index(index),
+        Expr::Concat(concat) => folder.fold_concat(concat),
+        Expr::Error(span) => folder.fold_error(span),
+    }
+}
+
+pub fn walk_unary<F: Fold>(folder: &mut F, unary: Unary) -> Unary {
+    Unary {
+        expr: Box::new(folder.fold_expr(*unary.expr)),
+        ..unary
+    }
+}
+
+pub fn walk_binary<F: Fold>(folder: &mut F, binary: Binary) -> Binary {
+    Binary {
+        left: Box::new(folder.fold_expr(*binary.left)),
+        right: Box::new(folder.fold_expr(*binary.right)),
+        ..binary
+    }
+}
+
+pub fn walk_app<F: Fold>(folder: &mut F, app: App) -> App {
+    App {
+        name: Box::new(folder.fold_expr(*app.name)),
+        args: app
+            .args
+            .into_iter()
+            .map(|arg| folder.fold_expr(arg))
+            .collect(),
+        ..app
+    }
+}
+
+pub fn walk_array<F: Fold>(folder: &mut F, array: Vec<Expr>) -> Vec<Expr> {
+    array
+        .into_iter()
+        .map(|item| folder.fold_expr(item))
+        .collect()
+}
+
+pub fn walk_closure<F: Fold>(folder: &mut F, head: Expr, tail: Expr) -> (Expr, Expr) {
+    (folder.fold_expr(head), folder.fold_expr(tail))
+}
+
+pub fn walk_if_else<F: Fold>(
+    folder: &mut F,
+    condition: Expr,
+    then: Expr,
+    r#else: Expr,
+) -> (Expr, Expr, Expr) {
+    (
+        folder.fold_expr(condition),
+        folder.fold_expr(then),
+        folder.fold_expr(r#else),
+    )
+}
+
+pub fn walk_func<F: Fold>(folder: &mut F, body: Expr) -> Expr {
+    folder.fold_expr(body)
+}
+
+pub fn walk_instance<F: Fold>(folder: &mut F, instance: Instance) -> Instance {
+    Instance {
+        methods: instance
+            .methods
+            .into_iter()
+            .map(|method| folder.fold_expr(method))
+            .collect(),
+        ..instance
+    }
+}
+
+pub fn walk_match<F: Fold>(folder: &mut F, m: Match) -> Match {
+    Match {
+        exprs: m
+            .exprs
+            .into_iter()
+            .map(|expr| folder.fold_expr(expr))
+            .collect(),
+        arms: m.arms.into_iter().map(|arm| folder.fold_arm(arm)).collect(),
+        ..m
+    }
+}
+
+pub fn walk_arm<F: Fold>(folder: &mut F, arm: Arm) -> Arm {
+    Arm {
+        pats: arm
+            .pats
+            .into_iter()
+            .map(|pat| folder.fold_pat(pat))
+            .collect(),
+        guard: arm.guard.map(|guard| folder.fold_expr(guard)),
+        body: folder.fold_expr(arm.body),
+        ..arm
+    }
+}
+
+/// Folds the fields of a constructor pattern, the other patterns have no
+/// patterns inside them.
+pub fn walk_pat<F: Fold>(folder: &mut F, pat: Pat) -> Pat {
+    match pat {
+        Pat::Constructor(name, args, span) => Pat::Constructor(
+            name,
+            args.into_iter().map(|arg| folder.fold_pat(arg)).collect(),
+            span,
+        ),
+        pat => pat,
+    }
+}
+
+pub fn walk_index<F: Fold>(folder: &mut F, index: Index) -> Index {
+    Index {
+        expr: Box::new(folder.fold_expr(*index.expr)),
+        index: Box::new(folder.fold_expr(*index.index)),
+        end: index.end.map(|end| Box::new(folder.fold_expr(*end))),
+        ..index
+    }
+}
+
+pub fn walk_concat<F: Fold>(folder: &mut F, concat: Concat) -> Concat {
+    Concat {
+        parts: concat
+            .parts
+            .into_iter()
+            .map(|part| folder.fold_expr(part))
+            .collect(),
+        ..concat
+    }
+}
+
+pub fn walk_do<F: Fold>(folder: &mut F, d: Do) -> Do {
+    Do {
+        stmts: d
+            .stmts
+            .into_iter()
+            .map(|stmt| folder.fold_stmt(stmt))
+            .collect(),
+        ..d
+    }
+}
+
+pub fn walk_stmt<F: Fold>(folder: &mut F, stmt: Stmt) -> Stmt {
+    match stmt {
+        Stmt::Bind(name, expr, span) => Stmt::Bind(name, folder.fold_expr(expr), span),
+        Stmt::Expr(expr) => Stmt::Expr(folder.fold_expr(expr)),
+    }
+}
@@ -1,8 +1,20 @@
+pub mod builtin;
 pub mod error;
 pub mod expr;
+pub mod fold;
 pub mod op;
 pub mod parser;
-pub use expr::{App, Atom, Binary, Expr, TypeInfo, Unary};
+pub mod validate;
+pub mod visit;
+pub mod visit_mut;
+pub use expr::{
+    Alias, App, Arm, Atom, Binary, Class, Concat, Data, Do, Expr, Index, Instance, Match,
+    Method, Pat, Stmt, TypeInfo, Unary, Variant,
+};
+pub use builtin::{Builtin, Extern};
+pub use fold::Fold;
+pub use visit::Visitor;
+pub use visit_mut::VisitorMut;
 pub use op::Op;
 
 #[cfg(test)]
@@ -10,7 +22,8 @@ mod tests;
 pub use snowc_lexer::{Ident, Scanner, Span, Token, TokenPosition};
 
 use error::Error;
-pub use parser::parse;
+pub use parser::{parse, parse_file};
+pub use validate::{validate, validate_with};
 type Result<T> = std::result::Result<T, Error>;
 type ParserResult = std::result::Result<Vec<Expr>, Vec<Error>>;
 
@@ -18,3 +31,14 @@ pub fn expression(src: &str) -> Result<Expr> {
     let mut tokens: Vec<Token> = Scanner::new(src).collect();
     parser::expression(&mut tokens)
 }
+
+/// The `--# name` lines at the top of a module, above anything that isn't a
+/// comment. `--# lazy` asks for lazy evaluation.
+pub fn pragmas(src: &str) -> Vec<&str> {
+    src.lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with("--"))
+        .filter_map(|line| line.strip_prefix("--#"))
+        .map(str::trim)
+        .collect()
+}
@@ -1,20 +1,29 @@
 use crate::TypeInfo;
 
 use super::error::Error;
-use super::expr::{App, Atom, Binary, Expr, Unary};
+use super::expr::{
+    Alias, App, Arm, Atom, Binary, Class, Concat, Data, Do, Expr, Index, Instance, Match,
+    Method, Pat, Stmt, Unary, Variant,
+};
 use super::op::Op as Oper;
 use super::op::Op::*;
 use super::{ParserResult, Result};
 use snowc_lexer::{Ctrl, Ident, KeyWord, Op, Scanner, Span, Token, TokenPosition};
 
 pub fn parse(src: &str) -> ParserResult {
-    let mut tokens: Vec<Token> = Scanner::new(src).collect();
-    let mut ast = Vec::new();
+    parse_file(src, 0)
+}
+
+/// Parses `src` as source number `file`, so spans from several sources can
+/// be told apart.
+pub fn parse_file(src: &str, file: usize) -> ParserResult {
+    let mut tokens: Vec<Token> = Scanner::in_file(src, file).collect();
+    let mut items: Vec<Item> = Vec::new();
     let mut errors = Vec::new();
     while !tokens.is_empty() {
-        match function(&mut tokens) {
-            Ok(func) => {
-                ast.push(func);
+        match item(&mut tokens) {
+            Ok(item) => {
+                items.push(item);
             }
 
             Err(error) => {
@@ -37,28 +46,412 @@ pub fn parse(src: &str) -> ParserResult {
     if !errors.is_empty() {
         return Err(errors);
     }
+    let mut ast = Vec::new();
+    for item in group_clauses(items) {
+        match item {
+            Group::Clauses(group) => match function(group) {
+                Ok(func) => ast.push(func),
+                Err(error) => errors.push(error),
+            },
+            Group::Decl(decl) => ast.push(decl),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
     Ok(ast)
 }
 
+/// Anything allowed at the top level of a file.
+enum Item {
+    Clause(Clause),
+    Decl(Expr),
+}
+
+enum Group {
+    Clauses(Vec<Clause>),
+    Decl(Expr),
+}
+
+fn item(tokens: &mut Vec<Token>) -> Result<Item> {
+    match tokens.first() {
+        Some(Token::KeyWord(kw)) if kw.lexme == "type" => alias(tokens).map(Item::Decl),
+        Some(Token::KeyWord(kw)) if kw.lexme == "data" => data(tokens).map(Item::Decl),
+        Some(Token::KeyWord(kw)) if kw.lexme == "class" => class(tokens).map(Item::Decl),
+        Some(Token::KeyWord(kw)) if kw.lexme == "instance" => {
+            instance(tokens).map(Item::Decl)
+        }
+        _ => clause(tokens).map(Item::Clause),
+    }
+}
+
+/// `type Name params = type`
+fn alias(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let start = consume_keyword(tokens, "type")?.span();
+    let (name, params) = type_head(tokens)?;
+    let (type_info, end) = type_arrow(tokens)?;
+    Ok(Expr::Alias(Alias {
+        name,
+        params,
+        type_info,
+        span: Span::from((start, end.span())),
+    }))
+}
+
+/// `data Name params = Constructor field* (| Constructor field*)*`
+fn data(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let start = consume_keyword(tokens, "data")?.span();
+    let (name, params) = type_head(tokens)?;
+    let mut variants = Vec::new();
+    loop {
+        let Some(Token::Ident(ident)) = tokens.first().cloned() else {
+            let span = tokens.first().map(|t| t.span()).unwrap_or(start);
+            return Err(Error::ExpectedConstructor(span));
+        };
+        if !ident.lexme.starts_with(|c: char| c.is_uppercase()) {
+            return Err(Error::ExpectedConstructor(ident.span));
+        }
+        let mut last = tokens.remove(0);
+        let mut fields = Vec::new();
+        while is_type_atom(tokens.first()) && !ends_item(&last, tokens.first()) {
+            let (field, end) = type_atom(tokens)?;
+            fields.push(field);
+            last = end;
+        }
+        variants.push(Variant {
+            name: ident.lexme,
+            fields,
+            span: Span::from((ident.span, last.span())),
+        });
+        if consume_op_if(tokens, "|").is_none() {
+            break;
+        }
+    }
+    let end = variants.last().map(|v| v.span).unwrap_or(start);
+    Ok(Expr::Data(Data {
+        name,
+        params,
+        variants,
+        span: Span::from((start, end)),
+    }))
+}
+
+/// `class Name param { method : type (; method : type)* }`
+fn class(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let start = consume_keyword(tokens, "class")?.span();
+    let name = type_name(tokens)?;
+    let param = type_name(tokens)?;
+    consume_ctrl(tokens, "{")?;
+    let mut methods = Vec::new();
+    let end = loop {
+        let Some(Token::Ident(Ident { lexme, span, .. })) = tokens.first().cloned() else {
+            let span = tokens.first().map(|t| t.span()).unwrap_or(start);
+            return Err(Error::NotAFunction(span));
+        };
+        tokens.remove(0);
+        consume_ctrl(tokens, ":")?;
+        let type_info = type_signature(tokens)?;
+        methods.push(Method {
+            name: lexme,
+            type_info,
+            span,
+        });
+        if let Some(end) = end_of_entry(tokens)? {
+            break end;
+        }
+    };
+    Ok(Expr::Class(Class {
+        name,
+        param,
+        methods,
+        span: Span::from((start, end.span())),
+    }))
+}
+
+/// `instance Class type { clause (; clause)* }`, clauses that share a name
+/// are one method like they are at the top level.
+fn instance(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let start = consume_keyword(tokens, "instance")?.span();
+    let class = type_name(tokens)?;
+    let (type_info, _) = type_app(tokens)?;
+    consume_ctrl(tokens, "{")?;
+    let mut clauses = Vec::new();
+    let end = loop {
+        clauses.push(Item::Clause(clause(tokens)?));
+        if let Some(end) = end_of_entry(tokens)? {
+            break end;
+        }
+    };
+    let mut methods = Vec::new();
+    for group in group_clauses(clauses) {
+        if let Group::Clauses(group) = group {
+            methods.push(function(group)?);
+        }
+    }
+    Ok(Expr::Instance(Instance {
+        class,
+        type_info,
+        methods,
+        span: Span::from((start, end.span())),
+    }))
+}
+
+/// Finishes one entry of a `{ a; b }` block and hands back the closing brace
+/// once there are no entries left.
+fn end_of_entry(tokens: &mut Vec<Token>) -> Result<Option<Token>> {
+    let ended = consume_ctrl_if(tokens, ";").is_some();
+    if let Some(end) = consume_ctrl_if(tokens, "}") {
+        return Ok(Some(end));
+    }
+    if !ended {
+        consume_ctrl(tokens, ";")?;
+    }
+    Ok(None)
+}
+
+fn type_name(tokens: &mut Vec<Token>) -> Result<String> {
+    let Some(Token::Ident(Ident { lexme, .. })) = tokens.first().cloned() else {
+        let span = tokens.first().map(|t| t.span()).unwrap_or_default();
+        return Err(Error::ExpectedType(span));
+    };
+    tokens.remove(0);
+    Ok(lexme)
+}
+
+/// The `Name a b =` part of `type` and `data` declarations.
+fn type_head(tokens: &mut Vec<Token>) -> Result<(String, Vec<String>)> {
+    let Some(Token::Ident(Ident { lexme: name, .. })) = tokens.first().cloned() else {
+        let span = tokens.first().map(|t| t.span()).unwrap_or_default();
+        return Err(Error::ExpectedType(span));
+    };
+    tokens.remove(0);
+    let mut params = Vec::new();
+    while let Some(Token::Ident(Ident { lexme, .. })) = tokens.first().cloned() {
+        tokens.remove(0);
+        params.push(lexme);
+    }
+    consume_ctrl(tokens, "=")?;
+    Ok((name, params))
+}
+
+/// True when `next` starts a new top level item rather than continuing the
+/// one `last` belongs to.
+fn ends_item(last: &Token, next: Option<&Token>) -> bool {
+    next.is_none_or(|next| is_deliminator(*last.position(), *next.position()))
+}
+
+fn is_type_atom(token: Option<&Token>) -> bool {
+    match token {
+        Some(Token::Ident(..)) => true,
+        Some(Token::Ctrl(Ctrl { lexme, .. })) => lexme == "(",
+        _ => false,
+    }
+}
+
+/// `Int`, `a`, `Array<type>` or `(type)`. Also hands back the last token it
+/// consumed so callers can tell where the type ends.
+fn type_atom(tokens: &mut Vec<Token>) -> Result<(TypeInfo, Token)> {
+    let Some(token) = tokens.first().cloned() else {
+        return Err(Error::UnexpectedEndOfInput(Span::default()));
+    };
+    match token {
+        Token::Ident(ident)
+            if ident.lexme == "Array"
+                && matches!(tokens.get(1), Some(Token::Op(Op { lexme, .. })) if lexme == "<") =>
+        {
+            tokens.drain(..2);
+            let (inner, _) = type_arrow(tokens)?;
+            let end = consume_op(tokens, ">")?;
+            Ok((TypeInfo::Array(Box::new(inner)), end))
+        }
+        Token::Ident(ident) => Ok((TypeInfo::from(ident), tokens.remove(0))),
+        Token::Ctrl(Ctrl { lexme, span, .. }) if lexme == "(" => {
+            tokens.remove(0);
+            let (type_info, _) = type_arrow(tokens)?;
+            let Some(end) = consume_ctrl_if(tokens, ")") else {
+                return Err(Error::UnclosedParen(span));
+            };
+            Ok((type_info, end))
+        }
+        token => Err(Error::ExpectedType(token.span())),
+    }
+}
+
+/// A type constructor with its arguments, `Tree a`, or a single [`type_atom`].
+fn type_app(tokens: &mut Vec<Token>) -> Result<(TypeInfo, Token)> {
+    let (head, mut last) = type_atom(tokens)?;
+    let TypeInfo::Custom(name) = head else {
+        return Ok((head, last));
+    };
+    let mut args = Vec::new();
+    while is_type_atom(tokens.first()) && !ends_item(&last, tokens.first()) {
+        let (arg, end) = type_atom(tokens)?;
+        args.push(arg);
+        last = end;
+    }
+    if args.is_empty() {
+        return Ok((TypeInfo::Custom(name), last));
+    }
+    Ok((TypeInfo::Apply(name, args), last))
+}
+
+/// `type (-> type)*`, the arrows nest to the right.
+fn type_arrow(tokens: &mut Vec<Token>) -> Result<(TypeInfo, Token)> {
+    let (lhs, last) = type_app(tokens)?;
+    if consume_ctrl_if(tokens, "->").is_none() {
+        return Ok((lhs, last));
+    }
+    let (rhs, last) = type_arrow(tokens)?;
+    Ok((TypeInfo::Func(Box::new(lhs), Box::new(rhs)), last))
+}
+
+/// One equation of a function, `name pat* (: type)? (= body | (| guard = body)+)`.
+struct Clause {
+    name: String,
+    pats: Vec<Pat>,
+    type_info: Vec<TypeInfo>,
+    bodies: Vec<(Option<Expr>, Expr)>,
+    span: Span,
+}
+
+impl Clause {
+    fn is_simple(&self) -> bool {
+        self.pats.iter().all(|pat| matches!(pat, Pat::Id(..)))
+            && matches!(self.bodies.as_slice(), [(None, _)])
+    }
+}
+
+fn clause(tokens: &mut Vec<Token>) -> Result<Clause> {
+    let Some(Token::Ident(Ident{lexme: name, span: start, ..})) = tokens.first().cloned() else {
+        let span = tokens.first().map(|t| t.span()).unwrap_or_default();
+        return Err(Error::NotAFunction(span));
+    };
+    tokens.remove(0);
+    let pats = get_function_params(tokens)?;
+    let type_info = get_function_type_info(tokens)?;
+    let mut bodies = Vec::new();
+    while consume_op_if(tokens, "|").is_some() {
+        let guard = expression(tokens)?;
+        consume_ctrl(tokens, "=")?;
+        let body = get_block(tokens)?;
+        bodies.push((Some(guard), body));
+    }
+    if bodies.is_empty() {
+        consume_ctrl(tokens, "=")?;
+        let body = get_block(tokens)?;
+        bodies.push((None, body));
+    }
+    let end = bodies.last().map(|(_, body)| body.span()).unwrap_or(start);
+    let span = Span::from((start, end));
+    Ok(Clause {
+        name,
+        pats,
+        type_info,
+        bodies,
+        span,
+    })
+}
+
+/// Equations that follow each other and share a name belong to one function.
+fn group_clauses(items: Vec<Item>) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    for item in items {
+        let clause = match item {
+            Item::Clause(clause) => clause,
+            Item::Decl(decl) => {
+                groups.push(Group::Decl(decl));
+                continue;
+            }
+        };
+        match groups.last_mut() {
+            Some(Group::Clauses(group)) if group[0].name == clause.name => {
+                group.push(clause)
+            }
+            _ => groups.push(Group::Clauses(vec![clause])),
+        }
+    }
+    groups
+}
+
 /// Functions are just syntax sugar for closures.
 /// ```hs
 /// add x y = x + y
 /// -- is the same as
 /// add = (\x -> (\y -> x + y))
 /// ```
-fn function(tokens: &mut Vec<Token>) -> Result<Expr> {
-    let Some(Token::Ident(Ident{lexme: name, span: start, ..})) = tokens.get(0).cloned() else {
-        let span = tokens.get(0).map(|t| t.span()).unwrap_or_default();
-        return Err(Error::NotAFunction(span));
-    };
-    tokens.remove(0);
-    let args = get_function_args(tokens);
-    let type_info = get_function_type_info(tokens)?;
-    consume_ctrl(tokens, "=")?;
-    let body = get_block(tokens)?;
-    let end = body.span();
-    let closures = create_closures(args, body);
+/// When a function is written as several clauses, or uses patterns or guards,
+/// the closures wrap a single `match` over every argument instead.
+/// ```hs
+/// fact 0 = 1
+/// fact n = n * fact (n - 1)
+/// -- is the same as
+/// fact = (\$0 -> match $0 | 0 -> 1 | n -> n * fact (n - 1))
+/// ```
+fn function(clauses: Vec<Clause>) -> Result<Expr> {
+    let first = &clauses[0];
+    let name = first.name.clone();
+    let start = first.span;
+    let end = clauses.last().map(|c| c.span).unwrap_or(start);
     let span = Span::from((start, end));
+    let mut signed = clauses.iter().filter(|c| !c.type_info.is_empty());
+    let type_info = signed
+        .next()
+        .map(|c| c.type_info.clone())
+        .unwrap_or_default();
+    if let Some(clause) = signed.next() {
+        return Err(Error::SecondSignature(name, clause.span));
+    }
+
+    if clauses.len() == 1 && first.is_simple() {
+        let mut clause = clauses.into_iter().next().expect("one clause");
+        let args = clause
+            .pats
+            .into_iter()
+            .map(|pat| match pat {
+                Pat::Id(name, span) => {
+                    Expr::Atom(Atom::Id(name, TokenPosition::Middle, span))
+                }
+                _ => unreachable!("simple clauses only have names as parameters"),
+            })
+            .collect();
+        let (_, body) = clause.bodies.remove(0);
+        let closures = create_closures(args, body);
+        return Ok(Expr::Func(name, type_info, Box::new(closures), span));
+    }
+
+    let arity = first.pats.len();
+    if let Some(clause) = clauses.iter().find(|c| c.pats.len() != arity) {
+        return Err(Error::ClauseArityMismatch(name, clause.span));
+    }
+
+    let mut arms: Vec<Arm> = Vec::new();
+    for clause in clauses {
+        for (guard, body) in clause.bodies {
+            let arm_start = guard.as_ref().map(|g| g.span()).unwrap_or(clause.span);
+            let arm = Arm {
+                pats: clause.pats.clone(),
+                span: Span::from((arm_start, body.span())),
+                guard,
+                body,
+            };
+            arms.push(arm);
+        }
+    }
+
+    let args = (0..arity)
+        .map(|i| Expr::Atom(Atom::Id(format!("${i}"), TokenPosition::Middle, start)))
+        .collect::<Vec<_>>();
+    let pos = arms
+        .last()
+        .map(|arm| arm.body.position())
+        .unwrap_or_default();
+    let body = Expr::Match(Match {
+        exprs: args.clone(),
+        arms,
+        pos,
+        span,
+    });
+    let closures = create_closures(args, body);
     Ok(Expr::Func(name, type_info, Box::new(closures), span))
 }
 
@@ -86,32 +479,102 @@ fn get_function_args(tokens: &mut Vec<Token>) -> Vec<Expr> {
     args
 }
 
-fn get_function_type_info(tokens: &mut Vec<Token>) -> Result<Vec<TypeInfo>> {
-    let mut types = Vec::new();
-    if consume_ctrl_if(tokens, ":").is_none() {
-        return Ok(types);
+fn get_function_params(tokens: &mut Vec<Token>) -> Result<Vec<Pat>> {
+    let mut pats = Vec::new();
+    while is_pattern(tokens) {
+        pats.push(pattern(tokens)?);
     }
+    Ok(pats)
+}
 
-    while !matches!(tokens.get(0), Some(Token::Ctrl(Ctrl{lexme, ..})) if lexme == "=") {
-        let Some(Token::Ident(ident)) = tokens.get(0).cloned() else {
-            break;
-        };
+fn is_pattern(tokens: &[Token]) -> bool {
+    match tokens {
+        [Token::Ctrl(Ctrl { lexme, .. }), ..] => lexme == "(",
+        [Token::KeyWord(KeyWord { lexme, .. }), ..] => {
+            matches!(lexme.as_str(), "true" | "false")
+        }
+        [Token::Op(Op { lexme, .. }), Token::Int(_), ..] => lexme == "-",
+        [Token::Ident(_) | Token::Int(_) | Token::Str(_) | Token::Char(_), ..] => true,
+        _ => false,
+    }
+}
 
-        if matches!(tokens.get(1), Some(Token::Op(Op{lexme, ..})) if lexme == "<" && ident.lexme == "Array" )
-        {
+/// ```ebnf
+/// pattern ::= "_" | ident | constructor | literal | "-" int
+///           | "(" constructor pattern* ")"
+/// ```
+fn pattern(tokens: &mut Vec<Token>) -> Result<Pat> {
+    match tokens.first().cloned() {
+        Some(Token::Ident(Ident { lexme, span, .. })) => {
             tokens.remove(0);
-            let last = tokens.remove(0);
-            let Some(Token::Ident(ident)) = tokens.get(0).cloned() else {
-                return Err(Error::ExpectedType(last.span()).into());
+            if lexme == "_" {
+                return Ok(Pat::Wildcard(span));
+            }
+            if lexme.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return Ok(Pat::Constructor(lexme, Vec::new(), span));
+            }
+            Ok(Pat::Id(lexme, span))
+        }
+        Some(Token::Ctrl(Ctrl { lexme, span: start, .. })) if lexme == "(" => {
+            tokens.remove(0);
+            let pat = match tokens.first().cloned() {
+                Some(Token::Ident(Ident { lexme, .. }))
+                    if lexme.starts_with(|c: char| c.is_ascii_uppercase()) =>
+                {
+                    tokens.remove(0);
+                    let mut args = Vec::new();
+                    while is_pattern(tokens) {
+                        args.push(pattern(tokens)?);
+                    }
+                    Pat::Constructor(lexme, args, start)
+                }
+                _ => pattern(tokens)?,
+            };
+            let Some(Token::Ctrl(Ctrl { span: end, .. })) = consume_ctrl_if(tokens, ")") else {
+                return Err(Error::UnclosedParen(start));
             };
+            Ok(match pat {
+                Pat::Constructor(name, args, _) => {
+                    Pat::Constructor(name, args, Span::from((start, end)))
+                }
+                pat => pat,
+            })
+        }
+        Some(Token::Op(Op { lexme, span: start, .. }))
+            if lexme == "-" && is_pattern(tokens) =>
+        {
             tokens.remove(0);
-            types.push(TypeInfo::Array(Box::new(TypeInfo::from(ident))));
-            consume_op(tokens, ">")?;
-            consume_ctrl_if(tokens, "->");
-            continue;
+            let Expr::Atom(Atom::Int(int, pos, end)) = primary(tokens)? else {
+                unreachable!("is_pattern only lets `-` in before an int");
+            };
+            Ok(Pat::Lit(Atom::Int(-int, pos, Span::from((start, end)))))
         }
-        types.push(TypeInfo::from(ident));
-        tokens.remove(0);
+        _ if is_pattern(tokens) => {
+            let Expr::Atom(atom) = primary(tokens)? else {
+                unreachable!("literal patterns are atoms");
+            };
+            Ok(Pat::Lit(atom))
+        }
+        token => {
+            let span = token.map(|t| t.span()).unwrap_or_default();
+            Err(Error::ExpectedPattern(span))
+        }
+    }
+}
+
+fn get_function_type_info(tokens: &mut Vec<Token>) -> Result<Vec<TypeInfo>> {
+    if consume_ctrl_if(tokens, ":").is_none() {
+        return Ok(Vec::new());
+    }
+    type_signature(tokens)
+}
+
+/// The part of a signature after the `:`, split on its top level arrows.
+pub(crate) fn type_signature(tokens: &mut Vec<Token>) -> Result<Vec<TypeInfo>> {
+    let mut types = Vec::new();
+    loop {
+        let (type_info, _) = type_app(tokens)?;
+        types.push(type_info);
         if consume_ctrl_if(tokens, "->").is_none() {
             break;
         }
@@ -122,6 +585,7 @@ fn get_function_type_info(tokens: &mut Vec<Token>) -> Result<Vec<TypeInfo>> {
 pub(crate) fn expression(tokens: &mut Vec<Token>) -> Result<Expr> {
     match tokens.get(0) {
         Some(Token::KeyWord(kw)) if kw.lexme == "if" => if_expression(tokens),
+        Some(Token::KeyWord(kw)) if kw.lexme == "do" => do_expression(tokens),
         Some(Token::Ctrl(c)) if vec!["λ", "\\"].contains(&c.lexme.as_str()) => {
             lambda_expression(tokens)
         }
@@ -148,6 +612,42 @@ fn if_expression(tokens: &mut Vec<Token>) -> Result<Expr> {
     ))
 }
 
+fn do_expression(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let start = consume_keyword(tokens, "do")?.span();
+    consume_ctrl(tokens, "{")?;
+    let mut stmts = Vec::new();
+    loop {
+        let is_bind = matches!(tokens.first(), Some(Token::Ident(..)))
+            && matches!(tokens.get(1), Some(Token::Ctrl(ctrl)) if ctrl.lexme == "<-");
+        if is_bind {
+            let Token::Ident(Ident { lexme, span, .. }) = tokens.remove(0) else {
+                unreachable!();
+            };
+            tokens.remove(0);
+            let expr = expression(tokens)?;
+            let span = Span::from((span, expr.span()));
+            stmts.push(Stmt::Bind(lexme, expr, span));
+        } else {
+            stmts.push(Stmt::Expr(expression(tokens)?));
+        }
+        let end_of_stmt = consume_ctrl_if(tokens, ";").is_some();
+        if matches!(tokens.first(), Some(Token::Ctrl(ctrl)) if ctrl.lexme == "}") {
+            break;
+        }
+        if !end_of_stmt {
+            consume_ctrl(tokens, ";")?;
+        }
+    }
+    let Token::Ctrl(Ctrl { pos, span: end, .. }) = tokens.remove(0) else {
+        unreachable!();
+    };
+    let span = Span::from((start, end));
+    if let Some(Stmt::Bind(.., span)) = stmts.last() {
+        return Err(Error::DoEndsWithBind(*span));
+    }
+    Ok(Expr::Do(Do { stmts, pos, span }))
+}
+
 fn lambda_expression(tokens: &mut Vec<Token>) -> Result<Expr> {
     let Token::Ctrl(Ctrl{span: start, ..}) = tokens.remove(0) else {
         panic!("expected `\\` or `λ` in lambda expression");
@@ -306,14 +806,14 @@ fn unary(tokens: &mut Vec<Token>) -> Result<Expr> {
 }
 
 fn call(tokens: &mut Vec<Token>) -> Result<Expr> {
-    let expr = primary(tokens)?;
+    let expr = postfix(tokens)?;
 
     let (mut pos, start) = match &expr {
         Expr::Atom(Atom::Id(_, pos, start)) => (*pos, *start),
-        Expr::Closure(_, tail, span) => (tail.position(), *span),
-        _ => return Ok(expr),
+        Expr::Atom(..) | Expr::Array(..) | Expr::Concat(..) => return Ok(expr),
+        expr => (expr.position(), expr.span()),
     };
-    let next_token = tokens.get(0);
+    let next_token = tokens.first();
     let mut pos1 = next_token.map(|t| t.position()).cloned().unwrap_or(pos);
     if !is_atom(next_token) || is_deliminator(pos, pos1) || is_keyword(next_token) {
         return Ok(expr);
@@ -325,7 +825,7 @@ fn call(tokens: &mut Vec<Token>) -> Result<Expr> {
         if !is_atom(next_token) || is_keyword(next_token) {
             break;
         }
-        let atom = primary(tokens)?;
+        let atom = postfix(tokens)?;
         pos = atom.position();
 
         args.push(atom);
@@ -348,6 +848,35 @@ fn call(tokens: &mut Vec<Token>) -> Result<Expr> {
     }));
 }
 
+/// `xs[i]` and `xs[i..j]`. The `[` has to touch the expression before it,
+/// `f xs [i]` is still `f` applied to `xs` and the array `[i]`.
+fn postfix(tokens: &mut Vec<Token>) -> Result<Expr> {
+    let mut expr = primary(tokens)?;
+    while let Some(Token::Ctrl(Ctrl { lexme, span, .. })) = tokens.first() {
+        if lexme != "[" || span.idx_start != expr.span().idx_end {
+            break;
+        }
+        tokens.remove(0);
+        let index = expression(tokens)?;
+        let end = match consume_op_if(tokens, "..") {
+            Some(_) => Some(Box::new(expression(tokens)?)),
+            None => None,
+        };
+        let Some(Token::Ctrl(Ctrl { pos, span: end_span, .. })) = consume_ctrl_if(tokens, "]")
+        else {
+            return Err(Error::UnclosedArray(index.span()));
+        };
+        expr = Expr::Index(Index {
+            span: Span::from((expr.span(), end_span)),
+            expr: Box::new(expr),
+            index: Box::new(index),
+            end,
+            pos,
+        });
+    }
+    Ok(expr)
+}
+
 fn primary(tokens: &mut Vec<Token>) -> Result<Expr> {
     let Some(_) = tokens.get(0) else {
         return Err(Error::UnexpectedEndOfInput(Span::default()));
@@ -392,6 +921,7 @@ fn primary(tokens: &mut Vec<Token>) -> Result<Expr> {
             Ok(expr.map_position(|_| pos))
         }
         Token::Ctrl(c) if c.lexme == "[" => array(tokens, c.span),
+        Token::Ctrl(c) if c.lexme == "\"" => interpolation(tokens, c.span),
         token => Err(Error::UnexpectedToken(
             "(, [, ident, str, int, float, true, false, char".to_string(),
             format!("{token:?}\r\n{:?}", tokens.get(1)),
@@ -401,6 +931,9 @@ fn primary(tokens: &mut Vec<Token>) -> Result<Expr> {
 }
 
 fn array(tokens: &mut Vec<Token>, start: Span) -> Result<Expr> {
+    if let Some(token) = consume_op_if(tokens, "..") {
+        return Err(Error::MalformedRange(Span::from((start, token.span()))));
+    }
     let mut exprs = Vec::new();
     while !tokens.is_empty() {
         if matches!(tokens.get(0), Some(Token::Ctrl(Ctrl{lexme, ..})) if lexme == "]") {
@@ -408,6 +941,15 @@ fn array(tokens: &mut Vec<Token>, start: Span) -> Result<Expr> {
         }
         let expr = expression(tokens)?;
         exprs.push(expr);
+        if let Some(token) = consume_op_if(tokens, "..") {
+            if exprs.len() > 2 {
+                return Err(Error::MalformedRange(Span::from((start, token.span()))));
+            }
+            return range(tokens, start, exprs);
+        }
+        if exprs.len() == 1 && consume_op_if(tokens, "|").is_some() {
+            return comprehension(tokens, start, exprs.remove(0));
+        }
         consume_ctrl_if(tokens, ",");
     }
     let Some(Token::Ctrl(Ctrl{span: end, pos, ..})) = consume_ctrl_if(tokens, "]") else {
@@ -417,6 +959,111 @@ fn array(tokens: &mut Vec<Token>, start: Span) -> Result<Expr> {
     Ok(Expr::Array(exprs, pos, span))
 }
 
+/// The lexer hands an interpolated string over as `"`, then the string
+/// parts and the `{ expr }` holes in order, then a closing `"`.
+fn interpolation(tokens: &mut Vec<Token>, start: Span) -> Result<Expr> {
+    let mut parts = Vec::new();
+    loop {
+        match tokens.first() {
+            Some(Token::Str(..)) => parts.push(primary(tokens)?),
+            Some(Token::Ctrl(Ctrl { lexme, span, .. })) if lexme == "{" => {
+                let open = *span;
+                tokens.remove(0);
+                parts.push(expression(tokens)?);
+                if consume_ctrl_if(tokens, "}").is_none() {
+                    return Err(Error::UnclosedInterpolation(open));
+                }
+            }
+            _ => break,
+        }
+    }
+    let Some(Token::Ctrl(Ctrl { pos, span: end, .. })) = consume_ctrl_if(tokens, "\"")
+    else {
+        return Err(Error::UnclosedInterpolation(start));
+    };
+    Ok(Expr::Concat(Concat {
+        parts,
+        pos,
+        span: Span::from((start, end)),
+    }))
+}
+
+/// `[a..b]` becomes `range a b` and `[a, b..c]` becomes `range_then a b c`.
+fn range(tokens: &mut Vec<Token>, start: Span, mut exprs: Vec<Expr>) -> Result<Expr> {
+    let is_end = |token: Option<&Token>| {
+        matches!(token, Some(Token::Ctrl(Ctrl { lexme, .. })) if lexme == "]")
+    };
+    if tokens.is_empty() || is_end(tokens.first()) {
+        let end = tokens.first().map(|t| t.span()).unwrap_or(start);
+        return Err(Error::MalformedRange(Span::from((start, end))));
+    }
+    exprs.push(expression(tokens)?);
+    if !is_end(tokens.first()) {
+        let end = tokens.first().map(|t| t.span()).unwrap_or(start);
+        return Err(Error::MalformedRange(Span::from((start, end))));
+    }
+    let Token::Ctrl(Ctrl { pos, span: end, .. }) = tokens.remove(0) else {
+        unreachable!();
+    };
+    let span = Span::from((start, end));
+    let name = if exprs.len() == 2 { "range" } else { "range_then" };
+    Ok(Expr::App(App {
+        name: Box::new(Expr::Atom(Atom::Id(name.into(), pos, start))),
+        args: exprs,
+        pos,
+        span,
+    }))
+}
+
+enum Qualifier {
+    Generator(Expr, Expr),
+    Guard(Expr),
+}
+
+/// `[e | x <- xs, p]` becomes `concat_map (\x -> if p then [e] else []) xs`.
+fn comprehension(tokens: &mut Vec<Token>, start: Span, expr: Expr) -> Result<Expr> {
+    let mut qualifiers = Vec::new();
+    loop {
+        let is_generator = matches!(tokens.first(), Some(Token::Ident(..)))
+            && matches!(tokens.get(1), Some(Token::Ctrl(ctrl)) if ctrl.lexme == "<-");
+        if is_generator {
+            let Token::Ident(Ident { lexme, pos, span }) = tokens.remove(0) else {
+                unreachable!();
+            };
+            tokens.remove(0);
+            let name = Expr::Atom(Atom::Id(lexme, pos, span));
+            qualifiers.push(Qualifier::Generator(name, expression(tokens)?));
+        } else {
+            qualifiers.push(Qualifier::Guard(expression(tokens)?));
+        }
+        if consume_ctrl_if(tokens, ",").is_none() {
+            break;
+        }
+    }
+    let Some(Token::Ctrl(Ctrl { span: end, pos, .. })) = consume_ctrl_if(tokens, "]") else {
+        return Err(Error::UnclosedArray(start));
+    };
+    let span = Span::from((start, end));
+    let body = Expr::Array(vec![expr], pos, span);
+    Ok(qualifiers
+        .into_iter()
+        .rev()
+        .fold(body, |body, qualifier| match qualifier {
+            Qualifier::Generator(name, iter) => Expr::App(App {
+                name: Box::new(Expr::Atom(Atom::Id("concat_map".into(), pos, start))),
+                args: vec![Expr::Closure(Box::new(name), Box::new(body), span), iter],
+                pos,
+                span,
+            }),
+            Qualifier::Guard(condition) => Expr::IfElse(
+                Box::new(condition),
+                Box::new(body),
+                Box::new(Expr::Array(vec![], pos, span)),
+                span,
+            ),
+        }))
+}
+
 fn is_atom(token: Option<&Token>) -> bool {
     let Some(token) = token else {
         return false
@@ -433,7 +1080,7 @@ fn is_atom(token: Option<&Token>) -> bool {
     };
 
     match lexme {
-        "true" | "false" | "(" | "[" => true,
+        "true" | "false" | "(" | "[" | "\"" => true,
         _ => false,
     }
 }
@@ -512,6 +1159,14 @@ fn consume_ctrl_if(tokens: &mut Vec<Token>, expected: &str) -> Option<Token> {
     return Some(tokens.remove(0));
 }
 
+fn consume_op_if(tokens: &mut Vec<Token>, expected: &str) -> Option<Token> {
+    let token = tokens.first();
+    if !matches!(&token, Some(Token::Op(Op{lexme, ..})) if lexme == expected) {
+        return None;
+    }
+    Some(tokens.remove(0))
+}
+
 fn consume_op(tokens: &mut Vec<Token>, expected: &str) -> Result<Token> {
     let token = tokens.get(0);
     if matches!(&token, Some(Token::Op(Op{lexme, ..})) if lexme != expected) {
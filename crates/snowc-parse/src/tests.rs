@@ -213,3 +213,420 @@
 //     r#"main = 1 mod 10;"#,
 //     vec![r#"<main: (mod 1 10)>"#,],
 // );
+
+use super::{
+    error::Error,
+    expr::Binary,
+    fold::{self, Fold},
+    parse, Atom, Expr, Op, Pat, Visitor,
+};
+use pretty_assertions::assert_eq;
+
+macro_rules! parse_test {
+    ($name:ident, $src:expr, $expected:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            let ast = match parse($src) {
+                Ok(ast) => ast,
+                Err(errors) => {
+                    for err in errors.iter() {
+                        eprintln!("{}", err.report("test", $src));
+                    }
+                    panic!("failed to parse");
+                }
+            };
+            let left = ast.iter().map(ToString::to_string).collect::<Vec<_>>();
+            assert_eq!(left, $expected);
+        }
+    };
+}
+
+parse_test!(
+    single_clause_stays_closures,
+    "add x y = x + y",
+    vec![r#"<add:  = (\x -> (\y -> (+ x y)))>"#],
+);
+
+parse_test!(
+    literal_clauses,
+    "fact 0 = 1\nfact n = n * fact (n - 1)",
+    vec![r#"<fact:  = (\$0 -> (match $0 | 0 -> 1 | n -> (* n <fact: ((- n 1))>)))>"#],
+);
+
+parse_test!(
+    negative_literal_clauses,
+    "sign -1 = 0\nsign (Some -2) = 1\nsign n = n",
+    vec![
+        r#"<sign:  = (\$0 -> (match $0 | -1 -> 0 | (Some -2) -> 1 | n -> n))>"#
+    ],
+);
+
+parse_test!(
+    guard_clauses,
+    "abs n\n  | n < 0 = 0 - n\n  | true = n",
+    vec![r#"<abs:  = (\$0 -> (match $0 | n if (< n 0) -> (- 0 n) | n if true -> n))>"#],
+);
+
+parse_test!(
+    constructor_and_wildcard_patterns,
+    "unwrap (Some x) _ = x\nunwrap None d = d",
+    vec![
+        r#"<unwrap:  = (\$0 -> (\$1 -> (match $0, $1 | (Some x), _ -> x | None, d -> d)))>"#
+    ],
+);
+
+parse_test!(
+    any_expression_can_be_applied,
+    "main = [(f 1) 2, (head fns) 1, (\\x -> x) 3]",
+    vec![r#"<main:  = [<<f: (1)>: (2)>, <<head: (fns)>: (1)>, <(\x -> x): (3)>]>"#],
+);
+
+#[test]
+fn clause_arity_mismatch() {
+    let errors = parse("f 0 = 1\nf a b = 2").unwrap_err();
+    assert!(matches!(errors[0], Error::ClauseArityMismatch(..)));
+}
+
+#[test]
+fn one_signature_per_function() {
+    let errors = parse("f 0 : Int -> Int = 1\nf n : Bool -> Bool = n").unwrap_err();
+    let [Error::SecondSignature(name, span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    assert_eq!((name.as_str(), span.row_start), ("f", 1));
+}
+
+parse_test!(
+    ranges,
+    "main = [[1..10], [1, 3..9]]",
+    vec![r#"<main:  = [<range: (1, 10)>, <range_then: (1, 3, 9)>]>"#],
+);
+
+parse_test!(
+    list_comprehension,
+    "main = [f x | x <- xs, p x]",
+    vec![
+        r#"<main:  = <concat_map: ((\x -> (if (<p: (x)>) then [<f: (x)>] else [])), xs)>>"#
+    ],
+);
+
+#[test]
+fn malformed_ranges() {
+    for src in [
+        "main = [1..]",
+        "main = [..3]",
+        "main = [1, 2, 3..9]",
+        "main = [1..3, 4]",
+    ] {
+        let errors = parse(src).unwrap_err();
+        assert!(
+            matches!(errors[0], Error::MalformedRange(..)),
+            "{src}: {errors:?}"
+        );
+    }
+}
+
+parse_test!(
+    do_block,
+    "main = do { print \"a\"; x <- read_line; print x }",
+    vec![r#"<main:  = (do { <print: (a)>; x <- read_line; <print: (x)> })>"#],
+);
+
+parse_test!(
+    do_block_over_many_lines,
+    "main = do {\n  x <- read_line;\n  print x;\n}",
+    vec![r#"<main:  = (do { x <- read_line; <print: (x)> })>"#],
+);
+
+#[test]
+fn do_block_ends_with_bind() {
+    let errors = parse("main = do { x <- read_line }").unwrap_err();
+    assert!(matches!(errors[0], Error::DoEndsWithBind(..)));
+}
+
+parse_test!(
+    type_alias,
+    "type Grid = Array<Bool>\ntype Pair a = Array<a>",
+    vec!["<type Grid = Array<Bool>>", "<type Pair a = Array<a>>"],
+);
+
+parse_test!(
+    data_declaration,
+    "data Tree a = Leaf | Node (Tree a) a (Tree a)\nsize Leaf = 0",
+    vec![
+        "<data Tree a = Leaf | Node (Tree a) a (Tree a)>",
+        r#"<size:  = (\$0 -> (match $0 | Leaf -> 0))>"#,
+    ],
+);
+
+parse_test!(
+    data_declaration_over_many_lines,
+    "data Shape\n  = Circle Int\n  | Rect Int Int\narea s : Shape -> Int = 0",
+    vec![
+        "<data Shape = Circle Int | Rect Int Int>",
+        "<area: Shape -> Int = (\\s -> 0)>"
+    ],
+);
+
+#[test]
+fn data_needs_constructors() {
+    let errors = parse("data Tree a = leaf").unwrap_err();
+    assert!(matches!(errors[0], Error::ExpectedConstructor(..)));
+}
+
+#[test]
+fn aliases_expand() {
+    use crate::{expr::Alias, Expr, TypeInfo};
+    let ast = parse("type Grid = Array<Bool>\ntype Board a = Array<a>").unwrap();
+    let aliases = ast
+        .into_iter()
+        .filter_map(|expr| match expr {
+            Expr::Alias(alias) => Some((alias.name.clone(), alias)),
+            _ => None,
+        })
+        .collect::<std::collections::HashMap<String, Alias>>();
+    let grid = TypeInfo::Custom("Grid".into());
+    let board = TypeInfo::Apply("Board".into(), vec![grid.clone()]);
+    assert_eq!(
+        board.expand_aliases(&aliases).to_string(),
+        "Array<Array<Bool>>"
+    );
+}
+
+parse_test!(
+    index_and_slice,
+    "main = [xs[0], xs[1..n], grid[0][1], f xs [1]]",
+    vec!["<main:  = [xs[0], xs[1..n], grid[0][1], <f: (xs, [1])>]>"],
+);
+
+parse_test!(
+    string_interpolation,
+    r#"main = print "cell {x} is {show c}" "{if a then "b" else "c"}\{}""#,
+    vec![
+        r#"<main:  = <print: ("cell {x} is {<show: (c)>}", "{(if (a) then b else c)}\{}")>>"#
+    ],
+);
+
+#[test]
+fn unclosed_interpolation() {
+    let errors = parse(r#"main = "cell {x""#).unwrap_err();
+    assert!(matches!(errors[0], Error::UnclosedInterpolation(..)));
+}
+
+#[test]
+fn unclosed_interpolation_points_at_the_brace() {
+    let src = "main : IO = print \"{\"\n";
+    let errors = parse(src).unwrap_err();
+    let [Error::UnclosedInterpolation(span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    assert_eq!((span.idx_start, span.idx_end), (19, 20), "{span:?}");
+    let report = errors[0].report("main.snow", src);
+    assert!(!report.contains("unexpected end of file"), "{report}");
+    assert!(report.contains("main.snow:1:20"), "{report}");
+    assert!(!report.contains("(19,0)"), "{report}");
+}
+
+#[test]
+fn builtin_signatures() {
+    use crate::builtin::{lookup, BUILTINS};
+    for builtin in BUILTINS.iter() {
+        let type_info = builtin.type_info();
+        assert_eq!(builtin.arity(), type_info.len() - 1, "{}", builtin.name);
+    }
+    let push = lookup("push").unwrap().type_info();
+    let push = push.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(push, vec!["Array<a>", "a", "Array<a>"]);
+}
+
+#[test]
+fn pragmas_come_before_the_code() {
+    let src = "-- streams\n--# lazy\n\nmain = 1\n--# strict\n";
+    assert_eq!(crate::pragmas(src), vec!["lazy"]);
+    assert!(crate::pragmas("main = 1").is_empty());
+}
+
+fn validate_errors(src: &str) -> Vec<Error> {
+    crate::validate(&parse(src).unwrap())
+        .err()
+        .unwrap_or_default()
+}
+
+#[test]
+fn validate_accepts_bound_names() {
+    let src = r#"
+data Option a = Some a | None
+add x y : Int -> Int -> Int = x + y
+inc : Int -> Int = add 1
+unwrap (Some x) _ = x
+unwrap None d = d
+main = do {
+  line <- read_line;
+  print [(\x -> x + 1) y | y <- [1..3]] line (unwrap None 0) (inc 1)
+}
+"#;
+    assert!(
+        validate_errors(src).is_empty(),
+        "{:?}",
+        validate_errors(src)
+    );
+}
+
+#[test]
+fn validate_reports_every_mistake() {
+    let src = r#"
+twice x = x
+add x y : Int -> Int = x + y
+twice y = y
+start = foo 1
+"#;
+    let errors = validate_errors(src);
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [
+                Error::Duplicate(twice, ..),
+                Error::SignatureArity(add, 2, 1, _),
+                Error::Undefined(foo, _),
+                Error::MissingMain(..),
+            ] if twice == "twice" && add == "add" && foo == "foo"
+        ),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn builtins_cannot_be_defined_again() {
+    for src in [
+        "head x = x + 1\nmain = print (head 5)",
+        "length xs = \"hi\"\nmain = length [1]",
+        "concat_map f xs = xs\nmain = [x | x <- [1]]",
+    ] {
+        let errors = validate_errors(src);
+        assert!(
+            matches!(errors.as_slice(), [Error::RedefinedBuiltin(..)]),
+            "{errors:?}"
+        );
+    }
+}
+
+#[test]
+fn externs_cannot_be_defined_again() {
+    use crate::builtin::Extern;
+    let ast = parse("double x = x + x\nmain = double 2").unwrap();
+    let double = Extern {
+        name: "double".into(),
+        arity: 1,
+        signature: None,
+    };
+    let errors = crate::validate_with(&ast, &[double]).unwrap_err();
+    assert!(
+        matches!(errors.as_slice(), [Error::RedefinedBuiltin(name, _)] if name == "double"),
+        "{errors:?}"
+    );
+}
+
+parse_test!(
+    class_and_instance,
+    r#"
+class Size a {
+  size : a -> Int;
+  empty : a -> Bool
+}
+instance Size (Tree a) {
+  size Leaf = 0;
+  size (Node l _ r) = size l + 1 + size r;
+  empty t = size t == 0
+}
+"#,
+    vec![
+        "<class Size a { size : a -> Int; empty : a -> Bool }>",
+        concat!(
+            r#"<instance Size (Tree a) { <size:  = (\$0 -> (match $0 | Leaf -> 0 | "#,
+            r#"(Node l _ r) -> (+ (+ <size: (l)> 1) <size: (r)>)))>; "#,
+            r#"<empty:  = (\t -> (== <size: (t)> 0))> }>"#,
+        ),
+    ],
+);
+
+#[test]
+fn builtin_classes() {
+    use crate::{
+        builtin::{classes, operator_method},
+        Op,
+    };
+    let classes = classes();
+    let names = classes.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Eq", "Ord", "Add", "Num", "Show"]);
+    for op in [Op::Eq, Op::Les, Op::Plus, Op::Minus, Op::Mod] {
+        let method = operator_method(op).unwrap();
+        assert!(
+            classes
+                .iter()
+                .any(|c| c.methods.iter().any(|m| m.name == method)),
+            "{method}"
+        );
+    }
+}
+
+#[test]
+fn test_visitor_collects_ids() {
+    #[derive(Default)]
+    struct Ids(Vec<String>);
+    impl Visitor for Ids {
+        fn visit_atom(&mut self, atom: &Atom) {
+            if let Atom::Id(name, ..) = atom {
+                self.0.push(name.clone());
+            }
+        }
+    }
+    let ast = parse("add x y = x + (f y)").unwrap();
+    let mut ids = Ids::default();
+    for node in ast.iter() {
+        ids.visit_expr(node);
+    }
+    assert_eq!(ids.0, vec!["x", "y", "x", "f", "y"]);
+}
+
+#[test]
+fn test_fold_rewrites_ops() {
+    struct PlusToMinus;
+    impl Fold for PlusToMinus {
+        fn fold_binary(&mut self, binary: Binary) -> Expr {
+            let binary = fold::walk_binary(self, binary);
+            let op = match binary.op {
+                Op::Plus => Op::Minus,
+                op => op,
+            };
+            Expr::Binary(Binary { op, ..binary })
+        }
+    }
+    let ast = parse("add x y = x + y + 1").unwrap();
+    let ast = ast
+        .into_iter()
+        .map(|node| PlusToMinus.fold_expr(node).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(ast, vec!["<add:  = (\\x -> (\\y -> (- (- x y) 1)))>"]);
+}
+
+#[test]
+fn test_fold_rewrites_patterns() {
+    struct Rename;
+    impl Fold for Rename {
+        fn fold_pat(&mut self, pat: Pat) -> Pat {
+            match fold::walk_pat(self, pat) {
+                Pat::Id(name, span) => Pat::Id(format!("{name}2"), span),
+                pat => pat,
+            }
+        }
+    }
+    let ast = parse("first (Some x) = x\nfirst None = 0").unwrap();
+    let ast = ast
+        .into_iter()
+        .map(|node| Rename.fold_expr(node).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ast,
+        vec![r#"<first:  = (\$0 -> (match $0 | (Some x2) -> x | None -> 0))>"#]
+    );
+}
@@ -0,0 +1,262 @@
+//! Checks that only need names, run between parsing and anything else so a
+//! misspelt function is reported before the program starts instead of when
+//! the evaluator first reaches it.
+use super::{
+    builtin::{classes, Extern, BUILTINS},
+    error::Error,
+    visit::{walk_stmt, Visitor},
+    Alias, Arm, Atom, Class, Do, Expr, Pat, Span, Stmt, TypeInfo,
+};
+use std::collections::HashMap;
+
+/// Every mistake found in `ast`, a program without a `main` is one of them.
+pub fn validate(ast: &[Expr]) -> Result<(), Vec<Error>> {
+    validate_with(ast, &[])
+}
+
+/// [`validate`] for a program that can also call `externs`.
+pub fn validate_with(ast: &[Expr], externs: &[Extern]) -> Result<(), Vec<Error>> {
+    let mut errors = duplicates(ast, externs);
+    for ext in externs.iter() {
+        if let Err(error) = ext.type_info() {
+            errors.push(error);
+        }
+    }
+    let aliases = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Alias(alias) => Some((alias.name.clone(), alias.clone())),
+            _ => None,
+        })
+        .collect();
+    for expr in ast.iter() {
+        if let Expr::Func(name, type_info, body, span) = expr {
+            if let Some(error) = signature_arity(name, type_info, body, &aliases, *span) {
+                errors.push(error);
+            }
+        }
+    }
+
+    let builtin_classes = classes();
+    let all_classes = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Class(class) => Some(class),
+            _ => None,
+        })
+        .chain(builtin_classes.iter())
+        .collect::<Vec<_>>();
+    errors.extend(instances(ast, &all_classes));
+
+    let mut names = Names {
+        globals: ast.iter().flat_map(defines).map(|(name, _)| name).collect(),
+        locals: vec![],
+        errors: vec![],
+    };
+    names
+        .globals
+        .extend(BUILTINS.iter().map(|builtin| builtin.name.to_string()));
+    names
+        .globals
+        .extend(externs.iter().map(|ext| ext.name.clone()));
+    names.globals.extend(
+        builtin_classes
+            .iter()
+            .flat_map(|class| class.methods.iter().map(|method| method.name.clone())),
+    );
+    for expr in ast.iter() {
+        names.visit_expr(expr);
+    }
+    errors.extend(names.errors);
+
+    let has_main = ast
+        .iter()
+        .any(|expr| matches!(expr, Expr::Func(name, ..) if name == "main"));
+    if !has_main {
+        errors.push(Error::MissingMain(Span::default()));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(())
+}
+
+/// The values a top level item brings into scope, a function, the
+/// constructors of a `data` type or the methods of a class.
+fn defines(expr: &Expr) -> Vec<(String, Span)> {
+    match expr {
+        Expr::Func(name, .., span) => vec![(name.clone(), *span)],
+        Expr::Data(data) => data
+            .variants
+            .iter()
+            .map(|variant| (variant.name.clone(), variant.span))
+            .collect(),
+        Expr::Class(class) => class
+            .methods
+            .iter()
+            .map(|method| (method.name.clone(), method.span))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// A name can only be defined once. The builtins and the externs count as
+/// defined before the program, the type checker and the evaluator would
+/// each pick a different one of the two.
+fn duplicates(ast: &[Expr], externs: &[Extern]) -> Vec<Error> {
+    let mut errors = vec![];
+    let mut values: HashMap<String, Span> = HashMap::new();
+    let mut types: HashMap<String, Span> = HashMap::new();
+    for expr in ast.iter() {
+        for (name, span) in defines(expr) {
+            let builtin = BUILTINS.iter().any(|builtin| builtin.name == name)
+                || externs.iter().any(|ext| ext.name == name);
+            if builtin {
+                errors.push(Error::RedefinedBuiltin(name, span));
+                continue;
+            }
+            match values.get(&name) {
+                Some(first) => errors.push(Error::Duplicate(name, Box::new(*first), span)),
+                None => {
+                    values.insert(name, span);
+                }
+            }
+        }
+        let (name, span) = match expr {
+            Expr::Data(data) => (data.name.clone(), data.span),
+            Expr::Alias(alias) => (alias.name.clone(), alias.span),
+            Expr::Class(class) => (class.name.clone(), class.span),
+            Expr::Instance(instance) => {
+                let name = format!("instance {} {}", instance.class, instance.type_info);
+                (name, instance.span)
+            }
+            _ => continue,
+        };
+        match types.get(&name) {
+            Some(first) => errors.push(Error::Duplicate(name, Box::new(*first), span)),
+            None => {
+                types.insert(name, span);
+            }
+        }
+    }
+    errors
+}
+
+/// An instance has to be of a class that exists and can only give the
+/// methods that class declares.
+fn instances(ast: &[Expr], classes: &[&Class]) -> Vec<Error> {
+    let mut errors = vec![];
+    for expr in ast.iter() {
+        let Expr::Instance(instance) = expr else {
+            continue;
+        };
+        let Some(class) = classes.iter().find(|class| class.name == instance.class)
+        else {
+            errors.push(Error::Undefined(instance.class.clone(), instance.span));
+            continue;
+        };
+        for method in instance.methods.iter() {
+            let Expr::Func(name, .., span) = method else {
+                continue;
+            };
+            if !class.methods.iter().any(|m| &m.name == name) {
+                errors.push(Error::NotAMethod(name.clone(), class.name.clone(), *span));
+            }
+        }
+    }
+    errors
+}
+
+/// A function can't take more parameters than its signature gives it. It
+/// can take fewer, `inc : Int -> Int = add 1` returns a function instead.
+fn signature_arity(
+    name: &str,
+    type_info: &[TypeInfo],
+    body: &Expr,
+    aliases: &HashMap<String, Alias>,
+    span: Span,
+) -> Option<Error> {
+    let (ret, params) = type_info.split_last()?;
+    let mut room = params.len();
+    let mut ret = ret.expand_aliases(aliases);
+    while let TypeInfo::Func(_, rhs) = ret {
+        room += 1;
+        ret = *rhs;
+    }
+    let mut taken = 0;
+    let mut body = body;
+    while let Expr::Closure(_, tail, ..) = body {
+        taken += 1;
+        body = tail;
+    }
+    (taken > room).then(|| Error::SignatureArity(name.into(), taken, room, span))
+}
+
+/// Walks every function body with the names bound around each expression.
+struct Names {
+    globals: Vec<String>,
+    locals: Vec<String>,
+    errors: Vec<Error>,
+}
+
+impl Names {
+    fn check(&mut self, name: &str, span: Span) {
+        let known = self.locals.iter().rev().any(|local| local == name)
+            || self.globals.iter().any(|global| global == name);
+        if !known {
+            self.errors.push(Error::Undefined(name.into(), span));
+        }
+    }
+}
+
+impl Visitor for Names {
+    fn visit_atom(&mut self, atom: &Atom) {
+        if let Atom::Id(name, _, span) = atom {
+            self.check(name, *span);
+        }
+    }
+
+    fn visit_closure(&mut self, head: &Expr, tail: &Expr, _span: Span) {
+        let Expr::Atom(Atom::Id(name, ..)) = head else {
+            unreachable!("closure parameters are always names");
+        };
+        self.locals.push(name.clone());
+        self.visit_expr(tail);
+        self.locals.pop();
+    }
+
+    fn visit_arm(&mut self, arm: &Arm) {
+        let len = self.locals.len();
+        for pat in arm.pats.iter() {
+            self.visit_pat(pat);
+            self.locals
+                .extend(pat.names().into_iter().map(String::from));
+        }
+        if let Some(guard) = &arm.guard {
+            self.visit_expr(guard);
+        }
+        self.visit_expr(&arm.body);
+        self.locals.truncate(len);
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        if let Pat::Constructor(name, args, span) = pat {
+            self.check(name, *span);
+            for arg in args.iter() {
+                self.visit_pat(arg);
+            }
+        }
+    }
+
+    /// `name <- expr` is in scope for the lines after it.
+    fn visit_do(&mut self, d: &Do) {
+        let len = self.locals.len();
+        for stmt in d.stmts.iter() {
+            walk_stmt(self, stmt);
+            if let Stmt::Bind(name, ..) = stmt {
+                self.locals.push(name.clone());
+            }
+        }
+        self.locals.truncate(len);
+    }
+}
@@ -0,0 +1,212 @@
+//! Read only traversal of the AST.
+//!
+//! Implement [`Visitor`] and override only the `visit_*` methods for the
+//! nodes you care about. Call the matching `walk_*` function from inside an
+//! override to keep descending into the children of that node.
+use super::{
+    Alias, App, Arm, Atom, Binary, Class, Concat, Data, Do, Expr, Index, Instance, Match,
+    Pat, Span, Stmt, TokenPosition, TypeInfo, Unary,
+};
+
+pub trait Visitor: Sized {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_atom(&mut self, _atom: &Atom) {}
+
+    fn visit_unary(&mut self, unary: &Unary) {
+        walk_unary(self, unary);
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) {
+        walk_binary(self, binary);
+    }
+
+    fn visit_app(&mut self, app: &App) {
+        walk_app(self, app);
+    }
+
+    fn visit_array(&mut self, array: &[Expr], _pos: TokenPosition, _span: Span) {
+        walk_array(self, array);
+    }
+
+    fn visit_closure(&mut self, head: &Expr, tail: &Expr, _span: Span) {
+        walk_closure(self, head, tail);
+    }
+
+    fn visit_if_else(
+        &mut self,
+        condition: &Expr,
+        then: &Expr,
+        r#else: &Expr,
+        _span: Span,
+    ) {
+        walk_if_else(self, condition, then, r#else);
+    }
+
+    fn visit_func(
+        &mut self,
+        _name: &str,
+        _type_info: &[TypeInfo],
+        body: &Expr,
+        _span: Span,
+    ) {
+        walk_func(self, body);
+    }
+
+    fn visit_alias(&mut self, _alias: &Alias) {}
+
+    fn visit_data(&mut self, _data: &Data) {}
+
+    fn visit_class(&mut self, _class: &Class) {}
+
+    fn visit_instance(&mut self, instance: &Instance) {
+        walk_instance(self, instance);
+    }
+
+    fn visit_match(&mut self, m: &Match) {
+        walk_match(self, m);
+    }
+
+    fn visit_arm(&mut self, arm: &Arm) {
+        walk_arm(self, arm);
+    }
+
+    fn visit_pat(&mut self, _pat: &Pat) {}
+
+    fn visit_index(&mut self, index: &Index) {
+        walk_index(self, index);
+    }
+
+    fn visit_concat(&mut self, concat: &Concat) {
+        walk_concat(self, concat);
+    }
+
+    fn visit_do(&mut self, d: &Do) {
+        walk_do(self, d);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_error(&mut self, _span: Span) {}
+}
+
+pub fn walk_expr<V: Visitor>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Atom(atom) => visitor.visit_atom(atom),
+        Expr::Unary(unary) => visitor.visit_unary(unary),
+        Expr::Binary(binary) => visitor.visit_binary(binary),
+        Expr::App(app) => visitor.visit_app(app),
+        Expr::Array(array, pos, span) => visitor.visit_array(array, *pos, *span),
+        Expr::Closure(head, tail, span) => visitor.visit_closure(head, tail, *span),
+        Expr::IfElse(condition, then, r#else, span) => {
+            visitor.visit_if_else(condition, then, r#else, *span)
+        }
+        Expr::Func(name, type_info, body, span) => {
+            visitor.visit_func(name, type_info, body, *span)
+        }
+        Expr::Alias(alias) => visitor.visit_alias(alias),
+        Expr::Data(data) => visitor.visit_data(data),
+        Expr::Class(class) => visitor.visit_class(class),
+        Expr::Instance(instance) => visitor.visit_instance(instance),
+        Expr::Match(m) => visitor.visit_match(m),
+        Expr::Do(d) => visitor.visit_do(d),
+        Expr::Index(index) => visitor.visit_index(index),
+        Expr::Concat(concat) => visitor.visit_concat(concat),
+        Expr::Error(span) => visitor.visit_error(*span),
+    }
+}
+
+pub fn walk_unary<V: Visitor>(visitor: &mut V, unary: &Unary) {
+    visitor.visit_expr(&unary.expr);
+}
+
+pub fn walk_binary<V: Visitor>(visitor: &mut V, binary: &Binary) {
+    visitor.visit_expr(&binary.left);
+    visitor.visit_expr(&binary.right);
+}
+
+pub fn walk_app<V: Visitor>(visitor: &mut V, app: &App) {
+    visitor.visit_expr(&app.name);
+    for arg in app.args.iter() {
+        visitor.visit_expr(arg);
+    }
+}
+
+pub fn walk_array<V: Visitor>(visitor: &mut V, array: &[Expr]) {
+    for item in array.iter() {
+        visitor.visit_expr(item);
+    }
+}
+
+pub fn walk_closure<V: Visitor>(visitor: &mut V, head: &Expr, tail: &Expr) {
+    visitor.visit_expr(head);
+    visitor.visit_expr(tail);
+}
+
+pub fn walk_if_else<V: Visitor>(
+    visitor: &mut V,
+    condition: &Expr,
+    then: &Expr,
+    r#else: &Expr,
+) {
+    visitor.visit_expr(condition);
+    visitor.visit_expr(then);
+    visitor.visit_expr(r#else);
+}
+
+pub fn walk_func<V: Visitor>(visitor: &mut V, body: &Expr) {
+    visitor.visit_expr(body);
+}
+
+pub fn walk_instance<V: Visitor>(visitor: &mut V, instance: &Instance) {
+    for method in instance.methods.iter() {
+        visitor.visit_expr(method);
+    }
+}
+
+pub fn walk_match<V: Visitor>(visitor: &mut V, m: &Match) {
+    for expr in m.exprs.iter() {
+        visitor.visit_expr(expr);
+    }
+    for arm in m.arms.iter() {
+        visitor.visit_arm(arm);
+    }
+}
+
+pub fn walk_arm<V: Visitor>(visitor: &mut V, arm: &Arm) {
+    for pat in arm.pats.iter() {
+        visitor.visit_pat(pat);
+    }
+    if let Some(guard) = &arm.guard {
+        visitor.visit_expr(guard);
+    }
+    visitor.visit_expr(&arm.body);
+}
+
+pub fn walk_index<V: Visitor>(visitor: &mut V, index: &Index) {
+    visitor.visit_expr(&index.expr);
+    visitor.visit_expr(&index.index);
+    if let Some(end) = &index.end {
+        visitor.visit_expr(end);
+    }
+}
+
+pub fn walk_concat<V: Visitor>(visitor: &mut V, concat: &Concat) {
+    for part in concat.parts.iter() {
+        visitor.visit_expr(part);
+    }
+}
+
+pub fn walk_do<V: Visitor>(visitor: &mut V, d: &Do) {
+    for stmt in d.stmts.iter() {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor>(visitor: &mut V, stmt: &Stmt) {
+    visitor.visit_expr(stmt.expr());
+}
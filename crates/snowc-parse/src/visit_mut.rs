@@ -0,0 +1,222 @@
+//! In place traversal of the AST.
+//!
+//! Same shape as [`crate::visit::Visitor`] but every node is handed out as
+//! `&mut` so a pass can rewrite spans, names or whole sub trees as it goes.
+use super::{
+    Alias, App, Arm, Atom, Binary, Class, Concat, Data, Do, Expr, Index, Instance, Match,
+    Pat, Span, Stmt, TokenPosition, TypeInfo, Unary,
+};
+
+pub trait VisitorMut: Sized {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_atom_mut(&mut self, _atom: &mut Atom) {}
+
+    fn visit_unary_mut(&mut self, unary: &mut Unary) {
+        walk_unary_mut(self, unary);
+    }
+
+    fn visit_binary_mut(&mut self, binary: &mut Binary) {
+        walk_binary_mut(self, binary);
+    }
+
+    fn visit_app_mut(&mut self, app: &mut App) {
+        walk_app_mut(self, app);
+    }
+
+    fn visit_array_mut(
+        &mut self,
+        array: &mut Vec<Expr>,
+        _pos: &mut TokenPosition,
+        _span: &mut Span,
+    ) {
+        walk_array_mut(self, array);
+    }
+
+    fn visit_closure_mut(&mut self, head: &mut Expr, tail: &mut Expr, _span: &mut Span) {
+        walk_closure_mut(self, head, tail);
+    }
+
+    fn visit_if_else_mut(
+        &mut self,
+        condition: &mut Expr,
+        then: &mut Expr,
+        r#else: &mut Expr,
+        _span: &mut Span,
+    ) {
+        walk_if_else_mut(self, condition, then, r#else);
+    }
+
+    fn visit_func_mut(
+        &mut self,
+        _name: &mut String,
+        _type_info: &mut Vec<TypeInfo>,
+        body: &mut Expr,
+        _span: &mut Span,
+    ) {
+        walk_func_mut(self, body);
+    }
+
+    fn visit_alias_mut(&mut self, _alias: &mut Alias) {}
+
+    fn visit_data_mut(&mut self, _data: &mut Data) {}
+
+    fn visit_class_mut(&mut self, _class: &mut Class) {}
+
+    fn visit_instance_mut(&mut self, instance: &mut Instance) {
+        walk_instance_mut(self, instance);
+    }
+
+    fn visit_match_mut(&mut self, m: &mut Match) {
+        walk_match_mut(self, m);
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        walk_arm_mut(self, arm);
+    }
+
+    fn visit_pat_mut(&mut self, _pat: &mut Pat) {}
+
+    fn visit_index_mut(&mut self, index: &mut Index) {
+        walk_index_mut(self, index);
+    }
+
+    fn visit_concat_mut(&mut self, concat: &mut Concat) {
+        walk_concat_mut(self, concat);
+    }
+
+    fn visit_do_mut(&mut self, d: &mut Do) {
+        walk_do_mut(self, d);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_error_mut(&mut self, _span: &mut Span) {}
+}
+
+pub fn walk_expr_mut<V: VisitorMut>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Atom(atom) => visitor.visit_atom_mut(atom),
+        Expr::Unary(unary) => visitor.visit_unary_mut(unary),
+        Expr::Binary(binary) => visitor.visit_binary_mut(binary),
+        Expr::App(app) => visitor.visit_app_mut(app),
+        Expr::Array(array, pos, span) => visitor.visit_array_mut(array, pos, span),
+        Expr::Closure(head, tail, span) => visitor.visit_closure_mut(head, tail, span),
+        Expr::IfElse(condition, then, r#else, span) => {
+            visitor.visit_if_else_mut(condition, then, r#else, span)
+        }
+        Expr::Func(name, type_info, body, span) => {
+            visitor.visit_func_mut(name, type_info, body, span)
+        }
+        Expr::Alias(alias) => visitor.visit_alias_mut(alias),
+        Expr::Data(data) => visitor.visit_data_mut(data),
+        Expr::Class(class) => visitor.visit_class_mut(class),
+        Expr::Instance(instance) => visitor.visit_instance_mut(instance),
+        Expr::Match(m) => visitor.visit_match_mut(m),
+        Expr::Do(d) => visitor.visit_do_mut(d),
+        Expr::Index(index) => visitor.visit_index_mut(index),
+        Expr::Concat(concat) => visitor.visit_concat_mut(concat),
+        Expr::Error(span) => visitor.visit_error_mut(span),
+    }
+}
+
+pub fn walk_unary_mut<V: VisitorMut>(visitor: &mut V, unary: &mut Unary) {
+    visitor.visit_expr_mut(&mut unary.expr);
+}
+
+pub fn walk_binary_mut<V: VisitorMut>(visitor: &mut V, binary: &mut Binary) {
+    visitor.visit_expr_mut(&mut binary.left);
+    visitor.visit_expr_mut(&mut binary.right);
+}
+
+pub fn walk_app_mut<V: VisitorMut>(visitor: &mut V, app: &mut App) {
+    visitor.visit_expr_mut(&mut app.name);
+    for arg in app.args.iter_mut() {
+        visitor.visit_expr_mut(arg);
+    }
+}
+
+pub fn walk_array_mut<V: VisitorMut>(visitor: &mut V, array: &mut [Expr]) {
+    for item in array.iter_mut() {
+        visitor.visit_expr_mut(item);
+    }
+}
+
+pub fn walk_closure_mut<V: VisitorMut>(
+    visitor: &mut V,
+    head: &mut Expr,
+    tail: &mut Expr,
+) {
+    visitor.visit_expr_mut(head);
+    visitor.visit_expr_mut(tail);
+}
+
+pub fn walk_if_else_mut<V: VisitorMut>(
+    visitor: &mut V,
+    condition: &mut Expr,
+    then: &mut Expr,
+    r#else: &mut Expr,
+) {
+    visitor.visit_expr_mut(condition);
+    visitor.visit_expr_mut(then);
+    visitor.visit_expr_mut(r#else);
+}
+
+pub fn walk_func_mut<V: VisitorMut>(visitor: &mut V, body: &mut Expr) {
+    visitor.visit_expr_mut(body);
+}
+
+pub fn walk_instance_mut<V: VisitorMut>(visitor: &mut V, instance: &mut Instance) {
+    for method in instance.methods.iter_mut() {
+        visitor.visit_expr_mut(method);
+    }
+}
+
+pub fn walk_match_mut<V: VisitorMut>(visitor: &mut V, m: &mut Match) {
+    for expr in m.exprs.iter_mut() {
+        visitor.visit_expr_mut(expr);
+    }
+    for arm in m.arms.iter_mut() {
+        visitor.visit_arm_mut(arm);
+    }
+}
+
+pub fn walk_arm_mut<V: VisitorMut>(visitor: &mut V, arm: &mut Arm) {
+    for pat in arm.pats.iter_mut() {
+        visitor.visit_pat_mut(pat);
+    }
+    if let Some(guard) = &mut arm.guard {
+        visitor.visit_expr_mut(guard);
+    }
+    visitor.visit_expr_mut(&mut arm.body);
+}
+
+pub fn walk_index_mut<V: VisitorMut>(visitor: &mut V, index: &mut Index) {
+    visitor.visit_expr_mut(&mut index.expr);
+    visitor.visit_expr_mut(&mut index.index);
+    if let Some(end) = &mut index.end {
+        visitor.visit_expr_mut(end);
+    }
+}
+
+pub fn walk_concat_mut<V: VisitorMut>(visitor: &mut V, concat: &mut Concat) {
+    for part in concat.parts.iter_mut() {
+        visitor.visit_expr_mut(part);
+    }
+}
+
+pub fn walk_do_mut<V: VisitorMut>(visitor: &mut V, d: &mut Do) {
+    for stmt in d.stmts.iter_mut() {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Bind(_, expr, ..) | Stmt::Expr(expr) => visitor.visit_expr_mut(expr),
+    }
+}
@@ -1,4 +1,4 @@
-use snowc_parse::{parse, Expr};
+use snowc_parse::{parse, visit::walk_expr, Expr, Visitor};
 
 pub fn snapshot_parsing(input: &str) -> String {
     let ast = match parse(input) {
@@ -21,7 +21,7 @@ pub fn snapshot_parsing(input: &str) -> String {
                 ast.push_front(node);
                 break;
             }
-            output += format_node(&input, &node).as_str();
+            output += format_node(&node).as_str();
             output += "\n";
             // output += &" ".repeat(node.span().col_start);
             // output += &"^".repeat(node.span().len());
@@ -32,87 +32,25 @@ pub fn snapshot_parsing(input: &str) -> String {
     output
 }
 
-enum ExprVisitor<'a> {
-    Root,
-    Unary(&'a Expr),
-    Binary(&'a Expr, &'a Expr),
-    IfElse(&'a Expr, &'a Expr, &'a Expr),
-    Closure(&'a Expr, &'a Expr),
-    Func(&'a Expr),
-    App(&'a Expr, &'a [Expr]),
-    Array(&'a [Expr]),
+#[derive(Default)]
+struct NodeFormatter {
+    output: String,
 }
 
-fn get_inner_expr<'a>(expr: &'a Expr) -> ExprVisitor<'a> {
-    match expr {
-        Expr::Atom(..) => ExprVisitor::Root,
-        Expr::Unary(unary) => ExprVisitor::Unary(unary.expr.as_ref()),
-        Expr::Binary(binary) => {
-            ExprVisitor::Binary(binary.left.as_ref(), binary.right.as_ref())
-        }
-        Expr::IfElse(condition, then, r#else, ..) => {
-            ExprVisitor::IfElse(condition.as_ref(), then.as_ref(), r#else.as_ref())
-        }
-        Expr::Closure(head, tail, ..) => {
-            ExprVisitor::Closure(head.as_ref(), tail.as_ref())
-        }
-        Expr::Func(_, _, node, ..) => ExprVisitor::Func(node.as_ref()),
-        Expr::App(app) => ExprVisitor::App(app.name.as_ref(), &app.args),
-        Expr::Array(nodes, ..) => ExprVisitor::Array(nodes),
-        Expr::Enum(..) => ExprVisitor::Root,
-        Expr::Error(..) => ExprVisitor::Root,
+impl Visitor for NodeFormatter {
+    fn visit_expr(&mut self, node: &Expr) {
+        walk_expr(self, node);
+        self.output += &" ".repeat(node.span().col_start);
+        self.output += &"^".repeat(node.span().len());
+        self.output += &format!(" {node:?}");
+        self.output += "\n";
     }
 }
 
-fn format_node<'a>(src: &str, node: &'a Expr) -> String {
-    let mut result = String::new();
-    loop {
-        match get_inner_expr(node) {
-            ExprVisitor::Root => break,
-            ExprVisitor::Unary(expr) => {
-                result += format_node(src, expr).as_str();
-                break;
-            }
-            ExprVisitor::Binary(lhs, rhs) => {
-                result += format_node(src, lhs).as_str();
-                result += format_node(src, rhs).as_str();
-                break;
-            }
-            ExprVisitor::IfElse(cond, then, r#else) => {
-                result += format_node(src, cond).as_str();
-                result += format_node(src, then).as_str();
-                result += format_node(src, r#else).as_str();
-                break;
-            }
-            ExprVisitor::Closure(head, tail) => {
-                result += format_node(src, head).as_str();
-                result += format_node(src, tail).as_str();
-                break;
-            }
-            ExprVisitor::Func(body) => {
-                result += format_node(src, body).as_str();
-                break;
-            }
-            ExprVisitor::App(name, args) => {
-                result += format_node(src, name).as_str();
-                for arg in args {
-                    result += format_node(src, arg).as_str();
-                }
-                break;
-            }
-            ExprVisitor::Array(array) => {
-                for item in array {
-                    result += format_node(src, item).as_str();
-                }
-                break;
-            }
-        }
-    }
-    result += &" ".repeat(node.span().col_start);
-    result += &"^".repeat(node.span().len());
-    result += &format!(" {node:?}");
-    result += "\n";
-    result
+fn format_node(node: &Expr) -> String {
+    let mut formatter = NodeFormatter::default();
+    formatter.visit_expr(node);
+    formatter.output
 }
 
 macro_rules! snapshot {
@@ -131,3 +69,13 @@ macro_rules! snapshot {
 
 snapshot!(hello_world, "./../../../samples/hello_world.snow");
 snapshot!(rule110, "./../../../samples/rule110.snow");
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_json_round_trip() {
+    let contents = include_str!("./../../../samples/hello_world.snow");
+    let ast = parse(contents).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let back: Vec<Expr> = serde_json::from_str(&json).unwrap();
+    assert_eq!(ast, back);
+}
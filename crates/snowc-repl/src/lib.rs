@@ -13,12 +13,17 @@ const HELP_MESSAGE: &str = "Help Commands
 :scope            show what is in scope
 :history          shows history from prompt
 :load <filename>  loads a snow file
+:type <expr>      shows the type of an expression
 ";
 
+use snowc_parse::Expr;
 use snowc_tree_walker::{eval_expr_with_scope, Scope, Value};
+use snowc_type_checker::Checked;
 
-pub fn repl() -> Result<()> {
-    let mut repl = Repl::new();
+/// Runs the repl, `type_check` is off when snowc was started with
+/// `--dynamic`.
+pub fn repl(type_check: bool) -> Result<()> {
+    let mut repl = Repl::new(type_check);
     let mut terminal = Terminal::new()?;
     let mut scope = Scope::default();
 
@@ -91,6 +96,14 @@ fn compile(
     if ast.iter().any(|x| x.is_error()) {
         return Ok(None);
     }
+    if repl.type_check {
+        type_check(repl, &ast).map_err(|errors| {
+            errors
+                .iter()
+                .map(|err| err.report(&filename, input))
+                .collect::<Vec<_>>()
+        })?;
+    }
 
     let mut results = vec![];
     for node in ast {
@@ -102,6 +115,48 @@ fn compile(
     Ok(results.pop().flatten())
 }
 
+/// Checks a line against everything defined before it. A line that is an
+/// expression is checked as the body of a function named `it`.
+fn type_check(
+    repl: &Repl,
+    ast: &[Expr],
+) -> std::result::Result<Checked, Vec<snowc_type_checker::TypeError>> {
+    let mut program = repl.declarations.clone();
+    for expr in ast.iter() {
+        match expr {
+            Expr::Func(..)
+            | Expr::Data(..)
+            | Expr::Alias(..)
+            | Expr::Class(..)
+            | Expr::Instance(..) => program.push(expr.clone()),
+            expr => program.push(Expr::Func(
+                "it".into(),
+                vec![],
+                Box::new(expr.clone()),
+                expr.span(),
+            )),
+        }
+    }
+    snowc_type_checker::type_check(&program)
+}
+
+/// The signature `input` would have as the body of `it`.
+fn type_of(repl: &Repl, input: &str) -> std::result::Result<String, String> {
+    let expr =
+        snowc_parse::expression(input).map_err(|err| err.report("snowc", input))?;
+    let checked = type_check(repl, &[expr]).map_err(|errors| {
+        errors
+            .iter()
+            .map(|err| err.report("snowc", input))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    Ok(checked
+        .signature_of("it")
+        .expect("`it` was checked")
+        .to_string())
+}
+
 fn execute_return_command(
     terminal: &mut Terminal,
     repl: &mut Repl,
@@ -156,18 +211,22 @@ fn execute_builtin_repl_command(
                 terminal.new_line()?;
                 return Ok(true);
             }
+            repl.declare(&src);
             repl.loaded_file = Some((filename.to_string(), src));
             terminal.print(&format!("loaded file {}", &i[6..]))?;
             terminal.new_line()?;
             Ok(true)
         }
-        ":scope" => {
-            terminal.print("LOCALS")?;
-            terminal.new_line()?;
-            for (k, v) in scope.local.iter() {
-                terminal.print(&format!("{k}: {v}"))?;
-                terminal.new_line()?;
+        i if i.starts_with(":type") => {
+            let input = i[5..].trim();
+            match type_of(repl, input) {
+                Ok(ty) => terminal.print(&format!("{input} : {}", ty.yellow()))?,
+                Err(error) => terminal.print(&error)?,
             }
+            terminal.new_line()?;
+            Ok(true)
+        }
+        ":scope" => {
             terminal.print("GLOBALS")?;
             terminal.new_line()?;
             for (k, v) in scope.global.iter() {
@@ -207,25 +266,47 @@ struct Repl {
     pos: Pos<usize>,
     compiled_lines: String,
     loaded_file: Option<(String, String)>,
+    /// Every definition so far, later lines are type checked against them.
+    declarations: Vec<Expr>,
+    type_check: bool,
     // history: History,
 }
 
 impl Repl {
-    fn new() -> Self {
+    fn new(type_check: bool) -> Self {
         Self {
             input: String::new(),
             running: true,
             pos: Pos::default(),
             compiled_lines: String::new(),
             loaded_file: None,
+            declarations: vec![],
+            type_check,
         }
     }
 
     fn successful_compiled_line(&mut self) {
+        self.declare(&self.input.clone());
         self.compiled_lines += &self.input;
         self.compiled_lines += "\n";
     }
 
+    /// Remembers the definitions in `src`, replacing earlier ones of the
+    /// same name.
+    fn declare(&mut self, src: &str) {
+        let Ok(ast) = snowc_parse::parse(src) else {
+            return;
+        };
+        for expr in ast {
+            let Some(name) = declared_name(&expr) else {
+                continue;
+            };
+            self.declarations
+                .retain(|old| declared_name(old).as_ref() != Some(&name));
+            self.declarations.push(expr);
+        }
+    }
+
     fn insert_char(&mut self, c: char) {
         self.input.insert(self.pos.x, c);
         self.pos.x += 1;
@@ -274,3 +355,19 @@ impl Repl {
         self.running = false;
     }
 }
+
+/// What a new definition replaces, an instance replaces the one for the
+/// same class and type.
+fn declared_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Func(name, ..) => Some(name.clone()),
+        Expr::Data(data) => Some(data.name.clone()),
+        Expr::Alias(alias) => Some(alias.name.clone()),
+        Expr::Class(class) => Some(class.name.clone()),
+        Expr::Instance(instance) => Some(format!(
+            "instance {} {}",
+            instance.class, instance.type_info
+        )),
+        _ => None,
+    }
+}
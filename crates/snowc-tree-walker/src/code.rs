@@ -0,0 +1,242 @@
+use snowc_parse::{expr, Atom, Expr, Op, Pat, Span};
+use std::{fmt, rc::Rc};
+
+/// An expression ready to run. Every part sits behind an `Rc` so the
+/// evaluator can hold on to the part it comes back to and a closure shares
+/// its body with the code it was made from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Code {
+    Atom(Atom),
+    Unary(Op, Rc<Self>, Span),
+    Binary(Op, Rc<Self>, Rc<Self>, Span),
+    IfElse(Rc<Self>, Rc<Self>, Rc<Self>, Span),
+    /// `\a -> \b -> body` with all of its parameters at once.
+    Closure(Vec<String>, Rc<Self>, Span),
+    /// The function first, then its arguments.
+    App(Vec<Rc<Self>>, Span),
+    Array(Vec<Rc<Self>>, Span),
+    Match(Vec<Rc<Self>>, Vec<Arm>, Span),
+    Do(Vec<Stmt>, Span),
+    Index(Rc<Self>, Rc<Self>, Option<Rc<Self>>, Span),
+    Concat(Vec<Rc<Self>>, Span),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Arm {
+    pub pats: Vec<Pat>,
+    pub guard: Option<Rc<Code>>,
+    pub body: Rc<Code>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Stmt {
+    Bind(String, Rc<Code>),
+    Expr(Rc<Code>),
+}
+
+impl Stmt {
+    pub fn code(&self) -> &Rc<Code> {
+        match self {
+            Self::Bind(_, code) | Self::Expr(code) => code,
+        }
+    }
+}
+
+impl Code {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Atom(atom) => atom.span(),
+            Self::Unary(.., span)
+            | Self::Binary(.., span)
+            | Self::IfElse(.., span)
+            | Self::Closure(.., span)
+            | Self::App(.., span)
+            | Self::Array(.., span)
+            | Self::Match(.., span)
+            | Self::Do(.., span)
+            | Self::Index(.., span)
+            | Self::Concat(.., span) => *span,
+        }
+    }
+
+    /// The parts run before the expression itself, in the order they run.
+    /// `if` only runs its condition up front, the branch it takes runs in
+    /// its place.
+    pub fn part(&self, idx: usize) -> Option<&Rc<Self>> {
+        match self {
+            Self::Unary(_, expr, _) => [expr].get(idx).copied(),
+            Self::Binary(_, left, right, _) => [left, right].get(idx).copied(),
+            Self::IfElse(condition, ..) => [condition].get(idx).copied(),
+            Self::Index(expr, index, end, _) => {
+                [Some(expr), Some(index), end.as_ref()].get(idx).copied()?
+            }
+            Self::App(parts, _)
+            | Self::Array(parts, _)
+            | Self::Match(parts, ..)
+            | Self::Concat(parts, _) => parts.get(idx),
+            Self::Atom(..) | Self::Closure(..) | Self::Do(..) => None,
+        }
+    }
+}
+
+/// Lowers a parsed expression to [`Code`].
+pub fn lower(expr: &Expr) -> Rc<Code> {
+    let code = match expr {
+        Expr::Atom(atom) => Code::Atom(atom.clone()),
+        Expr::Unary(unary) => Code::Unary(unary.op, lower(&unary.expr), unary.span),
+        Expr::Binary(binary) => Code::Binary(
+            binary.op,
+            lower(&binary.left),
+            lower(&binary.right),
+            binary.span,
+        ),
+        Expr::IfElse(condition, then, r#else, span) => {
+            Code::IfElse(lower(condition), lower(then), lower(r#else), *span)
+        }
+        Expr::Closure(.., span) => {
+            let mut params = vec![];
+            let mut body = expr;
+            while let Expr::Closure(head, tail, ..) = body {
+                let Expr::Atom(Atom::Id(name, ..)) = head.as_ref() else {
+                    unreachable!("closure parameters are always names");
+                };
+                params.push(name.clone());
+                body = tail;
+            }
+            Code::Closure(params, lower(body), *span)
+        }
+        Expr::App(app) => {
+            let parts = std::iter::once(app.name.as_ref())
+                .chain(app.args.iter())
+                .map(lower)
+                .collect();
+            Code::App(parts, app.span)
+        }
+        Expr::Array(items, ..) => {
+            let start = items.first().map(Expr::span).unwrap_or_default();
+            let end = items.last().map(Expr::span).unwrap_or_default();
+            Code::Array(items.iter().map(lower).collect(), Span::from((start, end)))
+        }
+        Expr::Match(m) => {
+            let arms = m
+                .arms
+                .iter()
+                .map(|arm| Arm {
+                    pats: arm.pats.clone(),
+                    guard: arm.guard.as_ref().map(lower),
+                    body: lower(&arm.body),
+                })
+                .collect();
+            Code::Match(m.exprs.iter().map(lower).collect(), arms, m.span)
+        }
+        Expr::Do(d) => {
+            let stmts = d
+                .stmts
+                .iter()
+                .map(|stmt| match stmt {
+                    expr::Stmt::Bind(name, expr, ..) => {
+                        Stmt::Bind(name.clone(), lower(expr))
+                    }
+                    expr::Stmt::Expr(expr) => Stmt::Expr(lower(expr)),
+                })
+                .collect();
+            Code::Do(stmts, d.span)
+        }
+        Expr::Index(index) => Code::Index(
+            lower(&index.expr),
+            lower(&index.index),
+            index.end.as_deref().map(lower),
+            index.span,
+        ),
+        Expr::Concat(concat) => {
+            Code::Concat(concat.parts.iter().map(lower).collect(), concat.span)
+        }
+        Expr::Alias(..) | Expr::Data(..) | Expr::Class(..) | Expr::Instance(..) => {
+            unreachable!("type declaration")
+        }
+        Expr::Func(..) => unreachable!("func"),
+        Expr::Error(..) => unreachable!("error"),
+    };
+    Rc::new(code)
+}
+
+/// Prints the same way as the [`Expr`] it was lowered from.
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Atom(atom) => write!(f, "{atom}"),
+            Self::Unary(op, expr, _) => write!(f, "({op} {expr})"),
+            Self::Binary(op, left, right, _) => write!(f, "({op} {left} {right})"),
+            Self::IfElse(condition, then, r#else, _) => {
+                write!(f, "(if ({condition}) then {then} else {else})")
+            }
+            Self::Closure(params, body, _) => {
+                for param in params.iter() {
+                    write!(f, "(\\{param} -> ")?;
+                }
+                write!(f, "{body}")?;
+                write!(f, "{}", ")".repeat(params.len()))
+            }
+            Self::App(parts, _) => {
+                write!(f, "<{}: (", parts[0])?;
+                write_separated(f, &parts[1..], ", ", |f, arg| write!(f, "{arg}"))?;
+                write!(f, ")>")
+            }
+            Self::Array(items, _) => {
+                write!(f, "[")?;
+                write_separated(f, items, ", ", |f, item| write!(f, "{item}"))?;
+                write!(f, "]")
+            }
+            Self::Match(exprs, arms, _) => {
+                write!(f, "(match ")?;
+                write_separated(f, exprs, ", ", |f, e| write!(f, "{e}"))?;
+                for arm in arms.iter() {
+                    write!(f, " | ")?;
+                    write_separated(f, &arm.pats, ", ", |f, p| write!(f, "{p}"))?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {guard}")?;
+                    }
+                    write!(f, " -> {}", arm.body)?;
+                }
+                write!(f, ")")
+            }
+            Self::Do(stmts, _) => {
+                write!(f, "(do {{ ")?;
+                write_separated(f, stmts, "; ", |f, stmt| match stmt {
+                    Stmt::Bind(name, code) => write!(f, "{name} <- {code}"),
+                    Stmt::Expr(code) => write!(f, "{code}"),
+                })?;
+                write!(f, " }})")
+            }
+            Self::Index(expr, index, Some(end), _) => write!(f, "{expr}[{index}..{end}]"),
+            Self::Index(expr, index, None, _) => write!(f, "{expr}[{index}]"),
+            Self::Concat(parts, _) => {
+                write!(f, "\"")?;
+                for part in parts.iter() {
+                    match part.as_ref() {
+                        Self::Atom(Atom::String(s, ..)) => {
+                            write!(f, "{}", s.replace('{', "\\{"))?
+                        }
+                        part => write!(f, "{{{part}}}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+        }
+    }
+}
+
+fn write_separated<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    separator: &str,
+    write_item: impl Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i != 0 {
+            write!(f, "{separator}")?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use super::{Thunk, Value};
+use std::rc::Rc;
+
+/// The local bindings of one function call, `match` arm or `do` line. Each
+/// frame points at the frame it was made in, a closure keeps the frame it was
+/// created in alive and sees every name that was in scope there.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Env {
+    vars: Vec<(String, Value)>,
+    parent: Option<Rc<Env>>,
+}
+
+impl Env {
+    /// A frame with `vars` in front of everything `parent` can see.
+    pub fn extend(
+        parent: &Rc<Self>,
+        vars: impl IntoIterator<Item = (String, Value)>,
+    ) -> Rc<Self> {
+        let vars = vars.into_iter().collect::<Vec<_>>();
+        if vars.is_empty() {
+            return parent.clone();
+        }
+        Rc::new(Self {
+            vars,
+            parent: Some(parent.clone()),
+        })
+    }
+
+    /// The innermost binding of `name`.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let mut env = self;
+        loop {
+            if let Some((_, value)) = env.vars.iter().rev().find(|(var, _)| var == name) {
+                return Some(value);
+            }
+            env = env.parent.as_deref()?;
+        }
+    }
+
+    /// Every binding from the innermost frame out, shadowed ones included.
+    pub fn iter(&self) -> impl Iterator<Item = &(String, Value)> {
+        let mut frames = vec![];
+        let mut env = Some(self);
+        while let Some(frame) = env {
+            frames.push(frame);
+            env = frame.parent.as_deref();
+        }
+        frames.into_iter().flat_map(|frame| frame.vars.iter().rev())
+    }
+}
+
+/// A thunk keeps the frame it was made in alive, so a lazy program can build
+/// a chain of frames as long as it ran. Dropping one frame at a time keeps
+/// the end of such a chain off the Rust stack.
+impl Drop for Env {
+    fn drop(&mut self) {
+        let mut envs = self.parent.take().into_iter().collect::<Vec<_>>();
+        let mut values = std::mem::take(&mut self.vars)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        loop {
+            if let Some(value) = values.pop() {
+                match value {
+                    Value::Thunk(thunk) => {
+                        if let Ok(thunk) = Rc::try_unwrap(thunk) {
+                            match thunk.into_inner() {
+                                Thunk::Pending(_, env) => envs.push(env),
+                                Thunk::Done(value) => values.push(value),
+                            }
+                        }
+                    }
+                    Value::Closure { env, .. } => envs.push(env),
+                    Value::Array(items, _) | Value::Variant(_, items, _) => {
+                        values.extend(items)
+                    }
+                    _ => {}
+                }
+            } else if let Some(env) = envs.pop() {
+                // a frame still used elsewhere is dropped by its last owner
+                if let Ok(mut env) = Rc::try_unwrap(env) {
+                    envs.extend(env.parent.take());
+                    values.extend(
+                        std::mem::take(&mut env.vars).into_iter().map(|(_, v)| v),
+                    );
+                }
+            } else {
+                break;
+            }
+        }
+    }
+}
@@ -19,9 +19,65 @@ pub enum RuntimeError {
     InvalidBinaryOp(Span),
     #[error("empty array")]
     EmptyArray(Span),
+    #[error("no clause matched the arguments")]
+    NoMatchingClause(Span),
+    #[error("expected a Bool")]
+    ExpectedBool(Span),
+    #[error("only functions can be applied to arguments")]
+    NotAFunction(Span),
+    #[error("actions can only run in main or in functions that give back IO")]
+    EffectInPureFunction(Span),
+    #[error("recursion went too deep")]
+    RecursionLimit(Span),
+    #[error("a range can't step by 0")]
+    ZeroStep(Span),
 }
 
 impl RuntimeError {
+    /// Where the error happened, nowhere for a missing `main`.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::MissingMainFunction => Span::default(),
+            Self::Undefined(_, span)
+            | Self::InvalidArguments(span)
+            | Self::IdxOutOfBounds(span)
+            | Self::InvalidBinaryOp(span)
+            | Self::EmptyArray(span)
+            | Self::NoMatchingClause(span)
+            | Self::ExpectedBool(span)
+            | Self::NotAFunction(span)
+            | Self::EffectInPureFunction(span)
+            | Self::RecursionLimit(span)
+            | Self::ZeroStep(span) => *span,
+        }
+    }
+
+    /// Points an error that doesn't know where it happened, one a native
+    /// gave without a span, at `span`.
+    pub(crate) fn or_at(self, span: Span) -> Self {
+        let or_at = |found: Span| {
+            if found == Span::default() {
+                span
+            } else {
+                found
+            }
+        };
+        match self {
+            Self::Undefined(name, found) => Self::Undefined(name, or_at(found)),
+            Self::MissingMainFunction => Self::MissingMainFunction,
+            Self::InvalidArguments(found) => Self::InvalidArguments(or_at(found)),
+            Self::IdxOutOfBounds(found) => Self::IdxOutOfBounds(or_at(found)),
+            Self::InvalidBinaryOp(found) => Self::InvalidBinaryOp(or_at(found)),
+            Self::EmptyArray(found) => Self::EmptyArray(or_at(found)),
+            Self::NoMatchingClause(found) => Self::NoMatchingClause(or_at(found)),
+            Self::ExpectedBool(found) => Self::ExpectedBool(or_at(found)),
+            Self::NotAFunction(found) => Self::NotAFunction(or_at(found)),
+            Self::EffectInPureFunction(found) => Self::EffectInPureFunction(or_at(found)),
+            Self::RecursionLimit(found) => Self::RecursionLimit(or_at(found)),
+            Self::ZeroStep(found) => Self::ZeroStep(or_at(found)),
+        }
+    }
+
     pub fn report(&self, filename: &str, src: &str) -> String {
         match self {
             Self::Undefined(name, span) => {
@@ -50,7 +106,13 @@ impl RuntimeError {
                 let snippet = snippet_builder(filename, src, &label, *span);
                 DisplayList::from(snippet).to_string()
             }
-            Self::EmptyArray(span) => {
+            Self::EmptyArray(span)
+            | Self::NoMatchingClause(span)
+            | Self::ExpectedBool(span)
+            | Self::NotAFunction(span)
+            | Self::EffectInPureFunction(span)
+            | Self::RecursionLimit(span)
+            | Self::ZeroStep(span) => {
                 let label = self.to_string();
                 let snippet = snippet_builder(filename, src, &label, *span);
                 DisplayList::from(snippet).to_string()
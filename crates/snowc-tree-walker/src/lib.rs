@@ -1,18 +1,40 @@
+mod code;
+mod env;
 mod error;
+mod machine;
+mod native;
 #[cfg(test)]
 mod tests;
+mod trace;
 mod value;
+use code::lower;
+pub use code::Code;
+pub use env::Env;
 pub use error::RuntimeError;
+use machine::{apply, force, run, whnf};
+pub use native::{Native, NativeFn};
 use snowc_parse::{
-    expr::{App, Binary},
-    Atom, Expr, Op, Span, TokenPosition, Unary,
+    builtin::{classes, lookup, operator_method, prelude},
+    expr::{App, Binary, Class, Data, Instance, Pat},
+    Atom, Expr, Extern, Op, Span, TokenPosition, TypeInfo,
 };
-use std::collections::HashMap;
-pub use value::Value;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+};
+pub use trace::{EvalObserver, Tracer};
+pub use value::{Thunk, Value};
 
-type Env = HashMap<String, Expr>;
 type Result<T> = std::result::Result<T, RuntimeError>;
 
+/// The default for [`Scope::max_depth`].
+pub const MAX_DEPTH: usize = 1_000_000;
+/// The default for [`Scope::max_nested`].
+pub const MAX_NESTED: usize = 64;
+
 fn builtin(op: Op) -> Expr {
     let pos = TokenPosition::Middle;
     let span = Span::default();
@@ -30,418 +52,683 @@ fn builtin(op: Op) -> Expr {
     Expr::Closure(right, Box::new(x), span)
 }
 
-#[derive(Debug, Clone)]
+/// A class method as a function that picks the instance once it has all of
+/// its arguments, so it can be passed around like any other.
+/// ```hs
+/// eq = (\$0 -> (\$1 -> $method "eq" $0 $1))
+/// ```
+fn method_stub(name: &str, arity: usize) -> Expr {
+    let pos = TokenPosition::Middle;
+    let span = Span::default();
+    let params = (0..arity)
+        .map(|i| Expr::Atom(Atom::Id(format!("${i}"), pos, span)))
+        .collect::<Vec<_>>();
+    let mut args = vec![Expr::Atom(Atom::String(name.into(), pos, span))];
+    args.extend(params.iter().cloned());
+    let body = Expr::App(App {
+        name: Box::new(Expr::Atom(Atom::Id("$method".into(), pos, span))),
+        args,
+        pos,
+        span,
+    });
+    params.into_iter().rev().fold(body, |body, param| {
+        Expr::Closure(Box::new(param), Box::new(body), span)
+    })
+}
+
+/// Everything defined at the top level. Nothing in it changes while a
+/// program runs, local bindings live in [`Env`].
+#[derive(Clone)]
 pub struct Scope {
-    pub local: Env,
-    pub global: Env,
+    /// Every top level function, as a closure over no bindings.
+    pub global: HashMap<String, Value>,
+    /// Every `data` constructor in scope and how many fields it takes.
+    pub constructors: HashMap<String, usize>,
+    /// The `data` type each constructor builds.
+    pub types: HashMap<String, String>,
+    /// The signature of every class method.
+    pub methods: HashMap<String, Vec<TypeInfo>>,
+    /// Method name to the `data` types with an instance of it and the
+    /// function the instance gives.
+    pub instances: HashMap<String, HashMap<String, Value>>,
+    /// Functions written in Rust, see [`Scope::register`].
+    pub natives: HashMap<String, Native>,
+    /// Set while running a function whose signature doesn't give back `IO`,
+    /// an action performed then is an error.
+    pub pure: Cell<bool>,
+    /// How many frames the evaluator may stack up for calls that aren't
+    /// tail calls before it gives up with [`RuntimeError::RecursionLimit`].
+    pub max_depth: usize,
+    /// How many evaluations builtins may start inside one another, `print`
+    /// starts one to show a value with a `Show` instance.
+    pub max_nested: usize,
+    /// Arguments and array items only run once something needs their
+    /// value, a match on them, an operator, a builtin or the end of the
+    /// program.
+    pub lazy: bool,
+    /// Told about every call to a named function and what it gave back.
+    pub observer: Option<Rc<RefCell<dyn EvalObserver>>>,
+    nested: Cell<usize>,
+    /// How many of the calls the observer heard about haven't returned.
+    calls: Cell<usize>,
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scope")
+            .field("global", &self.global)
+            .field("constructors", &self.constructors)
+            .field("types", &self.types)
+            .field("methods", &self.methods)
+            .field("instances", &self.instances)
+            .field("natives", &self.natives)
+            .field("pure", &self.pure)
+            .field("max_depth", &self.max_depth)
+            .field("max_nested", &self.max_nested)
+            .field("lazy", &self.lazy)
+            .field("observer", &self.observer.is_some())
+            .finish_non_exhaustive()
+    }
 }
 
 impl Scope {
-    pub fn get(&self, name: &str) -> Option<&Expr> {
-        self.local.get(name).or(self.global.get(name))
+    /// Adds a function written in Rust. It takes the place of any builtin or
+    /// other native of the same name, a local or a parameter of the same
+    /// name still hides it.
+    pub fn register(&mut self, native: Native) {
+        self.natives.insert(native.name.clone(), native);
+    }
+
+    /// The natives a program checked against this scope can call on top of
+    /// the builtins, and the builtins a native with a signature replaced.
+    pub fn externs(&self) -> Vec<Extern> {
+        self.natives
+            .values()
+            .filter(|native| native.signature.is_some() || lookup(&native.name).is_none())
+            .map(|native| Extern {
+                name: native.name.clone(),
+                arity: native.arity,
+                signature: native.signature.clone(),
+            })
+            .collect()
     }
 
-    fn insert_global(&mut self, k: String, v: Expr) {
-        self.global.insert(k, v);
+    /// Adds every top level definition in `ast`, a definition with the name
+    /// of one already in scope takes its place.
+    pub fn define(&mut self, ast: &[Expr]) {
+        for expr in ast.iter() {
+            match expr {
+                Expr::Func(name, type_info, closure, ..) => {
+                    self.insert_func(name, type_info, closure);
+                }
+                Expr::Data(data) => self.insert_data(data),
+                Expr::Class(class) => self.insert_class(class),
+                Expr::Instance(..) | Expr::Alias(..) => {}
+                _ => unreachable!("{:?}", expr),
+            }
+        }
+        // after every class so instances know the signatures of their methods
+        for expr in ast.iter() {
+            if let Expr::Instance(instance) = expr {
+                self.insert_instance(instance);
+            }
+        }
     }
-    fn insert_local(&mut self, k: String, v: Expr) {
-        self.local.insert(k, v);
+
+    /// Calls the top level function, constructor or native `name` with
+    /// `args` and gives back its value in full. Without arguments it gives
+    /// the value of `name`.
+    pub fn call(&self, name: &str, args: Vec<Value>) -> Result<Value> {
+        let pos = TokenPosition::Middle;
+        let code = Code::Atom(Atom::Id(name.into(), pos, Span::default()));
+        let mut value = run(Rc::new(code), Rc::default(), self)?;
+        if !args.is_empty() {
+            value = apply(value, args, self)?;
+        }
+        deep_force(value, self)
+    }
+
+    fn insert_global(&mut self, name: String, expr: &Expr) {
+        let value = closure(lower(expr), &Rc::default(), Some(&name), expr.span());
+        self.global.insert(name, value);
+    }
+
+    fn insert_func(&mut self, name: &str, type_info: &[TypeInfo], body: &Expr) {
+        self.insert_global(name.into(), &pure_unless_io(type_info, body));
+    }
+
+    fn insert_data(&mut self, data: &Data) {
+        for variant in data.variants.iter() {
+            self.constructors
+                .insert(variant.name.clone(), variant.fields.len());
+            self.types.insert(variant.name.clone(), data.name.clone());
+        }
+    }
+
+    fn insert_class(&mut self, class: &Class) {
+        for method in class.methods.iter() {
+            self.methods
+                .insert(method.name.clone(), method.type_info.clone());
+            // a method without parameters has nothing to pick an instance by
+            let arity = method.type_info.len() - 1;
+            // the instance it picks is the function observers hear about
+            if arity > 0 {
+                let stub = lower(&method_stub(&method.name, arity));
+                let value = closure(stub, &Rc::default(), None, Span::default());
+                self.global.insert(method.name.clone(), value);
+            }
+        }
+    }
+
+    fn insert_instance(&mut self, instance: &Instance) {
+        let Some(type_name) = instance.type_name() else {
+            return;
+        };
+        for method in instance.methods.iter() {
+            let Expr::Func(name, _, body, span) = method else {
+                continue;
+            };
+            let type_info = self.methods.get(name).cloned().unwrap_or_default();
+            let func = closure(
+                lower(&pure_unless_io(&type_info, body)),
+                &Rc::default(),
+                Some(name),
+                *span,
+            );
+            self.instances
+                .entry(name.clone())
+                .or_default()
+                .insert(type_name.into(), func);
+        }
     }
 }
+
 impl Default for Scope {
     fn default() -> Self {
         let mut scope = Self {
-            local: Env::default(),
-            global: Env::default(),
+            global: HashMap::default(),
+            constructors: HashMap::default(),
+            types: HashMap::default(),
+            methods: HashMap::default(),
+            instances: HashMap::default(),
+            natives: HashMap::default(),
+            pure: Cell::new(false),
+            max_depth: MAX_DEPTH,
+            max_nested: MAX_NESTED,
+            lazy: false,
+            observer: None,
+            nested: Cell::new(0),
+            calls: Cell::new(0),
         };
-        scope.insert_global("(+)".into(), builtin(Op::Plus));
-        scope.insert_global("(-)".into(), builtin(Op::Minus));
-        scope.insert_global("(*)".into(), builtin(Op::Mult));
-        scope.insert_global("(/)".into(), builtin(Op::Div));
+        for class in classes().iter() {
+            scope.insert_class(class);
+        }
+        // like the builtins in Rust, observers don't hear about them
+        for expr in prelude().iter() {
+            let Expr::Func(name, _, body, span) = expr else {
+                unreachable!("the prelude only defines functions");
+            };
+            let value = closure(lower(body), &Rc::default(), None, *span);
+            scope.global.insert(name.clone(), value);
+        }
+        // their signatures live with the ones the type checker reads
+        for mut native in native::builtins() {
+            native.signature = lookup(&native.name).map(|b| b.signature.into());
+            scope.register(native);
+        }
+        scope.insert_global("(+)".into(), &builtin(Op::Plus));
+        scope.insert_global("(-)".into(), &builtin(Op::Minus));
+        scope.insert_global("(*)".into(), &builtin(Op::Mult));
+        scope.insert_global("(/)".into(), &builtin(Op::Div));
         scope
     }
 }
 
-fn expr_unary(unary: &Unary, scope: &Scope) -> Result<Value> {
-    let Unary { op, expr, .. } = unary;
-    let atom = walk_expr(expr, scope)?;
-    match (op, atom) {
-        (Op::Minus, Value::Int(int, span)) => Ok(Value::Int(-int, span)),
-        (Op::Not, Value::Bool(b, span)) => Ok(Value::Bool(!b, span)),
-        _ => unimplemented!("for operator '{op:?}'"),
+fn gives_io(type_info: &TypeInfo) -> bool {
+    match type_info {
+        TypeInfo::Custom(name) | TypeInfo::Apply(name, ..) => name == "IO",
+        TypeInfo::Func(_, ret) => gives_io(ret),
+        _ => false,
     }
 }
 
-fn expr_binary(binary: &Binary, scope: &Scope) -> Result<Value> {
-    let Binary {
-        op,
-        left,
-        right,
-        span,
-        ..
-    } = binary;
-    let lhs_atom = walk_expr(left, scope)?;
-    let rhs_atom = walk_expr(right, scope)?;
-    match (op, lhs_atom, rhs_atom) {
+/// The body of a function, made to run with [`Scope::pure`] set unless its
+/// signature gives back `IO`.
+fn pure_unless_io(type_info: &[TypeInfo], body: &Expr) -> Expr {
+    match type_info.last() {
+        Some(ret) if !gives_io(ret) => mark_pure(body.clone()),
+        _ => body.clone(),
+    }
+}
+
+/// Wraps the body of a function under its parameters so it runs with
+/// [`Scope::pure`] set.
+fn mark_pure(expr: Expr) -> Expr {
+    match expr {
+        Expr::Closure(head, tail, span) => {
+            Expr::Closure(head, Box::new(mark_pure(*tail)), span)
+        }
+        body => {
+            let span = body.span();
+            let pos = body.position();
+            Expr::App(App {
+                name: Box::new(Expr::Atom(Atom::Id("$pure".into(), pos, span))),
+                args: vec![body],
+                pos,
+                span,
+            })
+        }
+    }
+}
+
+/// `lhs op rhs` once both sides are values. A `data` value goes to the
+/// method the operator stands for, see [`operator_method`].
+fn binary_value(
+    op: Op,
+    lhs: Value,
+    rhs: Value,
+    span: Span,
+    scope: &Scope,
+) -> Result<Value> {
+    let is_data = |value: &Value| matches!(value, Value::Variant(..));
+    if let Some(method) = operator_method(op).filter(|_| is_data(&lhs) || is_data(&rhs)) {
+        return operator_instance(op, method, lhs, rhs, span, scope);
+    }
+    match (op, lhs, rhs) {
         (Op::Plus, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Int(lhs + rhs, *span))
+            Ok(Value::Int(lhs + rhs, span))
         }
         (Op::Plus, Value::String(lhs, ..), Value::String(rhs, ..)) => {
-            Ok(Value::String(format!("{lhs}{rhs}"), *span))
+            Ok(Value::String(format!("{lhs}{rhs}"), span))
         }
         (Op::Plus, Value::Array(lhs, ..), Value::Array(rhs, ..)) => Ok(Value::Array(
             lhs.iter().cloned().chain(rhs.iter().cloned()).collect(),
-            *span,
+            span,
         )),
         (Op::Minus, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Int(lhs - rhs, *span))
+            Ok(Value::Int(lhs - rhs, span))
         }
         (Op::Mult, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Int(lhs * rhs, *span))
+            Ok(Value::Int(lhs * rhs, span))
         }
         (Op::Div, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Int(lhs / rhs, *span))
+            Ok(Value::Int(lhs / rhs, span))
         }
         (Op::Mod, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Int(lhs % rhs, *span))
-        }
-        (Op::Grt, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs > rhs, *span))
-        }
-        (Op::GrtEq, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs >= rhs, *span))
-        }
-        (Op::Les, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs < rhs, *span))
-        }
-        (Op::LesEq, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs <= rhs, *span))
-        }
-        (Op::Eq, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs == rhs, *span))
-        }
-        (Op::Neq, Value::Int(lhs, ..), Value::Int(rhs, ..)) => {
-            Ok(Value::Bool(lhs != rhs, *span))
-        }
-        (Op::Eq, Value::String(lhs, ..), Value::String(rhs, ..)) => {
-            Ok(Value::Bool(lhs == rhs, *span))
-        }
-        (Op::Neq, Value::String(lhs, ..), Value::String(rhs, ..)) => {
-            Ok(Value::Bool(lhs != rhs, *span))
+            Ok(Value::Int(lhs % rhs, span))
         }
         (Op::And, Value::Bool(lhs, ..), Value::Bool(rhs, ..)) => {
-            Ok(Value::Bool(lhs && rhs, *span))
+            Ok(Value::Bool(lhs && rhs, span))
         }
         (Op::Or, Value::Bool(lhs, ..), Value::Bool(rhs, ..)) => {
-            Ok(Value::Bool(lhs || rhs, *span))
+            Ok(Value::Bool(lhs || rhs, span))
+        }
+        // arrays are equal when their items are, which may be `data` values
+        (Op::Eq | Op::Neq, Value::Array(lhs, ..), Value::Array(rhs, ..)) => {
+            let mut equal = lhs.len() == rhs.len();
+            for (lhs, rhs) in lhs.into_iter().zip(rhs) {
+                if !equal {
+                    break;
+                }
+                let (lhs, rhs) = (whnf(lhs, scope)?, whnf(rhs, scope)?);
+                equal = binary_value(Op::Eq, lhs, rhs, span, scope)?.is_true();
+            }
+            Ok(Value::Bool(equal == (op == Op::Eq), span))
+        }
+        (op, lhs, rhs) => {
+            let Some(ordering) = compare(&lhs, &rhs) else {
+                return Err(RuntimeError::InvalidBinaryOp(span));
+            };
+            let result = match op {
+                Op::Eq => ordering.is_eq(),
+                Op::Neq => ordering.is_ne(),
+                Op::Grt => ordering.is_gt(),
+                Op::GrtEq => ordering.is_ge(),
+                Op::Les => ordering.is_lt(),
+                Op::LesEq => ordering.is_le(),
+                _ => return Err(RuntimeError::InvalidBinaryOp(span)),
+            };
+            Ok(Value::Bool(result, span))
         }
-        (_, _, _) => Err(RuntimeError::InvalidBinaryOp(*span)),
     }
 }
 
-fn expr_conditional(
-    condition: &Expr,
-    then: &Expr,
-    r#else: &Expr,
-    scope: &Scope,
-) -> Result<Value> {
-    eprintln!("condition: {condition} | {:?}", _typeofexpr(condition));
-    eprintln!("then: {then} | {:?}", _typeofexpr(then));
-    eprintln!("else: {} | {:?}", r#else, _typeofexpr(r#else));
-    match walk_expr(condition, scope)? {
-        Value::Bool(true, _) => walk_expr(then, scope),
-        Value::Bool(false, _) => walk_expr(r#else, scope),
-        _ => unreachable!(),
+/// How two values of the same builtin type compare.
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Int(lhs, ..), Value::Int(rhs, ..)) => Some(lhs.cmp(rhs)),
+        (Value::Float(lhs, ..), Value::Float(rhs, ..)) => {
+            let lhs = lhs.parse::<f64>().ok()?;
+            lhs.partial_cmp(&rhs.parse::<f64>().ok()?)
+        }
+        (Value::Bool(lhs, ..), Value::Bool(rhs, ..)) => Some(lhs.cmp(rhs)),
+        (Value::Char(lhs, ..), Value::Char(rhs, ..)) => Some(lhs.cmp(rhs)),
+        (Value::String(lhs, ..), Value::String(rhs, ..)) => Some(lhs.cmp(rhs)),
+        _ => None,
     }
 }
 
-fn expr_closure_with_args(
-    head: &Expr,
-    tail: &Expr,
-    args: &[Expr],
+/// The instance behind an operator and what to call it with, `a > b` is
+/// `lt b a`, `a <= b` is `not (lt b a)` and `a != b` is `not (eq a b)`.
+/// The last part says whether the result is flipped.
+fn operator_call(
+    op: Op,
+    method: &str,
+    lhs: Value,
+    rhs: Value,
+    span: Span,
     scope: &Scope,
-) -> Result<Value> {
-    let Some(arg) = args.first() else {
-        unimplemented!("not sure what to do here");
+) -> Result<(Value, Vec<Value>, bool)> {
+    let args = [lhs.clone(), rhs.clone()];
+    let Some(func) = instance_of(method, &args, scope)? else {
+        return Err(RuntimeError::InvalidBinaryOp(span));
     };
-    let Expr::Atom(Atom::Id(name, _, span)) = head else {
-        unimplemented!("shouldnt get here i think?");
+    let (args, negate) = match op {
+        Op::Grt => (vec![rhs, lhs], false),
+        Op::LesEq => (vec![rhs, lhs], true),
+        Op::GrtEq | Op::Neq => (vec![lhs, rhs], true),
+        _ => (vec![lhs, rhs], false),
     };
-    let mut scope = scope.clone();
-    scope.insert_local(name.clone(), arg.clone());
-    if tail.is_app() {
-        return expr_app(tail, &args[1..], *span, &scope);
+    Ok((func, args, negate))
+}
+
+/// Runs the instance behind an operator, see [`operator_call`].
+fn operator_instance(
+    op: Op,
+    method: &str,
+    lhs: Value,
+    rhs: Value,
+    span: Span,
+    scope: &Scope,
+) -> Result<Value> {
+    let (func, args, negate) = operator_call(op, method, lhs, rhs, span, scope)?;
+    match apply(func, args, scope)? {
+        Value::Bool(b, span) if negate => Ok(Value::Bool(!b, span)),
+        _ if negate => Err(RuntimeError::ExpectedBool(span)),
+        result => Ok(result),
     }
-    walk_expr(tail, &scope)
 }
 
-fn expr_app(expr: &Expr, args: &[Expr], span: Span, scope: &Scope) -> Result<Value> {
-    let Expr::Atom(Atom::Id(name, _, span)) = expr else {
-        let (Some(head), Some(tail)) = (expr.get_head(), expr.get_tail()) else {
-            let Expr::App(App { name, args, span, .. }) = expr else {
-                return Err(RuntimeError::InvalidArguments(span));
-            };
-            return expr_app(name, args, *span, scope);
-        };
-        return expr_closure_with_args(head, tail, args, scope);
+/// The function the instance of `method` gives for the type of the first
+/// `data` value in `args`.
+fn instance_of(method: &str, args: &[Value], scope: &Scope) -> Result<Option<Value>> {
+    let Some(instances) = scope.instances.get(method) else {
+        return Ok(None);
     };
-    match name.as_str() {
-        // Prints any item to console
-        "print" => {
-            let mut eval_args = vec![];
-            for expr in args.iter() {
-                let value = walk_expr(expr, scope)?;
-                eval_args.push(value);
-            }
-            let formated =
-                eval_args
-                    .iter()
-                    .enumerate()
-                    .fold("".into(), |acc, (idx, item)| {
-                        let item = match item {
-                            Value::Array(array, ..) => format!(
-                                "{}]",
-                                array.iter().fold("[".into(), |acc, item| {
-                                    if acc == "[" {
-                                        return format!("{acc}{item}");
-                                    }
-                                    format!("{acc}, {item}")
-                                })
-                            ),
-                            _ => item.to_string(),
-                        };
-                        if idx == 0 {
-                            return item;
-                        }
-                        format!("{acc} {item}")
-                    });
-            print!("{formated}");
-            Ok(eval_args[0].clone())
-        }
-        // use this function to index into an array
-        "nth" => {
-            let atom = walk_expr(&args[0], scope)?;
-            let Value::Array(array, span) = atom else {
-                return Err(RuntimeError::InvalidArguments(*span));
-            };
-            let atom = walk_expr(&args[1], scope)?;
-            let Value::Int(idx, span) = &atom else {
-                return Err(RuntimeError::InvalidArguments(span));
-            };
-            let idx = *idx as usize;
-            if idx >= array.len() {
-                return Err(RuntimeError::IdxOutOfBounds(*span));
-            }
-            let atom = &array[idx];
-            Ok(atom.clone())
-        }
-        // use this function to get the length of an array
-        "length" => {
-            let Value::Array(array, span) = walk_expr(&args[0], scope)? else {
-                return Ok(Value::Int(0, *span))
-            };
-            let len = array.len();
-            Ok(Value::Int(len as i32, span))
-        }
-        // use this function to push to the end of an array
-        "push" => {
-            let lhs = walk_expr(&args[0], scope)?;
-            let rhs = walk_expr(&args[1], scope)?;
-            match (lhs, rhs) {
-                (Value::String(mut string, span), Value::String(value, ..)) => {
-                    string.push_str(&value);
-                    Ok(Value::String(string, span))
-                }
-                (Value::Array(mut array, span), value) => {
-                    array.push(value);
-                    Ok(Value::Array(array, span))
-                }
-                _ => Err(RuntimeError::InvalidArguments(*span)),
-            }
-        }
-        "tail" => {
-            let iter = walk_expr(&args[0], scope)?;
-            eprintln!("tail: {iter} | {:?}", _typeofvalue(&iter));
-            match iter {
-                Value::String(string, span) => {
-                    if string.is_empty() {
-                        return Ok(Value::String(String::new(), span));
-                    }
-                    Ok(Value::String(string[1..].to_string(), span))
-                }
-                Value::Array(array, span) => {
-                    if array.is_empty() {
-                        return Ok(Value::Array(vec![], span));
-                    }
-                    Ok(Value::Array(array[1..].to_vec(), span))
-                }
-                _ => Err(RuntimeError::InvalidArguments(*span)),
-            }
-        }
-        "head" => {
-            let iter = walk_expr(&args[0], scope)?;
-            match iter {
-                Value::String(string, span) => {
-                    if string.is_empty() {
-                        return Err(RuntimeError::EmptyArray(span));
-                    }
-                    Ok(Value::String(string[0..1].to_string(), span))
-                }
-                Value::Array(array, span) => {
-                    if array.is_empty() {
-                        return Err(RuntimeError::EmptyArray(span));
-                    }
-                    Ok(array[0].clone())
-                }
-                _ => Err(RuntimeError::InvalidArguments(*span)),
-            }
-        }
-        _ => {
-            let Some(mut func) = scope.get(name).cloned() else {
-                return walk_expr(expr, scope);
-            };
-            let mut scope = scope.clone();
-            for arg in args.iter() {
-                match func.get_head() {
-                    Some(Expr::Atom(Atom::Id(name, pos, ..))) => {
-                        let value = match walk_expr(&arg.clone(), &scope) {
-                            Ok(v) => into_expr(&v, *pos),
-                            Err(_) => arg.clone(),
-                        };
-                        scope.insert_local(name.clone(), value);
-                        let Some(t) = func.get_tail() else {
-                            unreachable!()
-                        };
-                        func = t.clone();
-                    }
-                    _ => {
-                        let value = match walk_expr(&func, &scope) {
-                            Ok(v) => into_expr(&v, arg.position()),
-                            Err(_) => arg.clone(),
-                        };
-                        func = value;
-                    }
-                }
-            }
+    let found = args.iter().find_map(|arg| match arg {
+        Value::Variant(name, ..) => instances.get(scope.types.get(name)?),
+        _ => None,
+    });
+    found.cloned().map(|func| force(func, scope)).transpose()
+}
 
-            walk_expr(&func, &scope)
+/// A class method on the builtin types. They don't have instances, they get
+/// what the operator behind the method does.
+fn builtin_method(
+    method: &str,
+    args: Vec<Value>,
+    span: Span,
+    scope: &Scope,
+) -> Result<Value> {
+    let op = [
+        Op::Eq,
+        Op::Les,
+        Op::Plus,
+        Op::Minus,
+        Op::Mult,
+        Op::Div,
+        Op::Mod,
+    ]
+    .into_iter()
+    .find(|op| operator_method(*op) == Some(method));
+    match (method, op, <[Value; 2]>::try_from(args)) {
+        (_, Some(op), Ok([lhs, rhs])) => binary_value(op, lhs, rhs, span, scope),
+        ("show", _, Err(args)) if args.len() == 1 => {
+            Ok(Value::String(format_value(&args[0], scope)?, span))
         }
-    }
-}
-fn _typeofexpr(expr: &Expr) -> String {
-    match expr {
-        Expr::Atom(atom) => match atom {
-            Atom::Id(..) => "id".to_string(),
-            Atom::Int(..) => "int".to_string(),
-            Atom::Float(..) => "float".to_string(),
-            Atom::Bool(..) => "bool".to_string(),
-            Atom::String(..) => "string".to_string(),
-            Atom::Char(..) => "char".to_string(),
+        ("neg", _, Err(args)) => match args.as_slice() {
+            [Value::Int(int, ..)] => Ok(Value::Int(-int, span)),
+            _ => Err(RuntimeError::InvalidArguments(span)),
         },
-        Expr::Array(..) => "array".to_string(),
-        Expr::Closure(..) => "closure".to_string(),
-        Expr::Func(..) => "function".to_string(),
-        Expr::Error(..) => "error".to_string(),
-        Expr::App(..) => "app".to_string(),
-        Expr::Unary(..) => "unary".to_string(),
-        Expr::Binary(..) => "binary".to_string(),
-        Expr::IfElse(..) => "if".to_string(),
-        Expr::Enum(..) => "enum".to_string(),
+        _ => Err(RuntimeError::InvalidArguments(span)),
     }
 }
-fn _typeofvalue(expr: &Value) -> String {
-    match expr {
-        Value::Int(..) => "int".to_string(),
-        Value::Float(..) => "float".to_string(),
-        Value::Bool(..) => "bool".to_string(),
-        Value::String(..) => "string".to_string(),
-        Value::Char(..) => "char".to_string(),
-        Value::Array(..) => "array".to_string(),
-        Value::Func(..) => "function".to_string(),
+
+/// The value of a literal, names don't have one.
+fn literal(atom: &Atom) -> Option<Value> {
+    match atom {
+        Atom::Int(i, _, span) => Some(Value::Int(*i, *span)),
+        Atom::Float(f, _, span) => Some(Value::Float(f.clone(), *span)),
+        Atom::Bool(b, _, span) => Some(Value::Bool(*b, *span)),
+        Atom::String(string, _, span) => Some(Value::String(string.clone(), *span)),
+        Atom::Char(c, _, span) => Some(Value::Char(*c, *span)),
+        Atom::Id(..) => None,
     }
 }
-fn into_expr(v: &Value, pos: TokenPosition) -> Expr {
-    match v {
-        Value::Int(i, span) => Expr::Atom(Atom::Int(*i, pos, *span)),
-        Value::Float(i, span) => Expr::Atom(Atom::Float(i.clone(), pos, *span)),
-        Value::Bool(i, span) => Expr::Atom(Atom::Bool(*i, pos, *span)),
-        Value::String(i, span) => Expr::Atom(Atom::String(i.clone(), pos, *span)),
-        Value::Char(i, span) => Expr::Atom(Atom::Char(*i, pos, *span)),
-        Value::Array(array, span) => {
-            let array = array.iter().map(|v| into_expr(v, pos)).collect();
-            Expr::Array(array, pos, *span)
-        }
-        _ => unreachable!("{v}"),
+
+fn read_line(span: Span) -> Result<Value> {
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line).is_err() {
+        return Err(RuntimeError::InvalidArguments(span));
     }
+    let line = line.trim_end_matches(['\n', '\r']).to_string();
+    Ok(Value::String(line, span))
 }
 
-fn expr_closure(head: &Expr, tail: &Expr, scope: &Scope) -> Result<Value> {
-    walk_expr(head, scope)?;
-    walk_expr(tail, scope)
+/// Whether a value fits a pattern.
+#[derive(Debug, PartialEq)]
+enum Matched {
+    Yes,
+    No,
+    /// Part of the value is a thunk the pattern has to look inside, run it
+    /// and match again.
+    Force(Value),
 }
 
-fn walk_atom(atom: &Atom, scope: &Scope) -> Result<Value> {
-    match atom {
-        Atom::Id(name, _, span) => {
-            let Some(expr) = scope.get(name) else {
-                return Err(RuntimeError::Undefined(name.into(), *span));
-            };
-            walk_expr(expr, scope)
+impl From<bool> for Matched {
+    fn from(matched: bool) -> Self {
+        if matched {
+            Self::Yes
+        } else {
+            Self::No
         }
-        Atom::Int(i, _, span) => Ok(Value::Int(*i, *span)),
-        Atom::Float(f, _, span) => Ok(Value::Float(f.clone(), *span)),
-        Atom::Bool(b, _, span) => Ok(Value::Bool(*b, *span)),
-        Atom::String(string, _, span) => Ok(Value::String(string.clone(), *span)),
-        Atom::Char(c, _, span) => Ok(Value::Char(*c, *span)),
     }
 }
 
-fn walk_expr(expr: &Expr, scope: &Scope) -> Result<Value> {
-    match expr {
-        Expr::Atom(atom) => walk_atom(atom, scope),
-        Expr::Unary(unary) => expr_unary(unary, scope),
-        Expr::Binary(binary) => expr_binary(binary, scope),
-        Expr::IfElse(condition, then, r#else, ..) => {
-            expr_conditional(condition, then, r#else, scope)
-        }
-        Expr::Closure(head, tail, ..) => expr_closure(head, tail, scope),
-        Expr::App(App {
-            name, args, span, ..
-        }) => expr_app(name, args, *span, scope),
-        Expr::Array(array, ..) => {
-            let mut result = vec![];
-            let start_span = array.first().map(|e| e.span()).unwrap_or_default();
-            let end_span = array.last().map(|e| e.span()).unwrap_or_default();
-            let span = Span::from((start_span, end_span));
-            for e in array.iter() {
-                let expr = walk_expr(e, scope)?;
-                result.push(expr);
+/// Matches each value against its pattern, stopping at the first that
+/// doesn't match or needs a thunk run.
+fn match_patterns(
+    pats: &[Pat],
+    values: &[Value],
+    bindings: &mut Vec<(String, Value)>,
+) -> Matched {
+    pats.iter()
+        .zip(values.iter())
+        .map(|(pat, value)| match_pattern(pat, value, bindings))
+        .find(|matched| *matched != Matched::Yes)
+        .unwrap_or(Matched::Yes)
+}
+
+fn match_pattern(
+    pat: &Pat,
+    value: &Value,
+    bindings: &mut Vec<(String, Value)>,
+) -> Matched {
+    match (pat, value) {
+        (Pat::Wildcard(..), _) => Matched::Yes,
+        (Pat::Id(name, ..), value) => {
+            bindings.push((name.clone(), value.clone()));
+            Matched::Yes
+        }
+        (_, Value::Thunk(thunk)) => match &*thunk.borrow() {
+            Thunk::Done(value) => match_pattern(pat, value, bindings),
+            Thunk::Pending(..) => Matched::Force(value.clone()),
+        },
+        (Pat::Lit(Atom::Int(lhs, ..)), Value::Int(rhs, ..)) => (lhs == rhs).into(),
+        (Pat::Lit(Atom::Float(lhs, ..)), Value::Float(rhs, ..)) => (lhs == rhs).into(),
+        (Pat::Lit(Atom::Bool(lhs, ..)), Value::Bool(rhs, ..)) => (lhs == rhs).into(),
+        (Pat::Lit(Atom::String(lhs, ..)), Value::String(rhs, ..)) => (lhs == rhs).into(),
+        (Pat::Lit(Atom::Char(lhs, ..)), Value::Char(rhs, ..)) => (lhs == rhs).into(),
+        (Pat::Constructor(name, pats, ..), Value::Variant(variant, fields, ..)) => {
+            if name != variant || pats.len() != fields.len() {
+                return Matched::No;
             }
-            Ok(Value::Array(result, span))
+            match_patterns(pats, fields, bindings)
         }
-        Expr::Enum(..) => unimplemented!("enum"),
+        _ => Matched::No,
+    }
+}
 
-        // should never get to theres
-        Expr::Func(..) => unreachable!("func"),
-        Expr::Error(..) => unreachable!("error"),
+/// The function `code` is as a value over `env`. Code that isn't a
+/// closure becomes a function without parameters, which runs when it is
+/// looked up.
+fn closure(code: Rc<Code>, env: &Rc<Env>, name: Option<&str>, span: Span) -> Value {
+    let name = name.map(Rc::from);
+    match code.as_ref() {
+        Code::Closure(params, body, _) => Value::Closure {
+            params: params.clone(),
+            body: body.clone(),
+            env: env.clone(),
+            name,
+            span,
+        },
+        _ => Value::Closure {
+            params: vec![],
+            body: code,
+            env: env.clone(),
+            name,
+            span,
+        },
     }
 }
 
-pub fn walk(ast: &[Expr]) -> std::result::Result<Option<Value>, Vec<RuntimeError>> {
-    let mut scope = Scope::default();
-    let mut main_idx: Option<usize> = None;
-    for (idx, expr) in ast.iter().enumerate() {
-        match expr {
-            Expr::Func(name, ..) if name == "main" => {
-                main_idx = Some(idx);
+/// The builtins that need the evaluator, on their evaluated arguments. The
+/// others are [`Native`]s. How many there are was checked before they were
+/// evaluated.
+fn call_builtin(name: &str, args: Vec<Value>, scope: &Scope) -> Result<Value> {
+    let mut args = args.into_iter();
+    let mut arg = || {
+        args.next()
+            .expect("builtins are given all of their arguments")
+    };
+    match name {
+        // Prints any item to console
+        "print" => {
+            let first = arg();
+            let mut formated = format_value(&first, scope)?;
+            for item in args {
+                formated.push(' ');
+                formated += &format_value(&item, scope)?;
             }
-            Expr::Func(name, _, closure, ..) => {
-                scope.insert_global(name.to_string(), *closure.clone());
+            print!("{formated}");
+            Ok(first)
+        }
+        "deep_seq" => {
+            deep_force(arg(), scope)?;
+            Ok(arg())
+        }
+        _ => unreachable!("'{name}' isn't a builtin"),
+    }
+}
+
+/// How `print`, `show` and string interpolation turn a value into text.
+/// A `data` value with a `Show` instance is shown by it.
+fn format_value(value: &Value, scope: &Scope) -> Result<String> {
+    match value {
+        Value::Thunk(..) => format_value(&whnf(value.clone(), scope)?, scope),
+        Value::Array(array, ..) => {
+            let items = array
+                .iter()
+                .map(|item| format_value(item, scope))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("[{}]", items.join(", ")))
+        }
+        Value::Variant(name, fields, span) => {
+            if let Some(show) = instance_of("show", std::slice::from_ref(value), scope)? {
+                let Value::String(string, ..) = apply(show, vec![value.clone()], scope)?
+                else {
+                    return Err(RuntimeError::InvalidArguments(*span));
+                };
+                return Ok(string);
+            }
+            let mut string = name.clone();
+            for field in fields.iter() {
+                let field = whnf(field.clone(), scope)?;
+                let shown = format_value(&field, scope)?;
+                match field {
+                    Value::Variant(_, fields, ..) if !fields.is_empty() => {
+                        string += &format!(" ({shown})")
+                    }
+                    _ => string += &format!(" {shown}"),
+                }
             }
-            _ => unreachable!("{:?}", expr),
+            Ok(string)
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// `value` with every thunk inside it run and replaced by what it gave.
+fn deep_force(value: Value, scope: &Scope) -> Result<Value> {
+    let deep_force_all = |values: Vec<Value>| {
+        values
+            .into_iter()
+            .map(|value| deep_force(value, scope))
+            .collect::<Result<_>>()
+    };
+    match whnf(value, scope)? {
+        Value::Array(items, span) => Ok(Value::Array(deep_force_all(items)?, span)),
+        Value::Variant(name, fields, span) => {
+            Ok(Value::Variant(name, deep_force_all(fields)?, span))
         }
+        value => Ok(value),
+    }
+}
+
+/// A nullary constructor is already a value, the others become a function
+/// that takes each field in turn.
+fn constructor(name: &str, arity: usize, pos: TokenPosition, span: Span) -> Value {
+    if arity == 0 {
+        return Value::Variant(name.to_string(), vec![], span);
     }
+    curried(name, arity, pos, span)
+}
 
+/// A function that takes `arity` arguments one at a time and then applies
+/// `name` to all of them.
+/// ```hs
+/// Node = (\$0 -> (\$1 -> (\$2 -> Node $0 $1 $2)))
+/// ```
+fn curried(name: &str, arity: usize, pos: TokenPosition, span: Span) -> Value {
+    let params = (0..arity).map(|i| format!("${i}")).collect::<Vec<_>>();
+    let parts = std::iter::once(name.to_string())
+        .chain(params.iter().cloned())
+        .map(|name| Rc::new(Code::Atom(Atom::Id(name, pos, span))))
+        .collect();
+    Value::Closure {
+        params,
+        body: Rc::new(Code::App(parts, span)),
+        env: Rc::default(),
+        name: None,
+        span,
+    }
+}
+
+pub fn walk(ast: &[Expr]) -> std::result::Result<Option<Value>, Vec<RuntimeError>> {
+    walk_with_scope(ast, Scope::default())
+}
+
+/// Runs `main` with `scope` holding the settings, [`Scope::lazy`] and the
+/// limits, and whatever an embedder put in it.
+pub fn walk_with_scope(
+    ast: &[Expr],
+    scope: Scope,
+) -> std::result::Result<Option<Value>, Vec<RuntimeError>> {
+    let mut scope = scope;
+    scope.define(ast);
+    let main_idx = ast
+        .iter()
+        .position(|expr| matches!(expr, Expr::Func(name, ..) if name == "main"));
     let Some(idx) = main_idx else {
         return Err(vec![RuntimeError::MissingMainFunction]);
     };
@@ -454,7 +741,8 @@ pub fn walk(ast: &[Expr]) -> std::result::Result<Option<Value>, Vec<RuntimeError
     let Expr::Func(_, _, closure, ..) = main_function else {
         panic!("maybe you added a new prameter to Expr::Func?");
     };
-    match walk_expr(closure, &scope) {
+    let value = run(lower(closure), Rc::default(), &scope);
+    match value.and_then(|value| deep_force(value, &scope)) {
         Ok(v) => Ok(Some(v)),
         Err(err) => {
             errors.push(err);
@@ -468,11 +756,27 @@ pub fn eval_expr_with_scope(
     scope: &mut Scope,
 ) -> std::result::Result<Option<Value>, RuntimeError> {
     match expr {
-        Expr::Func(name, _, closure, ..) => {
-            scope.insert_global(name.to_string(), *closure.clone());
+        Expr::Func(name, type_info, closure, ..) => {
+            scope.insert_func(name, type_info, closure);
+            Ok(None)
+        }
+        Expr::Data(data) => {
+            scope.insert_data(data);
+            Ok(None)
+        }
+        Expr::Class(class) => {
+            scope.insert_class(class);
+            Ok(None)
+        }
+        Expr::Instance(instance) => {
+            scope.insert_instance(instance);
             Ok(None)
         }
-        _ => walk_expr(expr, scope).map(Some),
+        Expr::Alias(..) => Ok(None),
+        _ => {
+            let value = run(lower(expr), Rc::default(), scope)?;
+            deep_force(value, scope).map(Some)
+        }
     }
 }
 
@@ -483,5 +787,5 @@ fn test_walk() {
     let src = include_str!("./../../../samples/other.snow");
     let ast = parse(src);
     let result = walk(&ast.unwrap()).unwrap();
-    assert_eq!(result, None);
+    assert_eq!(result.map(|v| v.to_string()), Some("[2, 3]".to_string()));
 }
@@ -0,0 +1,856 @@
+//! The evaluator. Instead of recursing on the Rust stack it keeps what is
+//! left to do after each part of an expression in [`Frame`]s on a stack of
+//! its own. Whatever an expression hands its result straight back from, the
+//! branch an `if` takes, the body of a `match` arm, the last line of a `do`
+//! block, the body of a function, runs without pushing a frame, so calls in
+//! those places take no room at all and a loop written as a tail call runs
+//! forever. Deep recursion that isn't a tail call runs until the stack
+//! holds [`Scope::max_depth`] frames and stops with
+//! [`RuntimeError::RecursionLimit`].
+//!
+//! In lazy mode, see [`Scope::lazy`], arguments and array items become
+//! [`Thunk`]s. A frame that needs a value and gets a thunk that hasn't run
+//! stays where it is while the thunk runs on top of it.
+use super::{
+    builtin_method, call_builtin, constructor, curried, format_value, instance_of,
+    match_patterns, operator_call, read_line, Code, Env, Matched, Result, RuntimeError,
+    Scope, Thunk, Value,
+};
+use snowc_parse::{
+    builtin::{lookup, operator_method},
+    Atom, Op, Span,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// What the machine does next.
+enum Control {
+    /// Run `code` with the bindings in `env`.
+    Eval(Rc<Code>, Rc<Env>),
+    /// Hand a value to the frame on top of the stack.
+    Return(Value),
+}
+
+/// What an application does once its arguments are values.
+enum Call {
+    /// The first value is a function, apply it to the rest.
+    Apply,
+    /// One of the functions the language comes with that needs the
+    /// evaluator, see [`call_builtin`].
+    Builtin,
+    /// A function written in Rust, see [`super::Native`].
+    Native,
+    /// A `data` constructor with all of its fields.
+    Constructor,
+}
+
+/// What is left to do with the value coming back.
+enum Frame {
+    /// Running the parts of `code` one after another, see [`Code::part`].
+    Parts {
+        code: Rc<Code>,
+        env: Rc<Env>,
+        next: usize,
+        values: Vec<Value>,
+    },
+    /// Running the function and arguments of an application.
+    Args {
+        code: Rc<Code>,
+        env: Rc<Env>,
+        next: usize,
+        values: Vec<Value>,
+        call: Call,
+    },
+    /// Arguments left over once a function gave back another function.
+    Apply(Vec<Value>),
+    /// The guard of arm `arm` of the `match` in `code`, `bound` holds what
+    /// the arm's patterns bound.
+    Guard {
+        code: Rc<Code>,
+        arm: usize,
+        values: Vec<Value>,
+        env: Rc<Env>,
+        bound: Rc<Env>,
+    },
+    /// Trying arm `arm` of the `match` in `code` again once the thunk its
+    /// patterns had to look inside has run.
+    Match {
+        code: Rc<Code>,
+        arm: usize,
+        values: Vec<Value>,
+        env: Rc<Env>,
+    },
+    /// Line `line` of the `do` block in `code`.
+    Do {
+        code: Rc<Code>,
+        line: usize,
+        env: Rc<Env>,
+    },
+    /// String interpolation, `string` holds the text of the parts so far.
+    Concat {
+        parts: std::vec::IntoIter<Value>,
+        string: String,
+        span: Span,
+    },
+    /// Flips the `Bool` a comparison instance gives, see [`operator_call`].
+    Not(Span),
+    /// Sets [`Scope::pure`] back once a function that set it returns.
+    Pure(bool),
+    /// Keeps the value of a thunk that ran.
+    Update(Rc<RefCell<Thunk>>),
+    /// Tells [`Scope::observer`] the calls in `chain` returned, the first
+    /// one at `depth`. Each is a tail call of the one before it, a name
+    /// that calls itself over and over is there once with a count.
+    Trace {
+        chain: Vec<(Rc<str>, usize)>,
+        depth: usize,
+    },
+}
+
+impl Frame {
+    /// Whether the frame does something with the value it gets. The others
+    /// pass a thunk on as it is.
+    fn needs_value(&self) -> bool {
+        !matches!(self, Self::Do { .. } | Self::Pure(..) | Self::Trace { .. })
+    }
+}
+
+struct Machine<'a> {
+    stack: Vec<Frame>,
+    scope: &'a Scope,
+}
+
+/// Runs `code` to a value.
+pub(crate) fn run(code: Rc<Code>, env: Rc<Env>, scope: &Scope) -> Result<Value> {
+    enter(scope, |machine| machine.exec(Control::Eval(code, env)))
+}
+
+/// Applies a function value to already evaluated arguments. Fewer
+/// arguments than parameters leave a closure waiting for the rest, more go
+/// to whatever the function gives back.
+pub(crate) fn apply(func: Value, args: Vec<Value>, scope: &Scope) -> Result<Value> {
+    enter(scope, |machine| {
+        let control = machine.apply(func, args)?;
+        machine.exec(control)
+    })
+}
+
+/// Runs a function without parameters, everything else is already a value.
+pub(crate) fn force(value: Value, scope: &Scope) -> Result<Value> {
+    match value {
+        Value::Closure {
+            params, body, env, ..
+        } if params.is_empty() => run(body, env, scope),
+        value => Ok(value),
+    }
+}
+
+/// Runs a thunk if `value` is one, as far as the outermost constructor.
+pub(crate) fn whnf(value: Value, scope: &Scope) -> Result<Value> {
+    match value {
+        Value::Thunk(..) => enter(scope, |machine| {
+            let control = machine.force(value)?;
+            machine.exec(control)
+        }),
+        value => Ok(value),
+    }
+}
+
+/// A machine of its own for `f`. Builtins that need a function's value in
+/// the middle of their work, `show` inside `print`, start one on the Rust
+/// stack, so only [`Scope::max_nested`] of them can be running at once.
+fn enter(scope: &Scope, f: impl FnOnce(&mut Machine) -> Result<Value>) -> Result<Value> {
+    let nested = scope.nested.get();
+    if nested >= scope.max_nested {
+        return Err(RuntimeError::RecursionLimit(Span::default()));
+    }
+    let pure = scope.pure.get();
+    let calls = scope.calls.get();
+    scope.nested.set(nested + 1);
+    let mut machine = Machine {
+        stack: vec![],
+        scope,
+    };
+    let result = f(&mut machine);
+    scope.nested.set(nested);
+    // frames that would have set it back are gone with the error
+    if result.is_err() {
+        scope.pure.set(pure);
+        scope.calls.set(calls);
+    }
+    result
+}
+
+impl Machine<'_> {
+    fn exec(&mut self, control: Control) -> Result<Value> {
+        let mut control = control;
+        loop {
+            control = match control {
+                Control::Eval(code, env) => self.eval(code, env)?,
+                Control::Return(value @ Value::Thunk(..))
+                    if self.stack.last().is_none_or(Frame::needs_value) =>
+                {
+                    self.force(value)?
+                }
+                Control::Return(value) => match self.stack.pop() {
+                    Some(frame) => self.resume(frame, value)?,
+                    None => return Ok(value),
+                },
+            };
+        }
+    }
+
+    fn push(&mut self, frame: Frame, span: Span) -> Result<()> {
+        if self.stack.len() >= self.scope.max_depth {
+            return Err(RuntimeError::RecursionLimit(span));
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    fn eval(&mut self, code: Rc<Code>, env: Rc<Env>) -> Result<Control> {
+        match code.as_ref() {
+            Code::Atom(atom) => self.atom(atom, &env),
+            Code::Closure(params, body, span) => Ok(Control::Return(Value::Closure {
+                params: params.clone(),
+                body: body.clone(),
+                env,
+                name: None,
+                span: *span,
+            })),
+            Code::App(..) => self.app(code, env),
+            Code::Do(..) => self.line(code, 0, env),
+            Code::Array(items, span) if self.scope.lazy => {
+                let items = items.iter().map(|item| delay(item, &env)).collect();
+                Ok(Control::Return(Value::Array(items, *span)))
+            }
+            // the patterns run what they need to look at
+            Code::Match(exprs, ..) if self.scope.lazy => {
+                let values = exprs.iter().map(|expr| delay(expr, &env)).collect();
+                self.arm(code.clone(), 0, values, env)
+            }
+            _ => self.parts(code, env, 0, vec![]),
+        }
+    }
+
+    fn atom(&mut self, atom: &Atom, env: &Rc<Env>) -> Result<Control> {
+        let scope = self.scope;
+        let value = match atom {
+            Atom::Id(name, ..) if env.get(name).is_some() => {
+                env.get(name).cloned().expect("just looked it up")
+            }
+            // reads one line from stdin without the trailing new line
+            Atom::Id(name, _, span) if name == "read_line" => {
+                if scope.pure.get() {
+                    return Err(RuntimeError::EffectInPureFunction(*span));
+                }
+                read_line(*span)?
+            }
+            Atom::Id(name, pos, span) if scope.natives.contains_key(name) => {
+                let native = &scope.natives[name];
+                if native.arity > 0 {
+                    curried(name, native.arity, *pos, *span)
+                } else {
+                    native.call(&[], *span)?
+                }
+            }
+            Atom::Id(name, pos, span) if scope.constructors.contains_key(name) => {
+                constructor(name, scope.constructors[name], *pos, *span)
+            }
+            Atom::Id(name, _, span) => match scope.global.get(name) {
+                Some(Value::Closure {
+                    params,
+                    body,
+                    env,
+                    name,
+                    ..
+                }) if params.is_empty() => {
+                    if let Some(name) = name {
+                        self.observe(name, &[], *span)?;
+                    }
+                    return Ok(Control::Eval(body.clone(), env.clone()));
+                }
+                Some(value) => value.clone(),
+                None => return Err(RuntimeError::Undefined(name.into(), *span)),
+            },
+            literal => super::literal(literal).expect("names are handled above"),
+        };
+        Ok(Control::Return(value))
+    }
+
+    /// Tells the observer, if there is one, a call to `name` starts and
+    /// leaves a frame to tell it when the call returns. A tail call joins
+    /// the frame of the call it is in, so a traced loop doesn't grow the
+    /// stack.
+    fn observe(&mut self, name: &Rc<str>, args: &[Value], span: Span) -> Result<()> {
+        let Some(observer) = &self.scope.observer else {
+            return Ok(());
+        };
+        let depth = self.scope.calls.get();
+        observer.borrow_mut().enter(name, args, depth);
+        self.scope.calls.set(depth + 1);
+        // only frames that give the value back as it is can be above it
+        let tail = self
+            .stack
+            .iter_mut()
+            .rev()
+            .find(|frame| !matches!(frame, Frame::Pure(..)));
+        if let Some(Frame::Trace { chain, .. }) = tail {
+            match chain.last_mut() {
+                Some((last, count)) if last == name => *count += 1,
+                _ => chain.push((name.clone(), 1)),
+            }
+            return Ok(());
+        }
+        let chain = vec![(name.clone(), 1)];
+        self.push(Frame::Trace { chain, depth }, span)
+    }
+
+    /// Runs a thunk that hasn't run yet, the frame on top of the stack gets
+    /// its value.
+    fn force(&mut self, value: Value) -> Result<Control> {
+        let Value::Thunk(thunk) = value else {
+            return Ok(Control::Return(value));
+        };
+        let (code, env) = match &*thunk.borrow() {
+            Thunk::Pending(code, env) => (code.clone(), env.clone()),
+            Thunk::Done(value) => return Ok(Control::Return(value.clone())),
+        };
+        let span = code.span();
+        self.push(Frame::Update(thunk), span)?;
+        Ok(Control::Eval(code, env))
+    }
+
+    /// Runs the parts of `code` from `next` on, then the expression itself.
+    fn parts(
+        &mut self,
+        code: Rc<Code>,
+        env: Rc<Env>,
+        next: usize,
+        values: Vec<Value>,
+    ) -> Result<Control> {
+        let Some(part) = code.part(next).cloned() else {
+            return self.finish(code, env, values);
+        };
+        let span = code.span();
+        let frame = Frame::Parts {
+            code,
+            env: env.clone(),
+            next: next + 1,
+            values,
+        };
+        self.push(frame, span)?;
+        Ok(Control::Eval(part, env))
+    }
+
+    fn finish(
+        &mut self,
+        code: Rc<Code>,
+        env: Rc<Env>,
+        values: Vec<Value>,
+    ) -> Result<Control> {
+        let mut values = values.into_iter();
+        let mut value = || values.next().expect("every part has a value");
+        match code.as_ref() {
+            Code::Unary(op, _, span) => self.unary(*op, value(), *span),
+            Code::Binary(op, .., span) => {
+                let lhs = value();
+                self.binary(*op, lhs, value(), *span)
+            }
+            Code::IfElse(condition, then, r#else, _) => match value() {
+                Value::Bool(true, _) => Ok(Control::Eval(then.clone(), env)),
+                Value::Bool(false, _) => Ok(Control::Eval(r#else.clone(), env)),
+                _ => Err(RuntimeError::ExpectedBool(condition.span())),
+            },
+            Code::Array(.., span) => {
+                Ok(Control::Return(Value::Array(values.collect(), *span)))
+            }
+            Code::Concat(_, span) => self.concat(values, String::new(), *span),
+            Code::Index(expr, index, end, span) => {
+                let array = value();
+                let start = value();
+                let end = end.as_ref().map(|end| (value(), end.span()));
+                let index = (start, index.span());
+                Ok(Control::Return(index_value(
+                    array,
+                    expr.span(),
+                    index,
+                    end,
+                    *span,
+                )?))
+            }
+            Code::Match(..) => self.arm(code, 0, values.collect(), env),
+            Code::Atom(..) | Code::Closure(..) | Code::App(..) | Code::Do(..) => {
+                unreachable!("runs without parts")
+            }
+        }
+    }
+
+    fn unary(&mut self, op: Op, value: Value, span: Span) -> Result<Control> {
+        let value = match (op, value) {
+            (Op::Minus, Value::Int(int, span)) => Value::Int(-int, span),
+            (Op::Minus, value @ Value::Variant(..)) => {
+                return self.method("neg", vec![value], span);
+            }
+            (Op::Not, Value::Bool(b, span)) => Value::Bool(!b, span),
+            _ => unimplemented!("for operator '{op:?}'"),
+        };
+        Ok(Control::Return(value))
+    }
+
+    /// A `data` value goes to the instance behind the operator in this
+    /// machine, see [`operator_method`], the rest to [`super::binary_value`].
+    fn binary(&mut self, op: Op, lhs: Value, rhs: Value, span: Span) -> Result<Control> {
+        let is_data = |value: &Value| matches!(value, Value::Variant(..));
+        let Some(method) = operator_method(op).filter(|_| is_data(&lhs) || is_data(&rhs))
+        else {
+            let value = super::binary_value(op, lhs, rhs, span, self.scope)?;
+            return Ok(Control::Return(value));
+        };
+        let (func, args, negate) = operator_call(op, method, lhs, rhs, span, self.scope)?;
+        if negate {
+            self.push(Frame::Not(span), span)?;
+        }
+        self.apply(func, args)
+    }
+
+    /// Calls a class method with all of its arguments. The builtin types
+    /// don't have instances, [`builtin_method`] does what they do.
+    fn method(&mut self, method: &str, args: Vec<Value>, span: Span) -> Result<Control> {
+        if let Some(func) = instance_of(method, &args, self.scope)? {
+            return self.apply(func, args);
+        }
+        let value = builtin_method(method, args, span, self.scope)?;
+        Ok(Control::Return(value))
+    }
+
+    fn app(&mut self, code: Rc<Code>, env: Rc<Env>) -> Result<Control> {
+        let scope = self.scope;
+        let Code::App(parts, span) = code.as_ref() else {
+            unreachable!("only called on applications");
+        };
+        let span = *span;
+        let Code::Atom(Atom::Id(name, ..)) = parts[0].as_ref() else {
+            return self.args(code, env, 0, vec![], Call::Apply);
+        };
+        // a local shadows every builtin of the same name
+        if let Some(func) = env.get(name) {
+            // a thunk runs as the first part
+            let (next, values) = match func {
+                Value::Thunk(..) => (0, vec![]),
+                func => (1, vec![func.clone()]),
+            };
+            return self.args(code, env, next, values, Call::Apply);
+        }
+        let given = parts.len() - 1;
+        let arity = match scope.natives.get(name) {
+            Some(native) => Some((native.arity, false)),
+            None => lookup(name).map(|builtin| (builtin.arity(), builtin.variadic)),
+        };
+        if let Some((arity, variadic)) = arity {
+            if given < arity || (!variadic && given > arity) {
+                return Err(RuntimeError::InvalidArguments(span));
+            }
+        }
+        match name.as_str() {
+            "$pure" => {
+                // a tail call lands on the frame of the call it replaces
+                if !matches!(self.stack.last(), Some(Frame::Pure(..))) {
+                    self.push(Frame::Pure(scope.pure.get()), span)?;
+                }
+                scope.pure.set(true);
+                Ok(Control::Eval(parts[1].clone(), env))
+            }
+            "print" if scope.pure.get() => Err(RuntimeError::EffectInPureFunction(span)),
+            "$method" | "print" | "deep_seq" => {
+                self.args(code, env, 1, vec![], Call::Builtin)
+            }
+            _ if scope.natives.contains_key(name) => {
+                self.args(code, env, 1, vec![], Call::Native)
+            }
+            _ if scope.constructors.get(name) == Some(&given) => {
+                self.args(code, env, 1, vec![], Call::Constructor)
+            }
+            _ => self.args(code, env, 0, vec![], Call::Apply),
+        }
+    }
+
+    /// Runs the parts of an application from `next` on, then calls it.
+    fn args(
+        &mut self,
+        code: Rc<Code>,
+        env: Rc<Env>,
+        next: usize,
+        values: Vec<Value>,
+        call: Call,
+    ) -> Result<Control> {
+        // builtins get values, the rest of the arguments wait to be needed
+        if self.scope.lazy && next > 0 && matches!(call, Call::Apply | Call::Constructor)
+        {
+            let Code::App(parts, _) = code.as_ref() else {
+                unreachable!("only called on applications");
+            };
+            let mut values = values;
+            values.extend(parts[next..].iter().map(|part| delay(part, &env)));
+            return self.call(code, values, call);
+        }
+        let Some(part) = code.part(next).cloned() else {
+            return self.call(code, values, call);
+        };
+        let span = code.span();
+        let frame = Frame::Args {
+            code,
+            env: env.clone(),
+            next: next + 1,
+            values,
+            call,
+        };
+        self.push(frame, span)?;
+        Ok(Control::Eval(part, env))
+    }
+
+    fn call(
+        &mut self,
+        code: Rc<Code>,
+        values: Vec<Value>,
+        call: Call,
+    ) -> Result<Control> {
+        let Code::App(parts, span) = code.as_ref() else {
+            unreachable!("only called on applications");
+        };
+        let mut values = values;
+        let name = match parts[0].as_ref() {
+            Code::Atom(Atom::Id(name, ..)) => name.as_str(),
+            _ => "",
+        };
+        match (call, name) {
+            (Call::Apply, _) => {
+                let args = values.split_off(1);
+                self.apply(values.remove(0), args)
+            }
+            (Call::Constructor, _) => {
+                Ok(Control::Return(Value::Variant(name.into(), values, *span)))
+            }
+            // a class method with all of its arguments, see `method_stub`
+            (Call::Builtin, "$method") => {
+                let Value::String(method, ..) = values.remove(0) else {
+                    unreachable!("method stubs start with the name of their method");
+                };
+                self.method(&method, values, *span)
+            }
+            (Call::Builtin, _) => {
+                let value = call_builtin(name, values, self.scope)?;
+                Ok(Control::Return(value))
+            }
+            (Call::Native, _) => {
+                let value = self.scope.natives[name].call(&values, *span)?;
+                Ok(Control::Return(value))
+            }
+        }
+    }
+
+    /// Adds the text of each part to `string`. A `data` value with a `Show`
+    /// instance is shown in this machine, a recursive instance written with
+    /// interpolation recurses as deep as any other function.
+    fn concat(
+        &mut self,
+        parts: std::vec::IntoIter<Value>,
+        string: String,
+        span: Span,
+    ) -> Result<Control> {
+        let mut parts = parts;
+        let mut string = string;
+        while let Some(part) = parts.next() {
+            let show = instance_of("show", std::slice::from_ref(&part), self.scope)?;
+            if let Some(show) = show {
+                let frame = Frame::Concat {
+                    parts,
+                    string,
+                    span,
+                };
+                self.push(frame, span)?;
+                return self.apply(show, vec![part]);
+            }
+            string += &format_value(&part, self.scope)?;
+        }
+        Ok(Control::Return(Value::String(string, span)))
+    }
+
+    fn apply(&mut self, func: Value, args: Vec<Value>) -> Result<Control> {
+        if args.is_empty() {
+            return Ok(Control::Return(func));
+        }
+        let Value::Closure {
+            params,
+            body,
+            env,
+            name,
+            span,
+        } = func
+        else {
+            return Err(RuntimeError::NotAFunction(func.span()));
+        };
+        let mut args = args;
+        let rest = args.split_off(params.len().min(args.len()));
+        let taken = args.len();
+        if taken < params.len() {
+            return Ok(Control::Return(Value::Closure {
+                params: params[taken..].to_vec(),
+                body,
+                env: Env::extend(&env, params.iter().cloned().zip(args)),
+                name,
+                span,
+            }));
+        }
+        if !rest.is_empty() {
+            self.push(Frame::Apply(rest), span)?;
+        }
+        if let Some(name) = &name {
+            self.observe(name, &args, span)?;
+        }
+        let env = Env::extend(&env, params.iter().cloned().zip(args));
+        Ok(Control::Eval(body, env))
+    }
+
+    /// Tries the arms of the `match` in `code` from `arm` on.
+    fn arm(
+        &mut self,
+        code: Rc<Code>,
+        arm: usize,
+        values: Vec<Value>,
+        env: Rc<Env>,
+    ) -> Result<Control> {
+        let Code::Match(_, arms, span) = code.as_ref() else {
+            unreachable!("only called on matches");
+        };
+        for (idx, arm) in arms.iter().enumerate().skip(arm) {
+            let mut bindings = vec![];
+            match match_patterns(&arm.pats, &values, &mut bindings) {
+                Matched::Yes => {}
+                Matched::No => continue,
+                Matched::Force(thunk) => {
+                    let frame = Frame::Match {
+                        code: code.clone(),
+                        arm: idx,
+                        values,
+                        env,
+                    };
+                    self.push(frame, *span)?;
+                    return self.force(thunk);
+                }
+            }
+            let bound = Env::extend(&env, bindings);
+            let Some(guard) = arm.guard.clone() else {
+                return Ok(Control::Eval(arm.body.clone(), bound));
+            };
+            let span = guard.span();
+            let frame = Frame::Guard {
+                code: code.clone(),
+                arm: idx,
+                values,
+                env,
+                bound: bound.clone(),
+            };
+            self.push(frame, span)?;
+            return Ok(Control::Eval(guard, bound));
+        }
+        Err(RuntimeError::NoMatchingClause(*span))
+    }
+
+    /// Runs line `line` of the `do` block in `code`, the last one in place
+    /// of the block.
+    fn line(&mut self, code: Rc<Code>, line: usize, env: Rc<Env>) -> Result<Control> {
+        let Code::Do(stmts, span) = code.as_ref() else {
+            unreachable!("only called on do blocks");
+        };
+        // the parser rejects empty blocks and blocks that end on a bind
+        let Some(stmt) = stmts.get(line) else {
+            return Err(RuntimeError::InvalidArguments(*span));
+        };
+        let stmt = stmt.code().clone();
+        if line + 1 < stmts.len() {
+            let span = stmt.span();
+            let frame = Frame::Do {
+                code,
+                line,
+                env: env.clone(),
+            };
+            self.push(frame, span)?;
+        }
+        Ok(Control::Eval(stmt, env))
+    }
+
+    fn resume(&mut self, frame: Frame, value: Value) -> Result<Control> {
+        match frame {
+            Frame::Parts {
+                code,
+                env,
+                next,
+                mut values,
+            } => {
+                values.push(value);
+                self.parts(code, env, next, values)
+            }
+            Frame::Args {
+                code,
+                env,
+                next,
+                mut values,
+                call,
+            } => {
+                values.push(value);
+                self.args(code, env, next, values, call)
+            }
+            Frame::Apply(args) => self.apply(value, args),
+            Frame::Guard {
+                code,
+                arm,
+                values,
+                env,
+                bound,
+            } => {
+                let Code::Match(_, arms, _) = code.as_ref() else {
+                    unreachable!("guards belong to matches");
+                };
+                match value {
+                    Value::Bool(true, _) => {
+                        Ok(Control::Eval(arms[arm].body.clone(), bound))
+                    }
+                    Value::Bool(false, _) => self.arm(code, arm + 1, values, env),
+                    _ => {
+                        let guard =
+                            arms[arm].guard.as_ref().expect("the arm has a guard");
+                        Err(RuntimeError::ExpectedBool(guard.span()))
+                    }
+                }
+            }
+            Frame::Match {
+                code,
+                arm,
+                values,
+                env,
+            } => self.arm(code, arm, values, env),
+            Frame::Do { code, line, env } => {
+                let Code::Do(stmts, _) = code.as_ref() else {
+                    unreachable!("lines belong to do blocks");
+                };
+                let env = match &stmts[line] {
+                    super::code::Stmt::Bind(name, _) => {
+                        Env::extend(&env, [(name.clone(), value)])
+                    }
+                    super::code::Stmt::Expr(..) => env,
+                };
+                self.line(code, line + 1, env)
+            }
+            Frame::Concat {
+                parts,
+                mut string,
+                span,
+            } => {
+                let Value::String(shown, ..) = value else {
+                    return Err(RuntimeError::InvalidArguments(value.span()));
+                };
+                string += &shown;
+                self.concat(parts, string, span)
+            }
+            Frame::Not(span) => match value {
+                Value::Bool(b, span) => Ok(Control::Return(Value::Bool(!b, span))),
+                _ => Err(RuntimeError::ExpectedBool(span)),
+            },
+            Frame::Pure(outer) => {
+                self.scope.pure.set(outer);
+                Ok(Control::Return(value))
+            }
+            Frame::Update(thunk) => {
+                *thunk.borrow_mut() = Thunk::Done(value.clone());
+                Ok(Control::Return(value))
+            }
+            Frame::Trace { chain, depth } => {
+                self.scope.calls.set(depth);
+                if let Some(observer) = &self.scope.observer {
+                    let mut observer = observer.borrow_mut();
+                    let mut at =
+                        depth + chain.iter().map(|(_, count)| count).sum::<usize>();
+                    for (name, count) in chain.iter().rev() {
+                        for _ in 0..*count {
+                            at -= 1;
+                            observer.exit(name, &value, at);
+                        }
+                    }
+                }
+                Ok(Control::Return(value))
+            }
+        }
+    }
+}
+
+/// What an argument or array item is in lazy mode. Names in `env`,
+/// literals and closures already are values, anything else becomes a thunk.
+fn delay(code: &Rc<Code>, env: &Rc<Env>) -> Value {
+    let value = match code.as_ref() {
+        Code::Atom(Atom::Id(name, ..)) => env.get(name).cloned(),
+        Code::Atom(atom) => super::literal(atom),
+        Code::Closure(params, body, span) => Some(Value::Closure {
+            params: params.clone(),
+            body: body.clone(),
+            env: env.clone(),
+            name: None,
+            span: *span,
+        }),
+        _ => None,
+    };
+    value.unwrap_or_else(|| {
+        let thunk = Thunk::Pending(code.clone(), env.clone());
+        Value::Thunk(Rc::new(RefCell::new(thunk)))
+    })
+}
+
+/// Turns an index into a position inside `len` items. `len` itself is only
+/// a valid position for the end of a slice.
+fn position(value: Value, span: Span, len: usize, inclusive: bool) -> Result<usize> {
+    let Value::Int(idx, ..) = value else {
+        return Err(RuntimeError::InvalidArguments(span));
+    };
+    match usize::try_from(idx) {
+        Ok(idx) if idx < len || (inclusive && idx == len) => Ok(idx),
+        _ => Err(RuntimeError::IdxOutOfBounds(span)),
+    }
+}
+
+/// `value[index]` or `value[index..end]`, each with the span of the code
+/// it came from.
+fn index_value(
+    value: Value,
+    value_span: Span,
+    index: (Value, Span),
+    end: Option<(Value, Span)>,
+    span: Span,
+) -> Result<Value> {
+    let len = match &value {
+        Value::Array(array, ..) => array.len(),
+        Value::String(string, ..) => string.chars().count(),
+        _ => return Err(RuntimeError::InvalidArguments(value_span)),
+    };
+    let Some((end, end_span)) = end else {
+        let idx = position(index.0, index.1, len, false)?;
+        return Ok(match value {
+            Value::Array(mut array, ..) => array.swap_remove(idx),
+            Value::String(string, ..) => {
+                Value::Char(string.chars().nth(idx).unwrap_or_default(), span)
+            }
+            _ => unreachable!(),
+        });
+    };
+    let start = position(index.0, index.1, len, true)?;
+    let end = position(end, end_span, len, true)?;
+    if end < start {
+        return Err(RuntimeError::IdxOutOfBounds(end_span));
+    }
+    Ok(match value {
+        Value::Array(array, ..) => Value::Array(array[start..end].to_vec(), span),
+        Value::String(string, ..) => {
+            let slice = string.chars().skip(start).take(end - start).collect();
+            Value::String(slice, span)
+        }
+        _ => unreachable!(),
+    })
+}
@@ -0,0 +1,147 @@
+//! Functions written in Rust. Programs call them like any other function,
+//! [`crate::Scope::register`] adds one. The builtins that only look at
+//! their arguments are natives too, `print` and the others that run code of
+//! the program stay in the evaluator.
+use super::{Result, RuntimeError, Value};
+use snowc_parse::Span;
+use std::{fmt, rc::Rc};
+
+/// What a [`Native`] runs, on its arguments.
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value>;
+
+#[derive(Clone)]
+pub struct Native {
+    pub name: String,
+    pub arity: usize,
+    /// Written the way a function signature is, `Array<a> -> Int`. Without
+    /// one the type checker lets it take and give back anything.
+    pub signature: Option<String>,
+    /// Gets exactly `arity` arguments. In lazy mode they have run, what is
+    /// inside an array or a `data` value may still be a [`Value::Thunk`].
+    /// An error without a span points at the call.
+    pub func: Rc<NativeFn>,
+}
+
+impl Native {
+    pub fn new(
+        name: impl Into<String>,
+        arity: usize,
+        func: impl Fn(&[Value]) -> Result<Value> + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            arity,
+            signature: None,
+            func: Rc::new(func),
+        }
+    }
+
+    pub fn signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    pub(crate) fn call(&self, args: &[Value], span: Span) -> Result<Value> {
+        (self.func)(args).map_err(|err| err.or_at(span))
+    }
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Native")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The natives every program starts with.
+pub(crate) fn builtins() -> Vec<Native> {
+    vec![
+        // use this function to index into an array
+        Native::new("nth", 2, |args| match args {
+            [Value::Array(array, _), Value::Int(idx, span)] => usize::try_from(*idx)
+                .ok()
+                .and_then(|idx| array.get(idx).cloned())
+                .ok_or(RuntimeError::IdxOutOfBounds(*span)),
+            [Value::Array(..), idx] => Err(RuntimeError::InvalidArguments(idx.span())),
+            [array, _] => Err(RuntimeError::InvalidArguments(array.span())),
+            _ => unreachable!("natives get exactly their arity"),
+        }),
+        // use this function to get the length of an array
+        Native::new("length", 1, |args| match &args[0] {
+            Value::Array(array, span) => Ok(Value::Int(array.len() as i32, *span)),
+            value => Err(RuntimeError::InvalidArguments(value.span())),
+        }),
+        // use this function to push to the end of an array
+        Native::new("push", 2, |args| match args {
+            [Value::String(string, span), Value::String(value, ..)] => {
+                Ok(Value::String(format!("{string}{value}"), *span))
+            }
+            [Value::Array(array, span), value] => {
+                let mut array = array.clone();
+                array.push(value.clone());
+                Ok(Value::Array(array, *span))
+            }
+            _ => Err(RuntimeError::InvalidArguments(Span::default())),
+        }),
+        Native::new("tail", 1, |args| match &args[0] {
+            Value::String(string, span) => {
+                Ok(Value::String(string.chars().skip(1).collect(), *span))
+            }
+            Value::Array(array, span) => {
+                Ok(Value::Array(array.iter().skip(1).cloned().collect(), *span))
+            }
+            value => Err(RuntimeError::InvalidArguments(value.span())),
+        }),
+        Native::new("head", 1, |args| match &args[0] {
+            Value::String(string, span) => match string.chars().next() {
+                Some(c) => Ok(Value::String(c.into(), *span)),
+                None => Err(RuntimeError::EmptyArray(*span)),
+            },
+            Value::Array(array, span) => array
+                .first()
+                .cloned()
+                .ok_or(RuntimeError::EmptyArray(*span)),
+            value => Err(RuntimeError::InvalidArguments(value.span())),
+        }),
+        // `[a..b]` and `[a, b..c]` desugar to these two
+        Native::new("range", 2, range),
+        Native::new("range_then", 3, range),
+        // the first argument ran before the call like every native's does
+        Native::new("seq", 2, |args| Ok(args[1].clone())),
+    ]
+}
+
+/// `[from..to]` with the bounds in `args`, or `[from, then..to]`.
+fn range(args: &[Value]) -> Result<Value> {
+    let mut bounds = vec![];
+    for arg in args {
+        let Value::Int(int, ..) = arg else {
+            return Err(RuntimeError::InvalidArguments(arg.span()));
+        };
+        bounds.push(*int);
+    }
+    // wide enough for the distance between any two bounds
+    let (from, step, to) = match bounds[..] {
+        [from, to] => (from, 1, to),
+        [from, then, to] => (from, i64::from(then) - i64::from(from), to),
+        _ => unreachable!("natives get exactly their arity"),
+    };
+    if step == 0 {
+        return Err(RuntimeError::ZeroStep(args[1].span()));
+    }
+    let span = Span::from((args[0].span(), args[args.len() - 1].span()));
+    let mut array = vec![];
+    let mut current = Some(from);
+    while let Some(int) = current {
+        if (step > 0 && int > to) || (step < 0 && int < to) {
+            break;
+        }
+        array.push(Value::Int(int, span));
+        // past the largest or smallest Int there is nothing left to give
+        current = i32::try_from(i64::from(int) + step).ok();
+    }
+    Ok(Value::Array(array, span))
+}
@@ -1,54 +1,702 @@
-// use pretty_assertions::assert_eq;
-//
-// use super::*;
-// use snowc_parse::{parse, Scanner};
-//
-// macro_rules! build_test {
-//     ($name:ident, $src:expr, $expected:expr $(,)?) => {
-//         #[test]
-//         fn $name() {
-//             let scanner = Scanner::new($src);
-//             let ast = parse(scanner).unwrap();
-//             let v = match walk(&ast) {
-//                 Ok(v) => v,
-//                 Err(err) => {
-//                     for e in err.iter() {
-//                         e.report("yo momma", $src);
-//                     }
-//                     eprintln!("{err:?}");
-//                     None
-//                 }
-//             };
-//             assert_eq!(v, $expected);
-//         }
-//     };
-// }
-//
-// build_test! {
-//     unary,
-//     "main = -1;",
-//     Some(Value::Int(-1, Span::new(0, 0, 0, 0, 7, 9)))
-// }
-//
-// #[test]
-// fn hhhhhh() {
-//     let src = r#"
-// create_grid size char = if (0 < (size - 1)) then (push (create_grid (size - 1) char) char) else ([char]);
-// main = create_grid 9 " ";
-//         "#;
-//     let scanner = Scanner::new(src);
-//     let ast = parse(scanner).unwrap();
-//     let value = walk(&ast).unwrap();
-//     assert_eq!(
-//         Some(Value::Array(
-//             vec![
-//                 Value::String(String::from(" "), Span::default()),
-//                 Value::String(String::from(" "), Span::default()),
-//                 Value::String(String::from(" "), Span::default()),
-//                 Value::String(String::from(" "), Span::default()),
-//             ],
-//             Span::default()
-//         )),
-//         value
-//     );
-// }
+use pretty_assertions::assert_eq;
+
+use super::*;
+use snowc_parse::parse;
+
+macro_rules! build_test {
+    ($name:ident, $src:expr, $expected:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            let ast = parse($src).unwrap();
+            let v = match walk(&ast) {
+                Ok(v) => v,
+                Err(err) => {
+                    for e in err.iter() {
+                        eprintln!("{}", e.report("test", $src));
+                    }
+                    None
+                }
+            };
+            assert_eq!(v.map(|v| v.to_string()), $expected.map(ToString::to_string));
+        }
+    };
+}
+
+build_test! {
+    unary,
+    "main = -1",
+    Some("-1")
+}
+
+build_test! {
+    literal_clauses,
+    r#"
+fact 0 = 1
+fact n = n * fact (n - 1)
+main = fact 5
+"#,
+    Some("120")
+}
+
+build_test! {
+    guards,
+    r#"
+sign n
+  | n < 0 = 0 - 1
+  | n == 0 = 0
+  | true = 1
+main = [sign (0 - 4), sign 0, sign 9]
+"#,
+    Some("[-1, 0, 1]")
+}
+
+build_test! {
+    wildcard_clauses,
+    r#"
+pick 0 _ = "first"
+pick _ 0 = "second"
+pick _ _ = "neither"
+main = [pick 0 1, pick 1 0, pick 1 1]
+"#,
+    Some("[first, second, neither]")
+}
+
+build_test! {
+    guards_fall_through_to_next_clause,
+    r#"
+classify n
+  | n > 100 = "big"
+classify 0 = "zero"
+classify n = "small"
+main = [classify 200, classify 0, classify 5]
+"#,
+    Some("[big, zero, small]")
+}
+
+#[test]
+fn no_matching_clause() {
+    let ast = parse("only 1 = true\nmain = only 2").unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(errors[0], RuntimeError::NoMatchingClause(..)));
+}
+
+build_test! {
+    partial_application,
+    r#"
+add x y = x + y
+twice f x = f (f x)
+compose f g x = f (g x)
+fns = [add 1, add 10]
+main = [(head fns) 1, (add 1) 2, twice (add 3) 0, compose (add 1) (add 2) 0]
+"#,
+    Some("[2, 3, 6, 3]")
+}
+
+build_test! {
+    closures_capture_arguments,
+    r#"
+adder n = \x -> x + n
+main = (adder 5) 1
+"#,
+    Some("6")
+}
+
+#[test]
+fn applying_a_non_function() {
+    let ast = parse("main = (1 + 1) 2").unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(errors[0], RuntimeError::NotAFunction(..)));
+}
+
+build_test! {
+    ranges,
+    "main = [[1..5], [1, 3..9], [5, 4..1], [3..1]]",
+    Some("[[1, 2, 3, 4, 5], [1, 3, 5, 7, 9], [5, 4, 3, 2, 1], []]")
+}
+
+build_test! {
+    ranges_stop_at_the_largest_int,
+    "main = [[2147483645..2147483647], [0 - 2147483647, 0..2147483647]]",
+    Some("[[2147483645, 2147483646, 2147483647], [-2147483647, 0, 2147483647]]")
+}
+
+#[test]
+fn ranges_need_a_step() {
+    let src = "main = [1, 1..3]";
+    let Err(errors) = walk(&parse(src).unwrap()) else {
+        panic!("expected a runtime error");
+    };
+    let [RuntimeError::ZeroStep(span)] = errors.as_slice() else {
+        panic!("expected a zero step, got {errors:?}");
+    };
+    assert_eq!(span.col_start, 11, "{span:?}");
+}
+
+build_test! {
+    list_comprehension,
+    r#"
+main = [x * y | x <- [1..3], y <- [10, 20], x != 2]
+"#,
+    Some("[10, 20, 30, 60]")
+}
+
+build_test! {
+    ranges_sample,
+    include_str!("./../../../samples/ranges.snow"),
+    Some("[0, 2, 4, 6, 8, 10]")
+}
+
+build_test! {
+    do_block_runs_in_order,
+    r#"
+main = do {
+  x <- 1 + 2;
+  y <- [x, x * 2];
+  push y (x * 3)
+}
+"#,
+    Some("[3, 6, 9]")
+}
+
+build_test! {
+    locals_hide_read_line,
+    r#"
+add_one read_line : Int -> Int = read_line + 1
+main = add_one 1
+"#,
+    Some("2")
+}
+
+build_test! {
+    data_constructors,
+    r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+insert x Leaf = Node Leaf x Leaf
+insert x (Node l v r)
+  | x < v = Node (insert x l) v r
+  | true = Node l v (insert x r)
+size Leaf = 0
+size (Node l _ r) = size l + 1 + size r
+main = [size (insert 3 (insert 1 (insert 2 Leaf))), insert 1 Leaf]
+"#,
+    Some("[3, Node Leaf 1 Leaf]")
+}
+
+build_test! {
+    constructors_partially_applied,
+    r#"
+data Pair a b = Pair a b
+first (Pair a _) = a
+main = first ((Pair 1) 2)
+"#,
+    Some("1")
+}
+
+build_test! {
+    index_and_slice,
+    r#"
+xs = [10, 20, 30, 40]
+main = [xs[0], xs[3], length xs[1..3], length xs[4..4], [[1, 2], [3]][1][0]]
+"#,
+    Some("[10, 40, 2, 0, 3]")
+}
+
+build_test! {
+    string_index_and_slice,
+    r#"
+s = "snow"
+main = [s[1..3], s[0..0]]
+"#,
+    Some("[no, ]")
+}
+
+#[test]
+fn string_index_is_a_char() {
+    let ast = parse("main = \"snow\"[2]").unwrap();
+    let value = walk(&ast).unwrap();
+    assert!(matches!(value, Some(Value::Char('o', ..))));
+}
+
+#[test]
+fn index_out_of_bounds_points_at_the_index() {
+    let src = "main = [1, 2][1 + 1]";
+    let ast = parse(src).unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    let RuntimeError::IdxOutOfBounds(span) = errors[0] else {
+        panic!("expected index out of bounds, found {:?}", errors[0]);
+    };
+    assert_eq!(&src[span.idx_start..span.idx_end], "1 + 1");
+}
+
+build_test! {
+    string_interpolation,
+    include_str!("./../../../samples/interpolation.snow"),
+    Some("cell 3 is alive, [1, 2] { not a hole }")
+}
+
+#[test]
+fn builtin_given_too_few_arguments() {
+    let ast = parse("main = push [1]").unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(errors[0], RuntimeError::InvalidArguments(..)));
+}
+
+#[test]
+fn pure_functions_cannot_print() {
+    let src = r#"
+loud x = print x
+size xs : Array<Int> -> Int = length [loud xs]
+main = size [1]
+"#;
+    let ast = parse(src).unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(errors[0], RuntimeError::EffectInPureFunction(..)));
+}
+
+build_test! {
+    io_functions_can_print,
+    r#"
+greet name : String -> IO = print "hi {name}"
+main = do {
+  greet "snow";
+  1
+}
+"#,
+    Some("1")
+}
+
+build_test! {
+    instances_give_operators,
+    r#"
+data Size = Small | Large
+instance Eq Size {
+  eq Small Small = true;
+  eq Large Large = true;
+  eq _ _ = false
+}
+instance Ord Size {
+  lt Small Large = true;
+  lt _ _ = false
+}
+main = [Small == Small, Small != Large, Small < Large, Small >= Large, [Large] == [Large]]
+"#,
+    Some("[true, true, true, false, true]")
+}
+
+build_test! {
+    show_instance,
+    r#"
+data Point = Point Int Int
+instance Show Point {
+  show (Point x y) = "({x}, {y})"
+}
+main = "at {Point 1 2}, {show (Point 3 4)}"
+"#,
+    Some("at (1, 2), (3, 4)")
+}
+
+#[test]
+fn data_without_an_instance() {
+    let ast = parse("data Size = Small | Large\nmain = Small < Large").unwrap();
+    let Err(errors) = walk(&ast) else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(errors[0], RuntimeError::InvalidBinaryOp(..)));
+}
+
+build_test! {
+    user_classes_and_builtin_types,
+    r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+class Size a { size : a -> Int }
+instance Size (Tree a) {
+  size Leaf = 0;
+  size (Node l _ r) = size l + 1 + size r
+}
+data V = V Int Int
+instance Num V {
+  sub (V a b) (V c d) = V (a - c) (b - d);
+  neg (V a b) = V (0 - a) (0 - b)
+}
+main = [show (size (Node Leaf 1 (Node Leaf 2 Leaf))), show (eq 1 1), show [1], show (V 1 2 - V 3 4), show (-(V 1 2)), show (lt "a" "b")]
+"#,
+    Some("[2, true, [1], V -2 -2, V -1 -2, true]")
+}
+
+build_test! {
+    returned_closures_keep_their_environment,
+    r#"
+adder n = \x -> x + n
+add5 = adder 5
+main = [(adder 2) 3, add5 1, add5 10]
+"#,
+    Some("[5, 6, 15]")
+}
+
+build_test! {
+    higher_order_functions,
+    r#"
+map f xs = [f x | x <- xs]
+compose f g = \x -> f (g x)
+twice f = compose f f
+main = map (twice (\x -> x * 3)) [1, 2]
+"#,
+    Some("[9, 18]")
+}
+
+build_test! {
+    curried_application_over_several_calls,
+    r#"
+add a b c = a + b + c
+inc = add 1 0
+main = [inc 1, add 1 2 3, (add 1) 2 3]
+"#,
+    Some("[2, 6, 6]")
+}
+
+build_test! {
+    closures_in_arrays_and_data,
+    r#"
+data Op = Op (Int -> Int)
+run (Op f) x = f x
+fs n = [\x -> x + n, \x -> x * n]
+main = [run (Op (head (fs 10))) 1, run (Op (head (tail (fs 10)))) 2]
+"#,
+    Some("[11, 20]")
+}
+
+build_test! {
+    locals_shadow_builtins,
+    r#"
+apply length xs = length xs
+main = apply (\xs -> 42) [1, 2]
+"#,
+    Some("42")
+}
+
+/// Runs `main` of `src` with at most `max_depth` frames on the stack.
+fn walk_with_depth(src: &str, max_depth: usize) -> Result<Value> {
+    let mut scope = Scope {
+        max_depth,
+        ..Scope::default()
+    };
+    for expr in parse(src).unwrap().iter() {
+        eval_expr_with_scope(expr, &mut scope)?;
+    }
+    force(scope.global["main"].clone(), &scope)
+}
+
+#[test]
+fn tail_calls_run_in_constant_space() {
+    let src = r#"
+count n acc : Int -> Int -> Int = if n == 0 then acc else count (n - 1) (acc + 1)
+down 0 = 0
+down n = down (n - 1)
+even n = if n == 0 then true else odd (n - 1)
+odd n = if n == 0 then false else even (n - 1)
+main = [count 100000 0, down 100000, if even 100001 then 1 else 0]
+"#;
+    let value = walk_with_depth(src, 50).map(|value| value.to_string());
+    assert_eq!(value.ok(), Some("[100000, 0, 0]".to_string()));
+}
+
+#[test]
+fn deep_recursion_stops_at_the_limit() {
+    let src = "depth n = if n == 0 then 0 else 1 + depth (n - 1)\nmain = depth 100000";
+    let Err(error) = walk_with_depth(src, 1000) else {
+        panic!("expected the recursion to stop");
+    };
+    assert!(
+        matches!(error, RuntimeError::RecursionLimit(..)),
+        "{error:?}"
+    );
+}
+
+build_test! {
+    deep_recursion_runs_off_the_rust_stack,
+    r#"
+depth n = if n == 0 then 0 else 1 + depth (n - 1)
+main = depth 100000
+"#,
+    Some("100000")
+}
+
+build_test! {
+    recursive_show_with_interpolation,
+    r#"
+data List = Nil | Cons Int List
+instance Show List {
+  show Nil = "";
+  show (Cons x xs) = "{x} {xs}"
+}
+build n = if n == 0 then Nil else Cons n (build (n - 1))
+start s = s[0..7]
+main = start (show (build 200))
+"#,
+    Some("200 199")
+}
+
+fn walk_lazy(src: &str) -> std::result::Result<Option<String>, Vec<RuntimeError>> {
+    let ast = parse(src).unwrap();
+    let scope = Scope {
+        lazy: true,
+        ..Scope::default()
+    };
+    walk_with_scope(&ast, scope).map(|value| value.map(|value| value.to_string()))
+}
+
+#[test]
+fn lazy_take_from_an_infinite_stream() {
+    let src = r#"
+data Stream a = Cons a (Stream a) | Nil
+iterate f x = Cons x (iterate f (f x))
+take 0 _ = Nil
+take n Nil = Nil
+take n (Cons x xs) = Cons x (take (n - 1) xs)
+main = take 3 (iterate (\x -> x * 2) 1)
+"#;
+    let expected = "Cons 1 (Cons 2 (Cons 4 Nil))".to_string();
+    assert_eq!(walk_lazy(src).ok(), Some(Some(expected)));
+}
+
+#[test]
+fn lazy_array_items_only_run_when_needed() {
+    let src = "main = head [1, nth [] 3]";
+    assert_eq!(walk_lazy(src).ok(), Some(Some("1".to_string())));
+    let errors = walk(&parse(src).unwrap()).unwrap_err();
+    assert!(matches!(errors[0], RuntimeError::IdxOutOfBounds(..)));
+}
+
+#[test]
+fn lazy_arguments_run_at_most_once() {
+    let doubled = (0..25).fold("1".to_string(), |arg, _| format!("double ({arg})"));
+    let src = format!("double x = x + x\nmain = {doubled}");
+    assert_eq!(walk_lazy(&src).ok(), Some(Some((1 << 25).to_string())));
+}
+
+#[test]
+fn lazy_seq_runs_its_first_argument() {
+    let run = |main: &str| walk_lazy(&format!("const x y = y\nmain = {main}"));
+    assert_eq!(run("const (nth [] 3) 1").ok(), Some(Some("1".to_string())));
+    assert!(run("seq (nth [] 3) 1").is_err());
+    assert_eq!(run("seq [nth [] 3] 1").ok(), Some(Some("1".to_string())));
+    assert!(run("deep_seq [nth [] 3] 1").is_err());
+}
+
+#[test]
+fn lazy_results_are_shown_in_full() {
+    let src = "pair x y = [x, y]\nmain = pair (1 + 1) [2 * 3]";
+    assert_eq!(walk_lazy(src).ok(), Some(Some("[2, [6]]".to_string())));
+}
+
+#[test]
+fn lazy_runaway_recursion_is_an_error() {
+    // every call keeps a thunk of `n + 1` that points at the call before
+    let src = "nats n = [n] + (nats (n + 1))\nmain = print (head (nats 0))";
+    let scope = Scope {
+        lazy: true,
+        max_depth: 100_000,
+        ..Scope::default()
+    };
+    let result = walk_with_scope(&parse(src).unwrap(), scope);
+    assert!(matches!(
+        result.unwrap_err()[..],
+        [RuntimeError::RecursionLimit(..)]
+    ));
+}
+
+/// Keeps every event as a line of text.
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl EvalObserver for Recorder {
+    fn enter(&mut self, name: &str, args: &[Value], depth: usize) {
+        let args = args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        self.0
+            .push(format!("{depth} enter {name} {}", args.join(" ")));
+    }
+
+    fn exit(&mut self, name: &str, value: &Value, depth: usize) {
+        self.0.push(format!("{depth} exit {name} {value}"));
+    }
+}
+
+fn observe(src: &str, observer: Rc<RefCell<dyn EvalObserver>>) -> Option<Value> {
+    let scope = Scope {
+        observer: Some(observer),
+        ..Scope::default()
+    };
+    walk_with_scope(&parse(src).unwrap(), scope).unwrap()
+}
+
+#[test]
+fn observer_hears_about_named_calls() {
+    let src = r#"
+fact 0 = 1
+fact n = n * fact (n - 1)
+twice f x = f (f x)
+main = [fact 1, twice (\x -> x + 1) 0]
+"#;
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    let value = observe(src, recorder.clone());
+    assert_eq!(value.map(|value| value.to_string()), Some("[1, 2]".into()));
+    let expected = [
+        "0 enter fact 1",
+        "1 enter fact 0",
+        "1 exit fact 1",
+        "0 exit fact 1",
+        "0 enter twice \\x -> (+ x 1) 0",
+        "0 exit twice 2",
+    ];
+    assert_eq!(recorder.borrow().0, expected);
+}
+
+/// Lets a test read what a [`Tracer`] wrote.
+#[derive(Clone, Default)]
+struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn tracer_indents_and_filters() {
+    let src = r#"
+countdown 0 = "done"
+countdown n = countdown (n - 1)
+shout s = "{s}!"
+main = shout (countdown 2)
+"#;
+    let buffer = Buffer::default();
+    observe(
+        src,
+        Rc::new(RefCell::new(Tracer::new(buffer.clone(), vec![]))),
+    );
+    let trace = String::from_utf8(buffer.0.take()).unwrap();
+    let expected = r#"-> countdown 2
+  -> countdown 1
+    -> countdown 0
+    <- countdown = "done"
+  <- countdown = "done"
+<- countdown = "done"
+-> shout "done"
+<- shout = "done!"
+"#;
+    assert_eq!(trace, expected);
+
+    let buffer = Buffer::default();
+    let only = vec!["shout".to_string()];
+    observe(
+        src,
+        Rc::new(RefCell::new(Tracer::new(buffer.clone(), only))),
+    );
+    let trace = String::from_utf8(buffer.0.take()).unwrap();
+    assert_eq!(trace, "-> shout \"done\"\n<- shout = \"done!\"\n");
+}
+
+#[test]
+fn traced_tail_calls_run_in_constant_space() {
+    let src = r#"
+count n : Int -> Int = if n == 10000 then n else count (n + 1)
+main = count 0
+"#;
+    let tracer = Tracer::new(Buffer::default(), vec!["nothing".into()]);
+    let scope = Scope {
+        max_depth: 100,
+        observer: Some(Rc::new(RefCell::new(tracer))),
+        ..Scope::default()
+    };
+    let value = walk_with_scope(&parse(src).unwrap(), scope).unwrap();
+    assert_eq!(value.map(|value| value.to_string()), Some("10000".into()));
+}
+
+fn walk_with_natives(src: &str, natives: Vec<Native>) -> Result<Option<String>> {
+    let mut scope = Scope::default();
+    for native in natives {
+        scope.register(native);
+    }
+    let ast = parse(src).unwrap();
+    match walk_with_scope(&ast, scope) {
+        Ok(value) => Ok(value.map(|value| value.to_string())),
+        Err(mut errors) => Err(errors.remove(0)),
+    }
+}
+
+fn double() -> Native {
+    Native::new("double", 1, |args| match &args[0] {
+        Value::Int(i, span) => Ok(Value::Int(i * 2, *span)),
+        _ => Err(RuntimeError::InvalidArguments(Span::default())),
+    })
+    .signature("Int -> Int")
+}
+
+#[test]
+fn registered_natives_are_functions() {
+    let src = r#"
+twice f x = f (f x)
+main = [double 4, twice double 1, length [double]]
+"#;
+    let value = walk_with_natives(src, vec![double()]);
+    assert_eq!(value.ok().flatten(), Some("[8, 4, 1]".into()));
+}
+
+#[test]
+fn natives_replace_builtins_and_locals_hide_them() {
+    let length = Native::new("length", 1, |_| Ok(Value::Int(42, Span::default())));
+    let src = "shadow double = double\nmain = [length [], shadow 3]";
+    let value = walk_with_natives(src, vec![double(), length]);
+    assert_eq!(value.ok().flatten(), Some("[42, 3]".into()));
+}
+
+#[test]
+fn native_errors_without_a_span_point_at_the_call() {
+    let src = r#"main = double "four""#;
+    let error = walk_with_natives(src, vec![double()]).unwrap_err();
+    let RuntimeError::InvalidArguments(span) = error else {
+        panic!("expected invalid arguments, got {error:?}");
+    };
+    assert_eq!(&src[span.idx_start..span.idx_end], r#"double "four""#);
+}
+
+#[test]
+fn scope_calls_globals_constructors_and_natives() {
+    let src = r#"
+data Pair a = Pair a a
+add x y = x + y
+answer = add 40 2
+"#;
+    let mut scope = Scope::default();
+    scope.define(&parse(src).unwrap());
+    let int = |i| Value::Int(i, Span::default());
+    let called = |name, args| scope.call(name, args).map(|value| value.to_string());
+    assert_eq!(called("add", vec![int(1), int(2)]).ok(), Some("3".into()));
+    assert_eq!(called("answer", vec![]).ok(), Some("42".into()));
+    assert!(matches!(
+        called("length", vec![int(1)]),
+        Err(RuntimeError::InvalidArguments(..))
+    ));
+    assert_eq!(
+        called("Pair", vec![int(1), int(2)]).ok(),
+        Some("Pair 1 2".into())
+    );
+    assert!(matches!(
+        called("missing", vec![]),
+        Err(RuntimeError::Undefined(name, _)) if name == "missing"
+    ));
+}
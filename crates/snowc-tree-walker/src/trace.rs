@@ -0,0 +1,75 @@
+//! Hooks into evaluation. The evaluator tells the [`EvalObserver`] in
+//! [`crate::Scope::observer`] about every call to a named function, a top
+//! level function or an instance method, once it has all of its arguments,
+//! and again when it returns. `--trace` prints them with a [`Tracer`].
+use super::Value;
+use std::io::Write;
+
+pub trait EvalObserver {
+    /// A call to `name` starts. `depth` counts the calls already under way,
+    /// so the outermost is at 0.
+    fn enter(&mut self, name: &str, args: &[Value], depth: usize);
+    /// The call to `name` at `depth` gave back `value`. A call that fails
+    /// doesn't return.
+    fn exit(&mut self, name: &str, value: &Value, depth: usize);
+}
+
+/// Writes each call and what it gave back on a line of its own, indented
+/// by its depth.
+/// ```text
+/// -> fact 2
+///   -> fact 1
+///   <- fact = 1
+/// <- fact = 2
+/// ```
+pub struct Tracer {
+    out: Box<dyn Write>,
+    /// The functions to show, all of them when it is empty.
+    only: Vec<String>,
+}
+
+impl Tracer {
+    pub fn new(out: impl Write + 'static, only: Vec<String>) -> Self {
+        Self {
+            out: Box::new(out),
+            only,
+        }
+    }
+
+    fn shows(&self, name: &str) -> bool {
+        self.only.is_empty() || self.only.iter().any(|only| only == name)
+    }
+}
+
+impl EvalObserver for Tracer {
+    fn enter(&mut self, name: &str, args: &[Value], depth: usize) {
+        if !self.shows(name) {
+            return;
+        }
+        let indent = "  ".repeat(depth);
+        let args = args
+            .iter()
+            .fold(String::new(), |args, arg| args + " " + &arg_text(arg));
+        // tracing never stops the program it traces
+        let _ = writeln!(self.out, "{indent}-> {name}{args}");
+    }
+
+    fn exit(&mut self, name: &str, value: &Value, depth: usize) {
+        if !self.shows(name) {
+            return;
+        }
+        let indent = "  ".repeat(depth);
+        let _ = writeln!(self.out, "{indent}<- {name} = {}", arg_text(value));
+    }
+}
+
+/// A value the way it would be written as an argument.
+fn arg_text(value: &Value) -> String {
+    match value {
+        Value::String(string, ..) => format!("{string:?}"),
+        Value::Char(c, ..) => format!("{c:?}"),
+        Value::Variant(_, fields, ..) if !fields.is_empty() => format!("({value})"),
+        Value::Closure { .. } => "<function>".into(),
+        value => value.to_string(),
+    }
+}
@@ -1,5 +1,6 @@
-use snowc_parse::{Expr, Span};
-use std::fmt;
+use super::{Code, Env};
+use snowc_parse::Span;
+use std::{cell::RefCell, fmt, rc::Rc};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
@@ -9,7 +10,42 @@ pub enum Value {
     String(String, Span),
     Char(char, Span),
     Array(Vec<Self>, Span),
-    Func(Expr, Span),
+    /// A function together with the bindings it was created with.
+    /// `\x -> \y -> body` is one closure with two parameters, it can be
+    /// given fewer arguments and gives back a closure for the rest. Top level
+    /// values without parameters are closures with none, they are run when
+    /// looked up.
+    Closure {
+        params: Vec<String>,
+        body: Rc<Code>,
+        env: Rc<Env>,
+        /// The name of the top level function or instance method it is,
+        /// what an [`crate::EvalObserver`] is told was called.
+        name: Option<Rc<str>>,
+        span: Span,
+    },
+    /// A value built by a `data` constructor.
+    Variant(String, Vec<Self>, Span),
+    /// An argument or array item in lazy mode. It runs the first time
+    /// something needs its value and keeps it, everything holding the same
+    /// thunk sees the result.
+    Thunk(Rc<RefCell<Thunk>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Thunk {
+    Pending(Rc<Code>, Rc<Env>),
+    /// Never a thunk itself.
+    Done(Value),
+}
+
+impl Thunk {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Pending(code, _) => code.span(),
+            Self::Done(value) => value.span(),
+        }
+    }
 }
 
 impl Value {
@@ -21,9 +57,15 @@ impl Value {
             Self::String(_, span) => *span,
             Self::Char(_, span) => *span,
             Self::Array(_, span) => *span,
-            Self::Func(_, span) => *span,
+            Self::Closure { span, .. } => *span,
+            Self::Variant(.., span) => *span,
+            Self::Thunk(thunk) => thunk.borrow().span(),
         }
     }
+
+    pub fn is_true(&self) -> bool {
+        matches!(self, Self::Bool(true, ..))
+    }
 }
 
 impl fmt::Display for Value {
@@ -48,7 +90,28 @@ impl fmt::Display for Value {
                 a += "]";
                 write!(f, "{a}")
             }
-            Self::Func(expr, ..) => write!(f, "{expr}"),
+            Self::Closure { params, body, .. } => {
+                for param in params.iter() {
+                    write!(f, "\\{param} -> ")?;
+                }
+                write!(f, "{body}")
+            }
+            Self::Variant(name, fields, ..) => {
+                write!(f, "{name}")?;
+                for field in fields.iter() {
+                    match field {
+                        Self::Variant(_, fields, ..) if !fields.is_empty() => {
+                            write!(f, " ({field})")?
+                        }
+                        _ => write!(f, " {field}")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Thunk(thunk) => match &*thunk.borrow() {
+                Thunk::Done(value) => write!(f, "{value}"),
+                _ => write!(f, "<thunk>"),
+            },
         }
     }
 }
@@ -0,0 +1,197 @@
+use crate::Type;
+use annotate_snippets::{
+    display_list::{DisplayList, FormatOptions},
+    snippet::{Annotation, AnnotationType, Slice, Snippet, SourceAnnotation},
+};
+use snowc_parse::{Op, Span};
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone)]
+pub enum TypeError {
+    #[error("expected '{expected}' but found '{found}'")]
+    Mismatch {
+        expected: Box<Type>,
+        found: Box<Type>,
+        span: Span,
+    },
+    /// Two branches of an `if` or `match` that give back different types,
+    /// `span` is the branch that disagrees with the one at `first_span`.
+    #[error("branches give back '{first}' and '{second}'")]
+    BranchMismatch {
+        first: Box<Type>,
+        second: Box<Type>,
+        first_span: Box<Span>,
+        span: Span,
+    },
+    #[error("'{name}' runs an action here but its type '{ty}' doesn't give back IO")]
+    EffectInPureFunction {
+        name: String,
+        ty: Box<Type>,
+        span: Span,
+    },
+    #[error("'{ty}' would have to contain itself")]
+    InfiniteType { ty: Box<Type>, span: Span },
+    #[error("undefined '{0}'")]
+    Undefined(String, Span),
+    #[error("unknown type '{0}'")]
+    UnknownType(String, Span),
+    #[error("'{0}' takes {1} type arguments but was given {2}")]
+    TypeArity(String, usize, usize, Span),
+    #[error("'{0}' takes {1} arguments but the pattern gives it {2}")]
+    ConstructorArity(String, usize, usize, Span),
+    /// Some value matches none of the clauses of a function, the `String`
+    /// is an example call that would fail.
+    #[error("no clause of '{0}' matches '{1}'")]
+    MissingClause(String, String, Span),
+    #[error("'{ty}' is not a function")]
+    NotAFunction { ty: Box<Type>, span: Span },
+    #[error("'{op}' can't be used on '{ty}'")]
+    InvalidOperand { op: Op, ty: Box<Type>, span: Span },
+    #[error("'{ty}' is not an instance of '{class}'")]
+    MissingInstance {
+        class: String,
+        ty: Box<Type>,
+        span: Span,
+    },
+    #[error("instances can only be given for 'data' types, not '{0}'")]
+    InvalidInstance(String, Span),
+    #[error("'{name}' is declared as '{declared}' but its body is '{inferred}'")]
+    SignatureTooGeneral {
+        name: String,
+        declared: Box<Type>,
+        inferred: Box<Type>,
+        span: Span,
+    },
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Mismatch { span, .. }
+            | Self::BranchMismatch { span, .. }
+            | Self::EffectInPureFunction { span, .. }
+            | Self::InfiniteType { span, .. }
+            | Self::NotAFunction { span, .. }
+            | Self::InvalidOperand { span, .. }
+            | Self::MissingInstance { span, .. }
+            | Self::SignatureTooGeneral { span, .. }
+            | Self::Undefined(.., span)
+            | Self::UnknownType(.., span)
+            | Self::TypeArity(.., span)
+            | Self::ConstructorArity(.., span)
+            | Self::InvalidInstance(.., span)
+            | Self::MissingClause(.., span) => *span,
+        }
+    }
+
+    /// Other places in the source that help explain the error.
+    pub fn secondary(&self) -> Vec<(String, Span)> {
+        match self {
+            Self::BranchMismatch {
+                first, first_span, ..
+            } => vec![(format!("this branch is '{first}'"), **first_span)],
+            _ => vec![],
+        }
+    }
+
+    pub fn report(&self, filename: &str, src: &str) -> String {
+        let label = self.to_string();
+        let primary = match self {
+            Self::BranchMismatch { second, .. } => format!("but this one is '{second}'"),
+            _ => label.clone(),
+        };
+        let secondary = self.secondary();
+        let range = |span: Span| {
+            let end = span.idx_end.min(src.len());
+            (span.idx_start.min(end), end)
+        };
+        let mut annotations = vec![SourceAnnotation {
+            label: &primary,
+            annotation_type: AnnotationType::Error,
+            range: range(self.span()),
+        }];
+        for (label, span) in secondary.iter() {
+            annotations.push(SourceAnnotation {
+                label,
+                annotation_type: AnnotationType::Info,
+                range: range(*span),
+            });
+        }
+        let snippet = Snippet {
+            title: Some(Annotation {
+                label: Some(&label),
+                id: None,
+                annotation_type: AnnotationType::Error,
+            }),
+            footer: vec![],
+            slices: vec![Slice {
+                source: src,
+                line_start: 1,
+                origin: Some(filename),
+                fold: true,
+                annotations,
+            }],
+            opt: FormatOptions {
+                color: true,
+                ..Default::default()
+            },
+        };
+        DisplayList::from(snippet).to_string()
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// Only the type checker looks for these, `snowc --dynamic` doesn't
+    /// report them.
+    #[error("unreachable clause of '{0}'")]
+    UnreachableClause(String, Span),
+    /// The class and the method an instance leaves out, using it on that
+    /// type fails when the program runs.
+    #[error("instance of '{0}' doesn't define '{1}'")]
+    MissingMethod(String, String, Span),
+}
+
+impl Warning {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnreachableClause(_, s) | Self::MissingMethod(.., s) => *s,
+        }
+    }
+
+    pub fn report(&self, filename: &str, src: &str) -> String {
+        let span = self.span();
+        let label = self.to_string();
+        let annotation = match self {
+            Self::UnreachableClause(..) => {
+                "earlier clauses already match every value this one does"
+            }
+            Self::MissingMethod(..) => "calling it on this type is an error",
+        };
+        let end = span.idx_end.min(src.len());
+        let snippet = Snippet {
+            title: Some(Annotation {
+                label: Some(&label),
+                id: None,
+                annotation_type: AnnotationType::Warning,
+            }),
+            footer: vec![],
+            slices: vec![Slice {
+                source: src,
+                line_start: 1,
+                origin: Some(filename),
+                fold: true,
+                annotations: vec![SourceAnnotation {
+                    label: annotation,
+                    annotation_type: AnnotationType::Warning,
+                    range: (span.idx_start.min(end), end),
+                }],
+            }],
+            opt: FormatOptions {
+                color: true,
+                ..Default::default()
+            },
+        };
+        DisplayList::from(snippet).to_string()
+    }
+}
@@ -0,0 +1,282 @@
+//! Exhaustiveness and usefulness of the clauses of a function, following
+//! Maranget's "Warnings for pattern matching". Patterns are compared as rows
+//! of a matrix, a row is useful when some value matches it and none of the
+//! rows above it.
+use crate::{TypeError, Warning};
+use snowc_parse::{Atom, Data, Expr, Match, Pat, Visitor};
+use std::collections::HashMap;
+
+/// A pattern with names and spans stripped, only its shape matters here.
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    Wild,
+    Con(Ctor, Vec<Pattern>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Named(String),
+    Bool(bool),
+    /// Ints, strings and the rest, no match lists all of them.
+    Lit(String),
+}
+
+impl Pattern {
+    fn from_pat(pat: &Pat) -> Self {
+        match pat {
+            Pat::Wildcard(..) | Pat::Id(..) => Self::Wild,
+            Pat::Lit(Atom::Bool(b, ..)) => Self::Con(Ctor::Bool(*b), vec![]),
+            Pat::Lit(atom) => Self::Con(Ctor::Lit(format!("{atom:?}")), vec![]),
+            Pat::Constructor(name, args, ..) => Self::Con(
+                Ctor::Named(name.clone()),
+                args.iter().map(Self::from_pat).collect(),
+            ),
+        }
+    }
+
+    fn write(&self, out: &mut String, nested: bool) {
+        match self {
+            Self::Wild => out.push('_'),
+            Self::Con(Ctor::Bool(b), _) => out.push_str(&b.to_string()),
+            Self::Con(Ctor::Lit(lit), _) => out.push_str(lit),
+            Self::Con(Ctor::Named(name), args) if args.is_empty() => out.push_str(name),
+            Self::Con(Ctor::Named(name), args) => {
+                if nested {
+                    out.push('(');
+                }
+                out.push_str(name);
+                for arg in args.iter() {
+                    out.push(' ');
+                    arg.write(out, true);
+                }
+                if nested {
+                    out.push(')');
+                }
+            }
+        }
+    }
+}
+
+type Row = Vec<Pattern>;
+
+/// The constructors of every `data` type, used to tell when a column
+/// names all of them.
+pub(crate) struct Signatures {
+    /// Constructor to the type it builds.
+    owner: HashMap<String, String>,
+    /// Type to its constructors and how many fields each takes.
+    ctors: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl Signatures {
+    pub(crate) fn new<'a>(data: impl Iterator<Item = &'a Data>) -> Self {
+        let mut owner = HashMap::new();
+        let mut ctors = HashMap::new();
+        for data in data {
+            let variants = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    owner.insert(variant.name.clone(), data.name.clone());
+                    (variant.name.clone(), variant.fields.len())
+                })
+                .collect();
+            ctors.insert(data.name.clone(), variants);
+        }
+        Self { owner, ctors }
+    }
+
+    /// Every constructor of the type `ctor` belongs to, `None` when there
+    /// are too many to list.
+    fn complete(&self, ctor: &Ctor) -> Option<Vec<(Ctor, usize)>> {
+        match ctor {
+            Ctor::Bool(_) => Some(vec![(Ctor::Bool(true), 0), (Ctor::Bool(false), 0)]),
+            Ctor::Lit(_) => None,
+            Ctor::Named(name) => {
+                let ctors = &self.ctors[self.owner.get(name)?];
+                Some(
+                    ctors
+                        .iter()
+                        .map(|(name, arity)| (Ctor::Named(name.clone()), *arity))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Rows of `matrix` that can match `ctor`, with its fields spread out
+    /// in place of the first column.
+    fn specialize(&self, matrix: &[Row], ctor: &Ctor, arity: usize) -> Vec<Row> {
+        matrix
+            .iter()
+            .filter_map(|row| {
+                let (head, rest) = row.split_first()?;
+                let mut fields = match head {
+                    Pattern::Wild => vec![Pattern::Wild; arity],
+                    Pattern::Con(c, args) if c == ctor => args.clone(),
+                    Pattern::Con(..) => return None,
+                };
+                fields.extend(rest.iter().cloned());
+                Some(fields)
+            })
+            .collect()
+    }
+
+    /// Rows starting with a wildcard, without it.
+    fn default(&self, matrix: &[Row]) -> Vec<Row> {
+        matrix
+            .iter()
+            .filter(|row| row.first() == Some(&Pattern::Wild))
+            .map(|row| row[1..].to_vec())
+            .collect()
+    }
+
+    /// The constructors named in the first column, each once.
+    fn head_ctors(&self, matrix: &[Row]) -> Vec<(Ctor, usize)> {
+        let mut heads: Vec<(Ctor, usize)> = vec![];
+        for row in matrix.iter() {
+            if let Some(Pattern::Con(ctor, args)) = row.first() {
+                if !heads.iter().any(|(c, _)| c == ctor) {
+                    heads.push((ctor.clone(), args.len()));
+                }
+            }
+        }
+        heads
+    }
+
+    /// Whether `row` matches some value no row of `matrix` does.
+    fn useful(&self, matrix: &[Row], row: &[Pattern]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return matrix.is_empty();
+        };
+        let spread = |ctor: &Ctor, fields: Vec<Pattern>| {
+            let mut row = fields;
+            row.extend(rest.iter().cloned());
+            (self.specialize(matrix, ctor, row.len() - rest.len()), row)
+        };
+        match head {
+            Pattern::Con(ctor, args) => {
+                let (matrix, row) = spread(ctor, args.clone());
+                self.useful(&matrix, &row)
+            }
+            Pattern::Wild => {
+                let heads = self.head_ctors(matrix);
+                match heads.first().and_then(|(c, _)| self.complete(c)) {
+                    Some(all)
+                        if all.iter().all(|(c, _)| heads.iter().any(|(h, _)| h == c)) =>
+                    {
+                        all.iter().any(|(ctor, arity)| {
+                            let (matrix, row) = spread(ctor, vec![Pattern::Wild; *arity]);
+                            self.useful(&matrix, &row)
+                        })
+                    }
+                    _ => self.useful(&self.default(matrix), rest),
+                }
+            }
+        }
+    }
+
+    /// A row of `width` patterns that no row of `matrix` matches.
+    fn witness(&self, matrix: &[Row], width: usize) -> Option<Row> {
+        if width == 0 {
+            return matrix.is_empty().then(Vec::new);
+        }
+        let heads = self.head_ctors(matrix);
+        let all = heads.first().and_then(|(c, _)| self.complete(c));
+        if let Some(all) = &all {
+            if all.iter().all(|(c, _)| heads.iter().any(|(h, _)| h == c)) {
+                return all.iter().find_map(|(ctor, arity)| {
+                    let matrix = self.specialize(matrix, ctor, *arity);
+                    let mut found = self.witness(&matrix, arity + width - 1)?;
+                    let rest = found.split_off(*arity);
+                    let mut row = vec![Pattern::Con(ctor.clone(), found)];
+                    row.extend(rest);
+                    Some(row)
+                });
+            }
+        }
+        let rest = self.witness(&self.default(matrix), width - 1)?;
+        // name a constructor that is missing when there is one to name
+        let head = all
+            .into_iter()
+            .flatten()
+            .find(|(c, _)| !heads.iter().any(|(h, _)| h == c))
+            .map(|(ctor, arity)| Pattern::Con(ctor, vec![Pattern::Wild; arity]))
+            .unwrap_or(Pattern::Wild);
+        let mut row = vec![head];
+        row.extend(rest);
+        Some(row)
+    }
+
+    /// Warns about clauses that can never match and fails when some value
+    /// matches none of them.
+    fn check_match(
+        &self,
+        name: &str,
+        m: &Match,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(), TypeError> {
+        let mut matrix: Vec<Row> = vec![];
+        for arm in m.arms.iter() {
+            let row = arm.pats.iter().map(Pattern::from_pat).collect::<Row>();
+            if !self.useful(&matrix, &row) {
+                warnings.push(Warning::UnreachableClause(name.into(), arm.span));
+            }
+            // a guard can fail so its clause doesn't cover anything, unless
+            // it is a plain `true`
+            let guarded = arm
+                .guard
+                .as_ref()
+                .is_some_and(|guard| !matches!(guard, Expr::Atom(Atom::Bool(true, ..))));
+            if !guarded {
+                matrix.push(row);
+            }
+        }
+        let Some(missing) = self.witness(&matrix, m.exprs.len()) else {
+            return Ok(());
+        };
+        let mut example = name.to_string();
+        for pat in missing.iter() {
+            example.push(' ');
+            pat.write(&mut example, true);
+        }
+        Err(TypeError::MissingClause(name.into(), example, m.span))
+    }
+
+    /// Checks every function made of clauses, methods of instances
+    /// included.
+    pub(crate) fn check(
+        &self,
+        ast: &[Expr],
+        warnings: &mut Vec<Warning>,
+    ) -> Vec<TypeError> {
+        let mut matches = Matches::default();
+        let mut errors = vec![];
+        let funcs = ast.iter().flat_map(|expr| match expr {
+            Expr::Instance(instance) => instance.methods.iter(),
+            _ => std::slice::from_ref(expr).iter(),
+        });
+        for expr in funcs {
+            let Expr::Func(name, _, body, ..) = expr else {
+                continue;
+            };
+            matches.visit_expr(body);
+            for m in std::mem::take(&mut matches.0) {
+                if let Err(error) = self.check_match(name, &m, warnings) {
+                    errors.push(error);
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Default)]
+struct Matches(Vec<Match>);
+
+impl Visitor for Matches {
+    fn visit_match(&mut self, m: &Match) {
+        self.0.push(m.clone());
+        snowc_parse::visit::walk_match(self, m);
+    }
+}
@@ -0,0 +1,932 @@
+use crate::{error::TypeError, Scheme, Type, TypeVar};
+use snowc_parse::{
+    builtin::{classes, lookup, BUILTINS},
+    Alias, App, Atom, Binary, Class, Data, Do, Expr, Extern, Index, Instance, Match, Op,
+    Pat, Span, Stmt, TypeInfo, Unary,
+};
+use std::collections::{HashMap, HashSet};
+
+type Result<T> = std::result::Result<T, TypeError>;
+
+/// `ty` has to be an instance of `class`. Checked once the function it comes
+/// from is inferred, a variable that is still unknown by then becomes part
+/// of the function's scheme instead.
+#[derive(Debug, Clone)]
+struct Constraint {
+    class: String,
+    ty: Type,
+    /// The operator that asked for the instance, if any, to word the error.
+    op: Option<Op>,
+    span: Span,
+}
+
+/// `container[i]` gives `item`, a slice `container[i..j]` has no item and
+/// gives the container back. Only an `Array` or a `String` can be indexed,
+/// until the container is known this waits the way a [`Constraint`] does.
+#[derive(Debug, Clone)]
+struct Indexing {
+    container: Type,
+    item: Option<Type>,
+    span: Span,
+}
+
+/// The class an operator needs its operands to be an instance of.
+fn operator_class(op: Op) -> &'static str {
+    match op {
+        Op::Eq | Op::Neq => "Eq",
+        Op::Grt | Op::Les | Op::GrtEq | Op::LesEq => "Ord",
+        Op::Plus => "Add",
+        _ => "Num",
+    }
+}
+
+enum Unify {
+    Mismatch,
+    Occurs(Type),
+}
+
+/// Algorithm W over a mutable substitution, `bindings[var]` is what `var`
+/// has been unified with so far.
+#[derive(Default)]
+pub(crate) struct Infer {
+    bindings: Vec<Option<Type>>,
+    pub(crate) globals: HashMap<String, Scheme>,
+    locals: Vec<(String, Type)>,
+    constraints: Vec<Constraint>,
+    /// Constraints on variables the function being inferred left open,
+    /// they end up in its scheme.
+    deferred: Vec<(String, TypeVar)>,
+    indexing: Vec<Indexing>,
+    /// Indexing of variables the function being inferred left open, it
+    /// ends up in its scheme.
+    deferred_indexing: Vec<(TypeVar, Option<Type>)>,
+    /// Every method and the class it belongs to, with the class variable
+    /// in its scheme.
+    pub(crate) methods: HashMap<String, (String, Scheme)>,
+    /// Every class and the names of its methods.
+    pub(crate) classes: HashMap<String, Vec<String>>,
+    /// The class and `data` type of every instance.
+    instances: HashSet<(String, String)>,
+    /// The type of every expression in the function being inferred, so
+    /// actions in a function that isn't IO can be found once it is done.
+    actions: Vec<(Type, Span)>,
+    /// The type of every expression and bound name checked so far, kept
+    /// across functions for [`crate::Checked::type_at`].
+    pub(crate) typed: Vec<(Span, Type)>,
+    /// Every `data` type and how many parameters it takes.
+    data: HashMap<String, usize>,
+    aliases: HashMap<String, Alias>,
+}
+
+impl Infer {
+    pub(crate) fn new(
+        data: HashMap<String, usize>,
+        aliases: HashMap<String, Alias>,
+    ) -> Self {
+        let mut infer = Self {
+            data,
+            aliases,
+            ..Self::default()
+        };
+        infer.builtins();
+        infer
+    }
+
+    fn builtins(&mut self) {
+        for builtin in BUILTINS.iter() {
+            let type_info = builtin.type_info();
+            let ty =
+                crate::signature(self, &type_info, &mut HashMap::new(), Span::default())
+                    .expect("builtin signatures only use known types");
+            self.globals
+                .insert(builtin.name.into(), Scheme::generalize(ty));
+        }
+        for class in classes().iter() {
+            self.declare_class(class)
+                .expect("builtin classes only use known types");
+        }
+    }
+
+    /// Takes the host's word for the type of `ext`. One without a
+    /// signature takes its arguments and gives back a value of any type.
+    pub(crate) fn declare_extern(&mut self, ext: &Extern) -> Result<()> {
+        // validation reports a signature that doesn't parse
+        let type_info = ext.type_info().unwrap_or_default();
+        let ty = if type_info.is_empty() {
+            let ret = self.fresh();
+            (0..ext.arity).fold(ret, |ret, _| Type::func(self.fresh(), ret))
+        } else {
+            crate::signature(self, &type_info, &mut HashMap::new(), Span::default())?
+        };
+        self.globals
+            .insert(ext.name.clone(), Scheme::generalize(ty));
+        Ok(())
+    }
+
+    pub(crate) fn fresh(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Var(self.bindings.len() - 1)
+    }
+
+    /// Follows bound variables until it reaches a type that isn't one.
+    fn resolve(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty {
+            match &self.bindings[var] {
+                Some(bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// Applies everything learned so far to every part of `ty`.
+    pub(crate) fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Array(inner) => Type::Array(Box::new(self.zonk(&inner))),
+            Type::IO(inner) => Type::IO(Box::new(self.zonk(&inner))),
+            Type::Con(name, args) => {
+                Type::Con(name, args.iter().map(|arg| self.zonk(arg)).collect())
+            }
+            Type::Func(param, ret) => Type::func(self.zonk(&param), self.zonk(&ret)),
+            ty => ty,
+        }
+    }
+
+    fn occurs(&self, var: TypeVar, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(other) => var == other,
+            Type::Array(inner) | Type::IO(inner) => self.occurs(var, &inner),
+            Type::Con(_, args) => args.iter().any(|arg| self.occurs(var, arg)),
+            Type::Func(param, ret) => self.occurs(var, &param) || self.occurs(var, &ret),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn unify(
+        &mut self,
+        expected: &Type,
+        found: &Type,
+        span: Span,
+    ) -> Result<()> {
+        match self.unify_inner(expected, found) {
+            Ok(()) => Ok(()),
+            Err(Unify::Mismatch) => Err(TypeError::Mismatch {
+                expected: Box::new(self.zonk(expected)),
+                found: Box::new(self.zonk(found)),
+                span,
+            }),
+            Err(Unify::Occurs(ty)) => Err(TypeError::InfiniteType {
+                ty: Box::new(self.zonk(&ty)),
+                span,
+            }),
+        }
+    }
+
+    /// Like `unify` but blames both branches when they disagree.
+    fn unify_branches(
+        &mut self,
+        first: &Type,
+        second: &Type,
+        first_span: Span,
+        span: Span,
+    ) -> Result<()> {
+        match self.unify(first, second, span) {
+            Err(TypeError::Mismatch { .. }) => Err(TypeError::BranchMismatch {
+                first: Box::new(self.zonk(first)),
+                second: Box::new(self.zonk(second)),
+                first_span: Box::new(first_span),
+                span,
+            }),
+            result => result,
+        }
+    }
+
+    fn unify_inner(&mut self, lhs: &Type, rhs: &Type) -> std::result::Result<(), Unify> {
+        match (self.resolve(lhs), self.resolve(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) if lhs == rhs => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(Unify::Occurs(ty));
+                }
+                self.bindings[var] = Some(ty);
+                Ok(())
+            }
+            (Type::Array(lhs), Type::Array(rhs)) | (Type::IO(lhs), Type::IO(rhs)) => {
+                self.unify_inner(&lhs, &rhs)
+            }
+            (Type::Func(lhs_param, lhs_ret), Type::Func(rhs_param, rhs_ret)) => {
+                self.unify_inner(&lhs_param, &rhs_param)?;
+                self.unify_inner(&lhs_ret, &rhs_ret)
+            }
+            (Type::Con(lhs, lhs_args), Type::Con(rhs, rhs_args))
+                if lhs == rhs && lhs_args.len() == rhs_args.len() =>
+            {
+                for (lhs, rhs) in lhs_args.iter().zip(rhs_args.iter()) {
+                    self.unify_inner(lhs, rhs)?;
+                }
+                Ok(())
+            }
+            (lhs, rhs) if lhs == rhs => Ok(()),
+            _ => Err(Unify::Mismatch),
+        }
+    }
+
+    /// A fresh copy of `scheme`, its constraints are asked of the types its
+    /// variables are given at `span`.
+    pub(crate) fn instantiate(&mut self, scheme: &Scheme, span: Span) -> Type {
+        let fresh = scheme
+            .vars
+            .iter()
+            .map(|var| (*var, self.fresh()))
+            .collect::<HashMap<_, _>>();
+        for (class, var) in scheme.constraints.iter() {
+            self.constraints.push(Constraint {
+                class: class.clone(),
+                ty: fresh[var].clone(),
+                op: None,
+                span,
+            });
+        }
+        for (var, item) in scheme.indexed.iter() {
+            self.indexing.push(Indexing {
+                container: fresh[var].clone(),
+                item: item.as_ref().map(|item| substitute(item, &fresh)),
+                span,
+            });
+        }
+        substitute(&scheme.ty, &fresh)
+    }
+
+    /// Only called between top level functions, when no local can still
+    /// refer to a variable, so every variable left is free to generalize.
+    pub(crate) fn generalize(&self, ty: &Type) -> Scheme {
+        let ty = self.zonk(ty);
+        let vars = ty.vars();
+        let constraints = self
+            .deferred
+            .iter()
+            .filter(|(_, var)| vars.contains(var))
+            .cloned()
+            .collect();
+        let indexed = self
+            .deferred_indexing
+            .iter()
+            .filter(|(var, _)| vars.contains(var))
+            .map(|(var, item)| (*var, item.as_ref().map(|item| self.zonk(item))))
+            .collect();
+        Scheme {
+            vars,
+            constraints,
+            indexed,
+            ty,
+        }
+    }
+
+    /// Checks every constraint the function inferred last asked for.
+    pub(crate) fn check_constraints(&mut self) -> Result<()> {
+        // what an item turns out to be can decide a class constraint, and
+        // one container can be the item of another
+        loop {
+            let waiting = std::mem::take(&mut self.indexing);
+            let count = waiting.len();
+            for indexing in waiting {
+                if let Some(indexing) = self.solve_indexing(indexing)? {
+                    self.indexing.push(indexing);
+                }
+            }
+            if self.indexing.len() == count {
+                break;
+            }
+        }
+        for indexing in std::mem::take(&mut self.indexing) {
+            let Type::Var(var) = self.resolve(&indexing.container) else {
+                unreachable!("only unknown containers are left");
+            };
+            self.deferred_indexing.push((var, indexing.item));
+        }
+        for constraint in std::mem::take(&mut self.constraints) {
+            self.solve(constraint)?;
+        }
+        Ok(())
+    }
+
+    /// The builtin types are instances of the builtin classes they have
+    /// operators for, `data` types only of the classes they were given an
+    /// instance of. Every type can be shown.
+    fn solve(&mut self, constraint: Constraint) -> Result<()> {
+        let ty = self.zonk(&constraint.ty);
+        let needs = match (constraint.class.as_str(), &ty) {
+            (class, Type::Var(var)) => {
+                let deferred = (class.to_string(), *var);
+                if !self.deferred.contains(&deferred) {
+                    self.deferred.push(deferred);
+                }
+                return Ok(());
+            }
+            ("Show", _) => None,
+            ("Eq", Type::Int | Type::Float | Type::Bool | Type::Char | Type::String) => {
+                None
+            }
+            ("Eq", Type::Array(inner)) => Some(*inner.clone()),
+            ("Ord", Type::Int | Type::Float | Type::Char | Type::String) => None,
+            ("Add", Type::Int | Type::Float | Type::String | Type::Array(..)) => None,
+            ("Num", Type::Int | Type::Float) => None,
+            (class, Type::Con(name, _))
+                if self.instances.contains(&(class.to_string(), name.clone())) =>
+            {
+                None
+            }
+            _ => {
+                let ty = Box::new(ty);
+                let span = constraint.span;
+                return Err(match constraint.op {
+                    Some(op) => TypeError::InvalidOperand { op, ty, span },
+                    None => TypeError::MissingInstance {
+                        class: constraint.class,
+                        ty,
+                        span,
+                    },
+                });
+            }
+        };
+        match needs {
+            Some(ty) => self.solve(Constraint { ty, ..constraint }),
+            None => Ok(()),
+        }
+    }
+
+    /// Forgets everything that belonged to the function that was checked last.
+    pub(crate) fn reset(&mut self) {
+        self.locals.clear();
+        self.constraints.clear();
+        self.deferred.clear();
+        self.indexing.clear();
+        self.deferred_indexing.clear();
+        self.actions.clear();
+    }
+
+    /// Only functions that give back an action may run one, anywhere else
+    /// the evaluator would perform it while computing a plain value.
+    /// ```hs
+    /// greet name : String -> IO = print name -- fine
+    /// size xs : Array<a> -> Int = length [print xs] -- prints while counting
+    /// ```
+    pub(crate) fn check_effects(
+        &self,
+        name: &str,
+        ty: &Type,
+        actions: Vec<(Type, Span)>,
+    ) -> Result<()> {
+        let ty = self.zonk(ty);
+        if ty.uncurry().1.holds_action() {
+            return Ok(());
+        }
+        for (action, span) in actions {
+            if self.zonk(&action).holds_action() {
+                return Err(TypeError::EffectInPureFunction {
+                    name: name.into(),
+                    ty: Box::new(ty),
+                    span,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The expressions inferred since the last call.
+    pub(crate) fn take_actions(&mut self) -> Vec<(Type, Span)> {
+        std::mem::take(&mut self.actions)
+    }
+
+    /// Turns a written type into a `Type`. `vars` holds the type parameters
+    /// already in scope, a signature can introduce new ones but the fields of
+    /// a `data` declaration can only use the parameters it declares.
+    pub(crate) fn type_from_info(
+        &mut self,
+        info: &TypeInfo,
+        vars: &mut HashMap<String, Type>,
+        new_vars: bool,
+        span: Span,
+    ) -> Result<Type> {
+        let info = info.expand_aliases(&self.aliases);
+        self.convert(&info, vars, new_vars, span)
+    }
+
+    fn convert(
+        &mut self,
+        info: &TypeInfo,
+        vars: &mut HashMap<String, Type>,
+        new_vars: bool,
+        span: Span,
+    ) -> Result<Type> {
+        Ok(match info {
+            TypeInfo::Int => Type::Int,
+            TypeInfo::Float => Type::Float,
+            TypeInfo::Bool => Type::Bool,
+            TypeInfo::String => Type::String,
+            TypeInfo::Char => Type::Char,
+            TypeInfo::Array(inner) => {
+                Type::Array(Box::new(self.convert(inner, vars, new_vars, span)?))
+            }
+            TypeInfo::Var(name) => match vars.get(name) {
+                Some(ty) => ty.clone(),
+                None if new_vars => {
+                    let ty = self.fresh();
+                    vars.insert(name.clone(), ty.clone());
+                    ty
+                }
+                None => return Err(TypeError::UnknownType(name.clone(), span)),
+            },
+            TypeInfo::Custom(name) => self.type_con(name, vec![], span)?,
+            TypeInfo::Apply(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.convert(arg, vars, new_vars, span))
+                    .collect::<Result<Vec<_>>>()?;
+                self.type_con(name, args, span)?
+            }
+            TypeInfo::Func(param, ret) => Type::func(
+                self.convert(param, vars, new_vars, span)?,
+                self.convert(ret, vars, new_vars, span)?,
+            ),
+        })
+    }
+
+    fn type_con(&mut self, name: &str, args: Vec<Type>, span: Span) -> Result<Type> {
+        // a bare `IO` doesn't say what the action produces
+        if name == "IO" {
+            return match <[Type; 1]>::try_from(args) {
+                Ok([inner]) => Ok(Type::IO(Box::new(inner))),
+                Err(args) if args.is_empty() => Ok(Type::IO(Box::new(self.fresh()))),
+                Err(args) => Err(TypeError::TypeArity(name.into(), 1, args.len(), span)),
+            };
+        }
+        let Some(arity) = self.data.get(name) else {
+            return Err(TypeError::UnknownType(name.into(), span));
+        };
+        if *arity != args.len() {
+            return Err(TypeError::TypeArity(name.into(), *arity, args.len(), span));
+        }
+        Ok(Type::Con(name.into(), args))
+    }
+
+    /// Gives every constructor of `data` a function type from its fields to
+    /// the declared type.
+    /// ```hs
+    /// data Tree a = Leaf | Node (Tree a) a (Tree a)
+    /// -- Leaf : Tree a
+    /// -- Node : Tree a -> a -> Tree a -> Tree a
+    /// ```
+    pub(crate) fn declare_data(&mut self, data: &Data) -> Result<()> {
+        let mut vars = HashMap::new();
+        let mut params = vec![];
+        for param in data.params.iter() {
+            let ty = self.fresh();
+            vars.insert(param.clone(), ty.clone());
+            params.push(ty);
+        }
+        let ret = Type::Con(data.name.clone(), params);
+        for variant in data.variants.iter() {
+            let mut fields = vec![];
+            for field in variant.fields.iter() {
+                fields.push(self.type_from_info(
+                    field,
+                    &mut vars,
+                    false,
+                    variant.span,
+                )?);
+            }
+            let ty = fields
+                .into_iter()
+                .rev()
+                .fold(ret.clone(), |ret, field| Type::func(field, ret));
+            self.globals
+                .insert(variant.name.clone(), Scheme::generalize(ty));
+        }
+        Ok(())
+    }
+
+    /// Gives every method of `class` the signature it declares, with the
+    /// class variable constrained to be an instance of it.
+    /// ```hs
+    /// class Eq a { eq : a -> a -> Bool }
+    /// -- eq : a -> a -> Bool where Eq a
+    /// ```
+    pub(crate) fn declare_class(&mut self, class: &Class) -> Result<()> {
+        let var = self.bindings.len();
+        let param = self.fresh();
+        let mut names = vec![];
+        for method in class.methods.iter() {
+            let mut vars = HashMap::from([(class.param.clone(), param.clone())]);
+            let ty = crate::signature(self, &method.type_info, &mut vars, method.span)?;
+            let scheme = Scheme {
+                vars: ty.vars(),
+                constraints: vec![(class.name.clone(), var)],
+                indexed: vec![],
+                ty,
+            };
+            self.globals.insert(method.name.clone(), scheme.clone());
+            self.methods
+                .insert(method.name.clone(), (class.name.clone(), scheme));
+            names.push(method.name.clone());
+        }
+        self.classes.insert(class.name.clone(), names);
+        Ok(())
+    }
+
+    /// Makes the type of `instance` an instance of its class, the methods
+    /// themselves are checked once every function has a type.
+    pub(crate) fn declare_instance(&mut self, instance: &Instance) -> Result<()> {
+        if !self.classes.contains_key(&instance.class) {
+            return Err(TypeError::Undefined(instance.class.clone(), instance.span));
+        }
+        let ty = self.instance_type(instance)?;
+        let Type::Con(name, _) = ty else {
+            return Err(TypeError::InvalidInstance(
+                instance.type_info.to_string(),
+                instance.span,
+            ));
+        };
+        self.instances.insert((instance.class.clone(), name));
+        Ok(())
+    }
+
+    /// `Tree a` in `instance Show (Tree a)`, with fresh variables for its
+    /// parameters.
+    pub(crate) fn instance_type(&mut self, instance: &Instance) -> Result<Type> {
+        let span = instance.span;
+        self.type_from_info(&instance.type_info, &mut HashMap::new(), true, span)
+    }
+
+    /// The type the method `name` has in an instance for `head`.
+    /// ```hs
+    /// class Eq a { eq : a -> a -> Bool }
+    /// instance Eq Color { .. } -- eq : Color -> Color -> Bool
+    /// ```
+    pub(crate) fn method_type(&mut self, name: &str, head: Type) -> Option<Type> {
+        let (_, scheme) = self.methods.get(name)?.clone();
+        let (_, param) = scheme.constraints.first()?;
+        let mut fresh = scheme
+            .vars
+            .iter()
+            .map(|var| (*var, self.fresh()))
+            .collect::<HashMap<_, _>>();
+        fresh.insert(*param, head);
+        Some(substitute(&scheme.ty, &fresh))
+    }
+
+    /// Infers the body of a function whose type is known up front. Its
+    /// parameters get their types from `expected` before the body is looked
+    /// at, so a mistake in the body is reported against them.
+    pub(crate) fn check_body(&mut self, body: &Expr, expected: &Type) -> Result<()> {
+        let len = self.locals.len();
+        let mut closures = vec![];
+        let mut body = body;
+        let mut ret = expected.clone();
+        while let (Expr::Closure(head, tail, ..), Type::Func(param, inner)) =
+            (body, self.resolve(&ret))
+        {
+            let Expr::Atom(Atom::Id(name, ..)) = head.as_ref() else {
+                unreachable!("closure parameters are always names");
+            };
+            self.typed.push((head.span(), (*param).clone()));
+            self.locals.push((name.clone(), *param));
+            closures.push((body, ret));
+            body = tail;
+            ret = *inner;
+        }
+        let ty = self.infer(body);
+        self.locals.truncate(len);
+        self.unify(&ret, &ty?, body.span())?;
+        for (closure, ty) in closures {
+            self.actions.push((ty.clone(), closure.span()));
+            self.typed.push((closure.span(), ty));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn infer(&mut self, expr: &Expr) -> Result<Type> {
+        let ty = self.infer_expr(expr)?;
+        self.actions.push((ty.clone(), expr.span()));
+        self.typed.push((expr.span(), ty.clone()));
+        Ok(ty)
+    }
+
+    fn infer_expr(&mut self, expr: &Expr) -> Result<Type> {
+        match expr {
+            Expr::Atom(atom) => self.infer_atom(atom),
+            Expr::Unary(unary) => self.infer_unary(unary),
+            Expr::Binary(binary) => self.infer_binary(binary),
+            Expr::App(app) => self.infer_app(app),
+            Expr::Array(items, ..) => {
+                let elem = self.fresh();
+                for item in items.iter() {
+                    let ty = self.infer(item)?;
+                    self.unify(&elem, &ty, item.span())?;
+                }
+                Ok(Type::Array(Box::new(elem)))
+            }
+            Expr::Closure(head, tail, ..) => {
+                let Expr::Atom(Atom::Id(name, ..)) = head.as_ref() else {
+                    unreachable!("closure parameters are always names");
+                };
+                let param = self.fresh();
+                self.typed.push((head.span(), param.clone()));
+                self.locals.push((name.clone(), param.clone()));
+                let ret = self.infer(tail);
+                self.locals.pop();
+                Ok(Type::func(param, ret?))
+            }
+            Expr::IfElse(condition, then, r#else, ..) => {
+                let ty = self.infer(condition)?;
+                self.unify(&Type::Bool, &ty, condition.span())?;
+                let then_ty = self.infer(then)?;
+                let else_ty = self.infer(r#else)?;
+                self.unify_branches(&then_ty, &else_ty, then.span(), r#else.span())?;
+                Ok(then_ty)
+            }
+            Expr::Match(m) => self.infer_match(m),
+            Expr::Do(d) => self.infer_do(d),
+            Expr::Index(index) => self.infer_index(index),
+            Expr::Concat(concat) => {
+                for part in concat.parts.iter() {
+                    self.infer(part)?;
+                }
+                Ok(Type::String)
+            }
+            Expr::Error(..) => Ok(self.fresh()),
+            Expr::Func(..)
+            | Expr::Alias(..)
+            | Expr::Data(..)
+            | Expr::Class(..)
+            | Expr::Instance(..) => {
+                unreachable!("declarations only appear at the top level")
+            }
+        }
+    }
+
+    fn infer_atom(&mut self, atom: &Atom) -> Result<Type> {
+        Ok(match atom {
+            Atom::Int(..) => Type::Int,
+            Atom::Float(..) => Type::Float,
+            Atom::Bool(..) => Type::Bool,
+            Atom::String(..) => Type::String,
+            Atom::Char(..) => Type::Char,
+            Atom::Id(name, _, span) => {
+                if let Some((_, ty)) = self.locals.iter().rev().find(|(n, _)| n == name) {
+                    return Ok(ty.clone());
+                }
+                let Some(scheme) = self.globals.get(name).cloned() else {
+                    return Err(TypeError::Undefined(name.clone(), *span));
+                };
+                self.instantiate(&scheme, *span)
+            }
+        })
+    }
+
+    fn infer_unary(&mut self, unary: &Unary) -> Result<Type> {
+        let ty = self.infer(&unary.expr)?;
+        match unary.op {
+            Op::Not => {
+                self.unify(&Type::Bool, &ty, unary.expr.span())?;
+                Ok(Type::Bool)
+            }
+            op => {
+                self.constraints.push(Constraint {
+                    class: "Num".into(),
+                    ty: ty.clone(),
+                    op: Some(op),
+                    span: unary.span,
+                });
+                Ok(ty)
+            }
+        }
+    }
+
+    fn infer_binary(&mut self, binary: &Binary) -> Result<Type> {
+        let Binary {
+            op, left, right, ..
+        } = binary;
+        let lhs = self.infer(left)?;
+        let rhs = self.infer(right)?;
+        match op {
+            Op::And | Op::Or => {
+                self.unify(&Type::Bool, &lhs, left.span())?;
+                self.unify(&Type::Bool, &rhs, right.span())?;
+                return Ok(Type::Bool);
+            }
+            Op::LRPipe => return self.apply(rhs, lhs, right.span(), left.span()),
+            Op::RLPipe => return self.apply(lhs, rhs, left.span(), right.span()),
+            Op::Not | Op::Equals => unreachable!("'{op}' is not a binary operator"),
+            _ => {}
+        }
+        self.unify(&lhs, &rhs, right.span())?;
+        let class = operator_class(*op);
+        self.constraints.push(Constraint {
+            class: class.into(),
+            ty: lhs.clone(),
+            op: Some(*op),
+            span: binary.span,
+        });
+        Ok(match class {
+            "Eq" | "Ord" => Type::Bool,
+            _ => lhs,
+        })
+    }
+
+    fn infer_app(&mut self, app: &App) -> Result<Type> {
+        // variadic builtins give back what their signature says about the
+        // first argument
+        let variadic = match app.name.as_ref() {
+            Expr::Atom(Atom::Id(name, ..)) => lookup(name).filter(|b| b.variadic),
+            _ => None,
+        };
+        if let Some(builtin) = variadic {
+            let scheme = self.globals[builtin.name].clone();
+            let func = self.instantiate(&scheme, app.name.span());
+            let Some((first, rest)) = app.args.split_first() else {
+                return Ok(func);
+            };
+            let ty = self.infer(first)?;
+            let ret = self.apply(func, ty, app.name.span(), first.span())?;
+            for arg in rest.iter() {
+                self.infer(arg)?;
+            }
+            return Ok(ret);
+        }
+        let mut func = self.infer(&app.name)?;
+        for arg in app.args.iter() {
+            let ty = self.infer(arg)?;
+            func = self.apply(func, ty, app.name.span(), arg.span())?;
+        }
+        Ok(func)
+    }
+
+    fn apply(
+        &mut self,
+        func: Type,
+        arg: Type,
+        func_span: Span,
+        arg_span: Span,
+    ) -> Result<Type> {
+        let (param, ret) = match self.resolve(&func) {
+            Type::Func(param, ret) => (*param, *ret),
+            Type::Var(..) => {
+                let (param, ret) = (self.fresh(), self.fresh());
+                let ty = Type::func(param.clone(), ret.clone());
+                self.unify(&func, &ty, func_span)?;
+                (param, ret)
+            }
+            _ => {
+                return Err(TypeError::NotAFunction {
+                    ty: Box::new(self.zonk(&func)),
+                    span: func_span,
+                })
+            }
+        };
+        self.unify(&param, &arg, arg_span)?;
+        Ok(ret)
+    }
+
+    fn infer_match(&mut self, m: &Match) -> Result<Type> {
+        let mut scrutinees = vec![];
+        for expr in m.exprs.iter() {
+            scrutinees.push(self.infer(expr)?);
+        }
+        let result = self.fresh();
+        let mut first_span = None;
+        for arm in m.arms.iter() {
+            let len = self.locals.len();
+            for (pat, ty) in arm.pats.iter().zip(scrutinees.iter()) {
+                self.infer_pat(pat, ty)?;
+            }
+            if let Some(guard) = &arm.guard {
+                let ty = self.infer(guard)?;
+                self.unify(&Type::Bool, &ty, guard.span())?;
+            }
+            let body = self.infer(&arm.body)?;
+            let span = arm.body.span();
+            let first = *first_span.get_or_insert(span);
+            self.unify_branches(&result, &body, first, span)?;
+            self.locals.truncate(len);
+        }
+        Ok(result)
+    }
+
+    /// Binds every name in `pat` as a local, `ty` is the type of the value
+    /// being matched.
+    fn infer_pat(&mut self, pat: &Pat, ty: &Type) -> Result<()> {
+        match pat {
+            Pat::Wildcard(..) => Ok(()),
+            Pat::Id(name, span) => {
+                self.typed.push((*span, ty.clone()));
+                self.locals.push((name.clone(), ty.clone()));
+                Ok(())
+            }
+            Pat::Lit(atom) => {
+                let lit = self.infer_atom(atom)?;
+                self.unify(ty, &lit, atom.span())
+            }
+            Pat::Constructor(name, args, span) => {
+                let Some(scheme) = self.globals.get(name).cloned() else {
+                    return Err(TypeError::Undefined(name.clone(), *span));
+                };
+                let ctor = self.instantiate(&scheme, *span);
+                let (params, ret) = ctor.uncurry();
+                if params.len() != args.len() {
+                    return Err(TypeError::ConstructorArity(
+                        name.clone(),
+                        params.len(),
+                        args.len(),
+                        *span,
+                    ));
+                }
+                let params = params.into_iter().cloned().collect::<Vec<_>>();
+                self.unify(ty, &ret.clone(), *span)?;
+                for (arg, param) in args.iter().zip(params.iter()) {
+                    self.infer_pat(arg, param)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Each line that is an action binds what it produces, any other line
+    /// binds its own value. The block is an action producing its last line.
+    fn infer_do(&mut self, d: &Do) -> Result<Type> {
+        let len = self.locals.len();
+        let mut last = self.fresh();
+        for stmt in d.stmts.iter() {
+            let ty = self.infer(stmt.expr())?;
+            let value = match self.resolve(&ty) {
+                Type::IO(inner) => *inner,
+                _ => ty,
+            };
+            match stmt {
+                Stmt::Bind(name, ..) => self.locals.push((name.clone(), value)),
+                Stmt::Expr(..) => last = value,
+            }
+        }
+        self.locals.truncate(len);
+        Ok(Type::IO(Box::new(last)))
+    }
+
+    fn infer_index(&mut self, index: &Index) -> Result<Type> {
+        let ty = self.infer(&index.expr)?;
+        for position in std::iter::once(&index.index).chain(index.end.iter()) {
+            let position_ty = self.infer(position)?;
+            self.unify(&Type::Int, &position_ty, position.span())?;
+        }
+        let item = match index.end {
+            Some(_) => None,
+            None => Some(self.fresh()),
+        };
+        let indexing = Indexing {
+            container: ty.clone(),
+            item: item.clone(),
+            span: index.expr.span(),
+        };
+        if let Some(indexing) = self.solve_indexing(indexing)? {
+            self.indexing.push(indexing);
+        }
+        Ok(item.unwrap_or(ty))
+    }
+
+    /// Gives `indexing` back when its container isn't known yet.
+    fn solve_indexing(&mut self, indexing: Indexing) -> Result<Option<Indexing>> {
+        let Indexing {
+            container,
+            item,
+            span,
+        } = &indexing;
+        match (self.resolve(container), item) {
+            (Type::Var(_), _) => return Ok(Some(indexing)),
+            (Type::String | Type::Array(..), None) => {}
+            (Type::String, Some(item)) => self.unify(&Type::Char, item, *span)?,
+            (Type::Array(inner), Some(item)) => self.unify(&inner, item, *span)?,
+            // the same mismatch as for anything else that should be an array
+            (_, item) => {
+                let item = item.clone().unwrap_or_else(|| self.fresh());
+                self.unify(&Type::Array(Box::new(item)), container, *span)?;
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn substitute(ty: &Type, vars: &HashMap<TypeVar, Type>) -> Type {
+    match ty {
+        Type::Var(var) => vars.get(var).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Array(inner) => Type::Array(Box::new(substitute(inner, vars))),
+        Type::IO(inner) => Type::IO(Box::new(substitute(inner, vars))),
+        Type::Con(name, args) => Type::Con(
+            name.clone(),
+            args.iter().map(|arg| substitute(arg, vars)).collect(),
+        ),
+        Type::Func(param, ret) => {
+            Type::func(substitute(param, vars), substitute(ret, vars))
+        }
+        ty => ty.clone(),
+    }
+}
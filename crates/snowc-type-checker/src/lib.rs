@@ -1,351 +1,438 @@
-use snowc_parse::{Atom, Expr, Op, Span};
+//! Hindley–Milner type inference for Snow.
+//!
+//! Functions without a signature are inferred and generalized, so
+//! `map f arr` gets `(a -> b) -> Array<a> -> Array<b>` and can be used at any
+//! type afterwards. Functions with a signature are checked against it.
+//! Functions written as clauses must have a clause for every value.
+//!
+//! Operators and class methods ask for their operands to be an instance of
+//! a class, `==` needs `Eq a`. A function that leaves `a` open passes the
+//! constraint on to its callers, signatures don't spell them out.
+//!
+//! A program that checks comes back as [`Checked`], which knows the type of
+//! every expression for editors and the repl to ask about.
+mod error;
+mod exhaustive;
+mod infer;
+mod types;
 
-type Types = std::collections::HashMap<String, Item>;
+#[cfg(test)]
+mod tests;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum Type {
-    Int,
-    Float,
-    Bool,
-    String,
-    Char,
-    IO,
-    Custom(String),
-}
+pub use error::{TypeError, Warning};
+pub use types::{Scheme, Type, TypeVar};
 
-impl TryFrom<(&String, &Types)> for Type {
-    type Error = String;
-    fn try_from((t, types): (&String, &Types)) -> Result<Self, Self::Error> {
-        match t.as_str() {
-            "Int" => Ok(Self::Int),
-            "Float" => Ok(Self::Float),
-            "Bool" => Ok(Self::Bool),
-            "String" => Ok(Self::String),
-            "Char" => Ok(Self::Char),
-            "IO" => Ok(Self::IO),
-            _ => match types.get(t.as_str()) {
-                Some(item) => Ok(item.ret_type()),
-                None => Err(format!("unknown type '{t}'")),
-            },
-        }
-    }
-}
+use exhaustive::Signatures;
+use infer::Infer;
+use snowc_parse::{Atom, Expr, Extern, Instance, Span, TypeInfo, Visitor};
+use std::collections::HashMap;
 
-enum Item {
-    Func(TypedFunc),
-    Enum(TypedEnum),
+/// What the checker learned about a program that type checks.
+#[derive(Debug, Default)]
+pub struct Checked {
+    /// The type of every top level function, declared or inferred.
+    pub signatures: HashMap<String, Scheme>,
+    /// The type of every expression, parameter and pattern variable, by
+    /// where it is in the source.
+    pub types: Vec<(Span, Type)>,
+    pub warnings: Vec<Warning>,
 }
 
-impl Item {
-    fn ret_type(&self) -> Type {
-        match self {
-            Self::Func(typed_func) => typed_func.return_type.clone(),
-            Self::Enum(typed_enum) => typed_enum.ret_type(),
-        }
+impl Checked {
+    /// The type of the innermost expression or name covering
+    /// `byte_offset`. Its variables are named the way a signature would
+    /// name them, so it can be shown as is.
+    pub fn type_at(&self, byte_offset: usize) -> Option<&Type> {
+        self.types
+            .iter()
+            .filter(|(span, _)| (span.idx_start..span.idx_end).contains(&byte_offset))
+            .min_by_key(|(span, _)| span.idx_end - span.idx_start)
+            .map(|(_, ty)| ty)
     }
-    fn lookup(&self, name: impl Into<String>) -> Option<Type> {
-        match self {
-            Self::Func(typed_func) => typed_func.lookup(name),
-            Self::Enum(typed_enum) => typed_enum.lookup(name),
-        }
+
+    /// The type of the top level function `name`, written the way a
+    /// signature is, `map : (a -> b) -> Array<a> -> Array<b>`.
+    pub fn signature_of(&self, name: &str) -> Option<&Scheme> {
+        self.signatures.get(name)
     }
 }
 
-#[derive(Debug, Clone, Hash)]
-struct Variant {
-    name: String,
-    memebers: Vec<Type>,
+/// Checks `ast` and gives the type of what is at `byte_offset`, nothing when
+/// the program doesn't type check or there is no expression there.
+pub fn type_at(ast: &[Expr], byte_offset: usize) -> Option<Type> {
+    type_check(ast).ok()?.type_at(byte_offset).cloned()
 }
 
-#[derive(Debug, Clone, Hash)]
-struct TypedEnum {
-    return_type: Type,
-    variants: Vec<Variant>,
+type Func<'a> = (&'a str, &'a Expr);
+
+pub fn type_check(ast: &[Expr]) -> Result<Checked, Vec<TypeError>> {
+    type_check_with(ast, &[])
 }
 
-impl TypedEnum {
-    fn ret_type(&self) -> Type {
-        self.return_type.clone()
+/// [`type_check`] for a program that can also call `externs`.
+pub fn type_check_with(
+    ast: &[Expr],
+    externs: &[Extern],
+) -> Result<Checked, Vec<TypeError>> {
+    let aliases = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Alias(alias) => Some((alias.name.clone(), alias.clone())),
+            _ => None,
+        })
+        .collect();
+    let data = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Data(data) => Some((data.name.clone(), data.params.len())),
+            _ => None,
+        })
+        .collect();
+    let mut infer = Infer::new(data, aliases);
+    let mut errors = vec![];
+    for ext in externs.iter() {
+        if let Err(error) = infer.declare_extern(ext) {
+            errors.push(error);
+        }
     }
 
-    fn lookup(&self, name: impl Into<String>) -> Option<Type> {
-        let name = name.into();
-        if self.variants.iter().any(|v| v.name == name) {
-            return Some(Type::Custom(name));
+    for expr in ast.iter() {
+        if let Expr::Data(data) = expr {
+            if let Err(error) = infer.declare_data(data) {
+                errors.push(error);
+            }
+        }
+    }
+    for expr in ast.iter() {
+        if let Expr::Class(class) = expr {
+            if let Err(error) = infer.declare_class(class) {
+                errors.push(error);
+            }
+        }
+    }
+    let mut warnings = vec![];
+    for expr in ast.iter() {
+        let Expr::Instance(instance) = expr else {
+            continue;
+        };
+        if let Err(error) = infer.declare_instance(instance) {
+            errors.push(error);
+            continue;
+        }
+        for method in infer.classes[&instance.class].iter() {
+            let defined = instance
+                .methods
+                .iter()
+                .any(|m| matches!(m, Expr::Func(name, ..) if name == method));
+            if !defined {
+                warnings.push(Warning::MissingMethod(
+                    instance.class.clone(),
+                    method.clone(),
+                    instance.span,
+                ));
+            }
         }
-        None
     }
-}
-
-#[derive(Debug, Clone, Hash)]
-struct TypedFunc {
-    return_type: Type,
-    args: Vec<(Option<String>, Type)>,
-}
 
-impl TypedFunc {
-    // fn new(return_type: Type) -> Self {
-    //     Self::new_with_args(return_type, vec![])
-    // }
-    //
-    fn new_with_args(return_type: Type, args: Vec<(Option<String>, Type)>) -> Self {
-        Self { return_type, args }
+    // signatures are known up front so they can be used before their
+    // function is checked
+    let mut declared = vec![];
+    let mut inferred = vec![];
+    for expr in ast.iter() {
+        let Expr::Func(name, type_info, body, span) = expr else {
+            continue;
+        };
+        if type_info.is_empty() {
+            inferred.push((name.as_str(), body.as_ref()));
+            continue;
+        }
+        match signature(&mut infer, type_info, &mut HashMap::new(), *span) {
+            Ok(ty) => {
+                infer.globals.insert(name.clone(), Scheme::generalize(ty));
+                declared.push((name.as_str(), type_info, body.as_ref(), *span));
+            }
+            Err(error) => {
+                errors.push(error);
+                give_up_on(&mut infer, name);
+            }
+        }
     }
 
-    fn push_arg(&mut self, name: impl Into<String>) {
-        for (param, _type) in self.args.iter_mut() {
-            if param.is_none() {
-                *param = Some(name.into());
-                break;
+    for group in components(&inferred) {
+        if let Err(error) = infer_group(&mut infer, &group) {
+            errors.push(error);
+            for (name, _) in group.iter() {
+                give_up_on(&mut infer, name);
             }
         }
     }
 
-    fn lookup(&self, name: impl Into<String>) -> Option<Type> {
-        let name = name.into();
-        self.args
-            .iter()
-            .find(|(arg, _)| arg.as_ref().map(|i| i == &name).unwrap_or(false))
-            .map(|(_, t)| t.clone())
+    for (name, type_info, body, span) in declared {
+        if let Err(error) = check_declared(&mut infer, name, type_info, body, span) {
+            errors.push(error);
+        }
     }
-}
 
-fn lookup(func_name: &str, env: &Types, id: &str) -> Type {
-    match env.get(id) {
-        Some(type_func) => type_func.ret_type(),
-        None => match env.get(func_name).and_then(|i| i.lookup(id)) {
-            Some(t) => t,
-            None => panic!("unbound error '{id}' has never been created"),
-        },
+    for expr in ast.iter() {
+        let Expr::Instance(instance) = expr else {
+            continue;
+        };
+        for method in instance.methods.iter() {
+            let Expr::Func(name, _, body, span) = method else {
+                continue;
+            };
+            if let Err(error) = check_method(&mut infer, instance, name, body, *span) {
+                errors.push(error);
+            }
+        }
     }
-}
 
-fn type_check_binary<'a>(
-    func_name: &str,
-    env: &Types,
-    op: &'a Op,
-    lhs: &'a Expr,
-    rhs: &'a Expr,
-) -> Type {
-    let t1 = type_of(func_name, env, lhs);
-    let t2 = type_of(func_name, env, rhs);
-    if t1 != t2 {
-        panic!("type miss matched '{op:?}' lhs: '{t1:?}', rhs: '{t2:?}'");
+    if !errors.is_empty() {
+        return Err(errors);
     }
-    match op {
-        Op::Plus | Op::Minus | Op::Mult | Op::Div | Op::Mod => t1,
-        Op::Grt
-        | Op::Les
-        | Op::GrtEq
-        | Op::LesEq
-        | Op::Eq
-        | Op::Neq
-        | Op::Not
-        | Op::And
-        | Op::Or => Type::Bool,
-        Op::Equals | Op::LRPipe | Op::RLPipe => {
-            panic!("not yet implemented for assignment or pipe")
-        }
+    // patterns are only compared once they are known to be well typed
+    let data = ast.iter().filter_map(|expr| match expr {
+        Expr::Data(data) => Some(data),
+        _ => None,
+    });
+    let errors = Signatures::new(data).check(ast, &mut warnings);
+    if !errors.is_empty() {
+        return Err(errors);
     }
+    let signatures = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Func(name, ..) => Some((name.clone(), infer.globals[name].clone())),
+            _ => None,
+        })
+        .collect();
+    // a function's own span covers its name and parameters, the type of
+    // its body is always found first
+    let mut types = ast
+        .iter()
+        .filter_map(|expr| match expr {
+            Expr::Func(name, .., span) => Some((*span, infer.globals[name].ty.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    types.extend(infer.typed.iter().map(|(span, ty)| (*span, infer.zonk(ty))));
+    Ok(Checked {
+        signatures,
+        types,
+        warnings,
+    })
 }
 
-fn type_check_if_else<'a>(
-    func_name: &str,
-    env: &Types,
-    c: &'a Expr,
-    b1: &'a Expr,
-    b2: &'a Expr,
-) -> Type {
-    let tc = type_of(func_name, env, c);
-    let Type::Bool = tc else {
-        // c.span(),
-        panic!("if condition found '{tc:?}' but expected 'Bool' Type");
-    };
-    let t1 = type_of(func_name, env, b1);
-    let t2 = type_of(func_name, env, b2);
-    if t1 != t2 {
-        // b1.span().start..b2.span().end,
-        panic!("branch types do not match, expected '{t1:?}' but found '{t2:?}'");
+/// A function whose type couldn't be worked out can be used as anything so
+/// one mistake doesn't turn into an error at every call.
+fn give_up_on(infer: &mut Infer, name: &str) {
+    let ty = infer.fresh();
+    infer.globals.insert(name.into(), Scheme::generalize(ty));
+}
+
+/// `Int -> Array<a> -> a` is written down as its parts, folds them back into
+/// one type.
+fn signature(
+    infer: &mut Infer,
+    type_info: &[TypeInfo],
+    vars: &mut HashMap<String, Type>,
+    span: Span,
+) -> Result<Type, TypeError> {
+    let mut parts = vec![];
+    for info in type_info.iter() {
+        parts.push(infer.type_from_info(info, vars, true, span)?);
     }
-    t1
+    let ret = parts.pop().expect("a signature has at least one type");
+    Ok(parts
+        .into_iter()
+        .rev()
+        .fold(ret, |ret, param| Type::func(param, ret)))
 }
 
-fn type_check_app(
-    func_name: &str,
-    env: &Types,
-    name: &Expr,
-    args: &[Expr],
-    _span: &Span,
-) -> Type {
-    let t = type_of(func_name, env, name);
-    let Expr::Atom(Atom::Id(name, _)) = name else {
-        return t;
-    };
-    let Some(Item::Func(tfunc)) = env.get(name) else {
-        // span.clone(),
-        panic!("undefined '{name}'");
-    };
-    if args.len() > tfunc.args.len() {
-        // span.clone(),
-        panic!("to many args given to '{name}'");
+/// Functions that call each other are inferred together and only
+/// generalized once all of them are done.
+fn infer_group(infer: &mut Infer, group: &[Func]) -> Result<(), TypeError> {
+    infer.reset();
+    let mut vars = vec![];
+    for (name, _) in group.iter() {
+        let var = infer.fresh();
+        infer
+            .globals
+            .insert(name.to_string(), Scheme::mono(var.clone()));
+        vars.push(var);
     }
-    for (idx, arg) in args.iter().enumerate() {
-        let t = type_of(func_name, env, arg);
-        let (arg_name, pt) = &tfunc.args[idx];
-        if pt != &t {
-            // arg.span(),
-            panic!(
-                "expected '{pt:?}' for {} but found '{t:?}'",
-                arg_name.clone().unwrap_or_else(|| "<name>".to_string())
-            );
-        }
+    let mut actions = vec![];
+    for ((_, body), var) in group.iter().zip(vars.iter()) {
+        let ty = infer.infer(body)?;
+        infer.unify(var, &ty, body.span())?;
+        actions.push(infer.take_actions());
+    }
+    infer.check_constraints()?;
+    for (((name, _), var), actions) in group.iter().zip(vars.iter()).zip(actions) {
+        infer.check_effects(name, var, actions)?;
     }
-    t
+    for ((name, _), var) in group.iter().zip(vars.iter()) {
+        let scheme = infer.generalize(var);
+        infer.globals.insert(name.to_string(), scheme);
+    }
+    Ok(())
 }
 
-fn type_of(func_name: &str, env: &Types, e: &Expr) -> Type {
-    match e {
-        Expr::Atom(Atom::Int(..), ..) => Type::Int,
-        Expr::Atom(Atom::Float(..), ..) => Type::Float,
-        Expr::Atom(Atom::Bool(..), ..) => Type::Bool,
-        Expr::Atom(Atom::String(..), ..) => Type::String,
-        Expr::Atom(Atom::Char(..), ..) => Type::Char,
-        Expr::Atom(Atom::Id(id, ..)) => lookup(func_name, env, id),
-        Expr::Unary(unary) => type_of(func_name, env, &unary.expr),
-        Expr::Binary(op, lhs, rhs, ..) => type_check_binary(func_name, env, op, lhs, rhs),
-        Expr::IfElse(c, b1, b2, ..) => type_check_if_else(func_name, env, c, b1, b2),
-        // enum_var @ Expr::EnumVar(..) => {
-        //     let mut names = vec![];
-        //     get_names(enum_var, &mut names);
-        //     let name = &names[0];
-        //     let variant = &names[1];
-        //     let Some(typed_enum) = env.get(name) else {
-        //         panic!("use for before defining '{}'", name)
-        //     };
-        //     let Some(t) = typed_enum.lookup(variant) else {
-        //         panic!("'{}' does not have a variant named '{}'", name, variant);
-        //     };
-        //     t
-        // }
-        Expr::App(name, args, span) => type_check_app(func_name, env, name, args, span),
-        // e.span(),
-        _ => panic!("not implemented yet for expr: '{e:?}'"),
+/// The body has to be at least as general as the signature, `a -> a` can't
+/// be given a body that only works on `Int`.
+fn check_declared(
+    infer: &mut Infer,
+    name: &str,
+    type_info: &[TypeInfo],
+    body: &Expr,
+    span: Span,
+) -> Result<(), TypeError> {
+    infer.reset();
+    let mut vars = HashMap::new();
+    let expected = signature(infer, type_info, &mut vars, span)?;
+    infer.check_body(body, &expected)?;
+    infer.check_constraints()?;
+    let actions = infer.take_actions();
+    infer.check_effects(name, &expected, actions)?;
+    let mut seen = vec![];
+    for var in vars.values() {
+        match infer.zonk(var) {
+            Type::Var(var) if !seen.contains(&var) => seen.push(var),
+            _ => {
+                return Err(TypeError::SignatureTooGeneral {
+                    name: name.into(),
+                    declared: Box::new(infer.globals[name].ty.clone()),
+                    inferred: Box::new(infer.zonk(&expected)),
+                    span,
+                })
+            }
+        }
     }
+    // what a bare `IO` produces is only known now
+    let scheme = infer.generalize(&expected);
+    infer.globals.insert(name.into(), scheme);
+    Ok(())
 }
 
-fn pair_up_params<'a>(
-    _func_name: String,
-    type_func: &mut TypedFunc,
-    expr: &'a Expr,
-) -> &'a Expr {
-    if !expr.is_clouser() {
-        return expr;
-    }
-    let Expr::Closure(head, tail, _) = expr else {
-        // expr.span(), 
-        panic!("unimplemented yet for '{expr}'");
-    };
-    let Expr::Atom(Atom::Id(name, _)) = &**head else {
-        // head.span(), 
-        panic!("unimplemented yet for '{expr}'");
+/// A method of an instance has the type its class gives it with the
+/// instance's type in place of the class variable.
+fn check_method(
+    infer: &mut Infer,
+    instance: &Instance,
+    name: &str,
+    body: &Expr,
+    span: Span,
+) -> Result<(), TypeError> {
+    infer.reset();
+    let head = infer.instance_type(instance)?;
+    let Some(expected) = infer.method_type(name, head) else {
+        return Err(TypeError::Undefined(name.into(), span));
     };
-    type_func.push_arg(name);
-    pair_up_params(_func_name, type_func, tail)
+    let ty = infer.infer(body)?;
+    infer.unify(&expected, &ty, body.span())?;
+    infer.check_constraints()?;
+    let actions = infer.take_actions();
+    infer.check_effects(name, &expected, actions)
 }
 
-fn default_types() -> Types {
-    let mut env = Types::new();
-    let func = Item::Func(TypedFunc::new_with_args(
-        Type::IO,
-        vec![(Some("x".into()), Type::String)],
-    ));
-    env.insert("print_str".into(), func);
-    let func = Item::Func(TypedFunc::new_with_args(
-        Type::IO,
-        vec![(Some("x".into()), Type::Int)],
-    ));
-    env.insert("print_int".into(), func);
-    let func = Item::Func(TypedFunc::new_with_args(
-        Type::IO,
-        vec![(Some("x".into()), Type::Float)],
-    ));
-    env.insert("print_float".into(), func);
-    let func = Item::Func(TypedFunc::new_with_args(
-        Type::IO,
-        vec![(Some("x".into()), Type::Char)],
-    ));
-    env.insert("print_char".into(), func);
-    let func = Item::Func(TypedFunc::new_with_args(
-        Type::IO,
-        vec![(Some("x".into()), Type::Bool)],
-    ));
-    env.insert("print_bool".into(), func);
-    env
+/// Every name a function body refers to.
+#[derive(Default)]
+struct Names(Vec<String>);
+
+impl Visitor for Names {
+    fn visit_atom(&mut self, atom: &Atom) {
+        if let Atom::Id(name, ..) = atom {
+            self.0.push(name.clone());
+        }
+    }
 }
 
-pub fn type_check(ast: &[Expr]) -> Result<(), Vec<String>> {
-    let mut env = default_types();
-    let errors = Vec::new();
-    for def in ast.iter() {
-        match def {
-            Expr::Func(name, _type_info, body, _) => {
-                let Some(Item::Func(type_func)) = env.get_mut(name) else {
-                    // span.clone(),
-                    panic!(
-                        "function '{name}' missing type declaration"
-                    );
-                };
-                let body = pair_up_params(name.into(), type_func, body);
-                let dec_return_type = type_func.return_type.clone();
-                let return_type = type_of(name, &env, body);
-                if return_type != dec_return_type {
-                    // FIXME: add return error
+/// Tarjan's strongly connected components over the call graph of the
+/// functions without signatures. Groups come out after every group they
+/// call.
+fn components<'a>(funcs: &[Func<'a>]) -> Vec<Vec<Func<'a>>> {
+    struct Tarjan {
+        edges: Vec<Vec<usize>>,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        groups: Vec<Vec<usize>>,
+    }
 
-                    // body.span(),
-                    panic!(
-                        "miss matched return types: found '{return_type:?}' \
-                        but expected '{dec_return_type:?}'"
-                    );
+    impl Tarjan {
+        fn visit(&mut self, v: usize) {
+            self.index[v] = Some(self.next);
+            self.low[v] = self.next;
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+            for w in self.edges[v].clone() {
+                match self.index[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(index) if self.on_stack[w] => {
+                        self.low[v] = self.low[v].min(index);
+                    }
+                    Some(_) => {}
                 }
             }
-            // Expr::TypeDec(name, body, _) => {
-            //     let mut body = body.clone();
-            //     let Some(t) = body.pop() else {
-            //         // span.clone(),
-            //         panic!("missing return type from type declaration '{name}'");
-            //     };
-            //     let mut args = vec![];
-            //     for string_type in body.iter() {
-            //         args.push((None, Type::try_from((string_type, &env)).unwrap()));
-            //     }
-            //     let return_type = Type::try_from((&t, &env)).unwrap();
-            //     let typed_func = TypedFunc::new_with_args(return_type, args);
-            //     env.insert(name.into(), Item::Func(typed_func));
-            // }
-            Expr::Enum(name, var, _) => {
-                let mut variants = vec![];
-                for (name, memb) in var.iter() {
-                    variants.push(Variant {
-                        name: name.to_string(),
-                        memebers: memb
-                            .iter()
-                            .map(|i| {
-                                Type::try_from((i, &env)).expect("failed to get type")
-                            })
-                            .collect(),
-                    })
+            if Some(self.low[v]) != self.index[v] {
+                return;
+            }
+            let mut group = vec![];
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                group.push(w);
+                if w == v {
+                    break;
                 }
-                let typed_enum = TypedEnum {
-                    return_type: Type::Custom(name.to_string()),
-                    variants,
-                };
-                env.insert(name.into(), Item::Enum(typed_enum));
             }
-            _ => unimplemented!("for '{def}'"),
+            group.reverse();
+            self.groups.push(group);
         }
     }
-    if !errors.is_empty() {
-        return Err(errors);
+
+    let idx = funcs
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| (*name, i))
+        .collect::<HashMap<_, _>>();
+    let edges = funcs
+        .iter()
+        .map(|(_, body)| {
+            let mut names = Names::default();
+            names.visit_expr(body);
+            let mut edges = names
+                .0
+                .iter()
+                .filter_map(|name| idx.get(name.as_str()).copied())
+                .collect::<Vec<_>>();
+            edges.sort();
+            edges.dedup();
+            edges
+        })
+        .collect();
+    let mut tarjan = Tarjan {
+        edges,
+        index: vec![None; funcs.len()],
+        low: vec![0; funcs.len()],
+        on_stack: vec![false; funcs.len()],
+        stack: vec![],
+        next: 0,
+        groups: vec![],
+    };
+    for v in 0..funcs.len() {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
     }
-    Ok(())
+    tarjan
+        .groups
+        .into_iter()
+        .map(|group| group.into_iter().map(|i| funcs[i]).collect())
+        .collect()
 }
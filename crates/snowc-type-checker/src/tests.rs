@@ -0,0 +1,460 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+use snowc_parse::parse;
+
+fn check(src: &str) -> Result<Checked, Vec<TypeError>> {
+    let ast = parse(src).unwrap();
+    type_check(&ast)
+}
+
+macro_rules! infer_test {
+    ($name:ident, $src:expr, [$(($func:expr, $sig:expr)),* $(,)?] $(,)?) => {
+        #[test]
+        fn $name() {
+            let checked = check($src).unwrap();
+            $(
+                assert_eq!(checked.signatures[$func].to_string(), $sig, "{}", $func);
+            )*
+        }
+    };
+}
+
+macro_rules! error_test {
+    ($name:ident, $src:expr, $error:pat $(,)?) => {
+        #[test]
+        fn $name() {
+            let errors = check($src).unwrap_err();
+            assert!(matches!(errors[0], $error), "{errors:?}");
+        }
+    };
+}
+
+infer_test!(
+    map_is_inferred,
+    r#"
+map f arr
+  = if length arr == 0
+    then []
+    else [f (head arr)] + map f (tail arr)
+"#,
+    [("map", "(a -> b) -> Array<a> -> Array<b>")],
+);
+
+infer_test!(
+    let_polymorphism,
+    r#"
+id x = x
+main = [id 1, length (id [true])]
+"#,
+    [("id", "a -> a"), ("main", "Array<Int>")],
+);
+
+infer_test!(
+    mutual_recursion,
+    r#"
+even n = if n == 0 then true else odd (n - 1)
+odd n = if n == 0 then false else even (n - 1)
+"#,
+    [("even", "Int -> Bool"), ("odd", "Int -> Bool")],
+);
+
+infer_test!(
+    data_and_patterns,
+    r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+insert x Leaf = Node Leaf x Leaf
+insert x (Node l v r)
+  | x < v = Node (insert x l) v r
+  | true = Node l v (insert x r)
+size Leaf = 0
+size (Node l _ r) = size l + 1 + size r
+"#,
+    [
+        ("insert", "a -> Tree a -> Tree a"),
+        ("size", "Tree a -> Int")
+    ],
+);
+
+infer_test!(
+    signatures_are_checked,
+    r#"
+type Grid = Array<Bool>
+max x y
+  : Int -> Int -> Int
+  = if x > y then x else y
+first grid : Grid -> Bool = grid[0]
+pick xs : Array<a> -> a = xs[0]
+"#,
+    [
+        ("max", "Int -> Int -> Int"),
+        ("first", "Array<Bool> -> Bool"),
+        ("pick", "Array<a> -> a"),
+    ],
+);
+
+infer_test!(
+    io_actions,
+    r#"
+greet name : String -> IO = print "hello {name}"
+main = do {
+  name <- read_line;
+  greet name;
+  length [name]
+}
+"#,
+    [("greet", "String -> IO"), ("main", "IO")],
+);
+
+infer_test!(
+    indexing_works_on_strings_and_arrays,
+    r#"
+at xs i = xs[i]
+rest xs = xs[1..2]
+main = [at "snow" 0 == at (rest ['l', 'a']) 0]
+"#,
+    [
+        ("at", "a -> Int -> b"),
+        ("rest", "a -> a"),
+        ("main", "Array<Bool>")
+    ],
+);
+
+error_test!(
+    only_arrays_and_strings_are_indexed,
+    "at xs i = xs[i]\nmain = at 5 0",
+    TypeError::Mismatch { .. },
+);
+
+infer_test!(
+    ranges_and_comprehensions,
+    "evens n = [x | x <- [0..n], x % 2 == 0]",
+    [("evens", "Int -> Array<Int>")],
+);
+
+error_test!(
+    body_does_not_match_signature,
+    "inc x : Int -> String = x + 1",
+    TypeError::Mismatch { .. },
+);
+
+error_test!(
+    signature_too_general,
+    "inc x : a -> a = x + 1",
+    TypeError::SignatureTooGeneral { .. },
+);
+
+error_test!(undefined, "main = foo 1", TypeError::Undefined(..));
+
+error_test!(
+    invalid_operand,
+    "main = true + false",
+    TypeError::InvalidOperand { .. },
+);
+
+error_test!(
+    infinite_type,
+    "self x = x x",
+    TypeError::InfiniteType { .. },
+);
+
+error_test!(
+    unknown_type,
+    "size t : Tree -> Int = 0",
+    TypeError::UnknownType(..),
+);
+
+error_test!(
+    constructor_arity,
+    "data Pair a b = Pair a b\nfirst (Pair a) = a",
+    TypeError::ConstructorArity(..),
+);
+
+#[test]
+fn errors_in_one_function_do_not_hide_others() {
+    let errors = check("a = 1 + true\nb = a 1\nc = \"x\" - \"y\"").unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+}
+
+error_test!(
+    branches_disagree,
+    "main x = if x then 1 else \"one\"",
+    TypeError::BranchMismatch { .. },
+);
+
+#[test]
+fn report_points_at_both_branches() {
+    let src = "f 0 = true\nf _ = 'c'\n";
+    let errors = check(src).unwrap_err();
+    let report = errors[0].report("main.snow", src);
+    assert!(report.contains("this branch is 'Bool'"), "{report}");
+    assert!(report.contains("but this one is 'Char'"), "{report}");
+}
+
+#[test]
+fn body_mismatch_points_into_the_body() {
+    let errors = check("add x y : Int -> Int -> Int = x + \"a\"").unwrap_err();
+    let [TypeError::Mismatch {
+        expected,
+        found,
+        span,
+    }] = errors.as_slice()
+    else {
+        panic!("{errors:?}");
+    };
+    assert_eq!(
+        (expected.as_ref(), found.as_ref()),
+        (&Type::Int, &Type::String)
+    );
+    assert_eq!(span.col_start, 34, "{span:?}");
+}
+
+infer_test!(
+    builtins_are_typed,
+    r#"
+first xs = head xs
+append xs x = push (tail xs) x
+log x = print x "and" 1
+"#,
+    [
+        ("first", "Array<a> -> a"),
+        ("append", "Array<a> -> a -> Array<a>"),
+        ("log", "a -> IO"),
+    ],
+);
+
+#[test]
+fn externs_are_typed_by_their_signature() {
+    let ast = parse("quad x = double (double x)\nfirst xs = pick xs").unwrap();
+    let externs = [
+        Extern {
+            name: "double".into(),
+            arity: 1,
+            signature: Some("Int -> Int".into()),
+        },
+        Extern {
+            name: "pick".into(),
+            arity: 1,
+            signature: None,
+        },
+    ];
+    let checked = type_check_with(&ast, &externs).unwrap();
+    assert_eq!(checked.signatures["quad"].to_string(), "Int -> Int");
+    assert_eq!(checked.signatures["first"].to_string(), "a -> b");
+}
+
+#[test]
+fn prelude_has_the_builtin_signatures() {
+    use snowc_parse::builtin::{lookup, prelude};
+    let checked = type_check(&prelude()).unwrap();
+    for (name, scheme) in checked.signatures.iter() {
+        let builtin = lookup(name).unwrap();
+        assert_eq!(scheme.to_string(), builtin.signature, "{name}");
+    }
+}
+
+error_test!(
+    builtin_misuse,
+    "main = push [1] true",
+    TypeError::Mismatch { .. },
+);
+
+fn missing_clause(src: &str) -> String {
+    let errors = check(src).unwrap_err();
+    let [TypeError::MissingClause(_, example, _)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    example.clone()
+}
+
+#[test]
+fn missing_constructor() {
+    let src = r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+size (Node Leaf _ r) = 1 + size r
+size Leaf = 0
+"#;
+    assert_eq!(missing_clause(src), "size (Node (Node _ _ _) _ _)");
+}
+
+#[test]
+fn missing_bool_and_literal() {
+    assert_eq!(missing_clause("f true 0 = 1\nf false _ = 2"), "f true _");
+    assert_eq!(missing_clause("f 0 = 1\nf 1 = 2"), "f _");
+}
+
+#[test]
+fn guards_do_not_cover() {
+    assert_eq!(missing_clause("abs n | n < 0 = 0 - n"), "abs _");
+}
+
+infer_test!(
+    exhaustive_clauses,
+    r#"
+data Option a = Some a | None
+unwrap (Some x) _ = x
+unwrap None d = d
+flip true = false
+flip false = true
+"#,
+    [("unwrap", "Option a -> a -> a"), ("flip", "Bool -> Bool")],
+);
+
+#[test]
+fn unreachable_clause_warning() {
+    let checked = check("f _ = 1\nf 0 = 2\ng true = 1\ng false = 2\ng _ = 3").unwrap();
+    let names = checked
+        .warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            Warning::UnreachableClause(name, ..) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["f", "g"]);
+}
+
+error_test!(
+    print_in_a_pure_function,
+    "size xs : Array<Int> -> Int = length [print xs]",
+    TypeError::EffectInPureFunction { .. },
+);
+
+error_test!(
+    inferred_pure_functions_cannot_print,
+    "greet name = print name\ncount name = length [greet name]",
+    TypeError::EffectInPureFunction { .. },
+);
+
+infer_test!(
+    actions_can_be_passed_around,
+    r#"
+apply f x = f x
+greetings names = [print n | n <- names]
+main = apply print 1
+"#,
+    [
+        ("apply", "(a -> b) -> a -> b"),
+        ("greetings", "Array<a> -> Array<IO>"),
+        ("main", "IO"),
+    ],
+);
+
+const COLOR: &str = r#"
+data Color = Red | Green | Blue
+instance Eq Color {
+  eq Red Red = true;
+  eq Green Green = true;
+  eq Blue Blue = true;
+  eq _ _ = false
+}
+elem x xs = length [y | y <- xs, y == x] > 0
+"#;
+
+#[test]
+fn constraints_are_part_of_the_scheme() {
+    let checked = check(&format!("{COLOR}main = elem Red [Green, Blue]")).unwrap();
+    let elem = &checked.signatures["elem"];
+    assert_eq!(elem.to_string(), "a -> Array<a> -> Bool");
+    assert_eq!(
+        elem.constraints,
+        vec![("Eq".to_string(), elem.ty.vars()[0])]
+    );
+}
+
+error_test!(
+    data_needs_an_instance_for_operators,
+    "data Color = Red | Green\nmain = Red == Green",
+    TypeError::InvalidOperand { .. },
+);
+
+error_test!(
+    callers_need_the_instance_too,
+    &format!("{COLOR}data Shape = Square\nmain = elem Square [Square]"),
+    TypeError::MissingInstance { .. },
+);
+
+error_test!(
+    methods_have_the_class_type,
+    "data Color = Red\ninstance Eq Color { eq _ _ = 1 }",
+    TypeError::Mismatch { .. },
+);
+
+error_test!(
+    instances_are_for_data_types,
+    "instance Eq Int { eq _ _ = true }",
+    TypeError::InvalidInstance(..),
+);
+
+#[test]
+fn methods_need_every_clause() {
+    let src = "data Color = Red | Green\ninstance Eq Color { eq Red Red = true }";
+    assert_eq!(missing_clause(src), "eq Green _");
+}
+
+infer_test!(
+    user_classes,
+    r#"
+data Tree a = Leaf | Node (Tree a) a (Tree a)
+class Size a { size : a -> Int }
+instance Size (Tree a) {
+  size Leaf = 0;
+  size (Node l _ r) = size l + 1 + size r
+}
+total t = size t + 1
+main = total (Node Leaf 1 Leaf)
+"#,
+    [("total", "a -> Int"), ("main", "Int")],
+);
+
+#[test]
+fn instances_can_leave_methods_out() {
+    let src = r#"
+data V = V Int Int
+instance Num V { sub (V a b) (V c d) = V (a - c) (b - d) }
+main = V 1 2 - V 3 4
+"#;
+    let checked = check(src).unwrap();
+    let missing = checked
+        .warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            Warning::MissingMethod(_, method, _) => Some(method.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(missing, vec!["mul", "div", "rem", "neg"]);
+}
+
+fn type_at(src: &str, needle: &str) -> String {
+    let checked = check(src).unwrap();
+    let offset = src.find(needle).unwrap();
+    checked.type_at(offset).unwrap().to_string()
+}
+
+#[test]
+fn type_at_finds_the_innermost_expression() {
+    let src = "count xs = length xs + 1\nmain = count [\"a\"]";
+    assert_eq!(type_at(src, "length"), "Array<a> -> Int");
+    assert_eq!(type_at(src, "xs +"), "Array<a>");
+    assert_eq!(type_at(src, "+ 1"), "Int");
+    assert_eq!(type_at(src, "[\"a\"]"), "Array<String>");
+    assert_eq!(type_at(src, "count xs"), "Array<a> -> Int");
+}
+
+#[test]
+fn type_at_names_pattern_variables() {
+    let src =
+        "data Pair a b = Pair a b\nsecond (Pair _ y) = y\nmain = second (Pair 1 'c')";
+    assert_eq!(type_at(src, "y)"), "a");
+    assert_eq!(type_at(src, "Pair 1"), "Int -> Char -> Pair Int Char");
+}
+
+#[test]
+fn signatures_print_as_snow() {
+    let src = "roots f ts = [f t | t <- ts]";
+    let checked = check(src).unwrap();
+    let roots = checked.signature_of("roots").unwrap();
+    assert_eq!(roots.to_string(), "(a -> b) -> Array<a> -> Array<b>");
+    assert!(checked.signature_of("missing").is_none());
+}
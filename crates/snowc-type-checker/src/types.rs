@@ -0,0 +1,180 @@
+use std::fmt;
+
+/// Type variables are numbered in the order the checker creates them.
+pub type TypeVar = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Char,
+    Var(TypeVar),
+    Array(Box<Self>),
+    /// The result of running an action. Signatures only ever say `IO`, the
+    /// type of the value it produces is tracked here so `do` blocks can bind
+    /// it.
+    IO(Box<Self>),
+    /// A `data` type applied to its parameters, `Tree a`.
+    Con(String, Vec<Self>),
+    Func(Box<Self>, Box<Self>),
+}
+
+impl Type {
+    pub fn func(param: Self, ret: Self) -> Self {
+        Self::Func(Box::new(param), Box::new(ret))
+    }
+
+    /// Every type variable in order of first appearance.
+    pub fn vars(&self) -> Vec<TypeVar> {
+        let mut vars = vec![];
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<TypeVar>) {
+        match self {
+            Self::Var(var) if !vars.contains(var) => vars.push(*var),
+            Self::Array(inner) | Self::IO(inner) => inner.collect_vars(vars),
+            Self::Con(_, args) => args.iter().for_each(|arg| arg.collect_vars(vars)),
+            Self::Func(param, ret) => {
+                param.collect_vars(vars);
+                ret.collect_vars(vars);
+            }
+            _ => {}
+        }
+    }
+
+    /// Splits `a -> b -> c` into `[a, b]` and `c`.
+    pub fn uncurry(&self) -> (Vec<&Self>, &Self) {
+        let mut params = vec![];
+        let mut ty = self;
+        while let Self::Func(param, ret) = ty {
+            params.push(param.as_ref());
+            ty = ret;
+        }
+        (params, ty)
+    }
+
+    /// An action, or a value with actions inside it like `Array<IO>`.
+    /// Functions only run their actions once applied so they don't count.
+    pub fn holds_action(&self) -> bool {
+        match self {
+            Self::IO(..) => true,
+            Self::Array(inner) => inner.holds_action(),
+            Self::Con(_, args) => args.iter().any(Self::holds_action),
+            _ => false,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, names: &[TypeVar]) -> fmt::Result {
+        match self {
+            Self::Int => write!(f, "Int"),
+            Self::Float => write!(f, "Float"),
+            Self::Bool => write!(f, "Bool"),
+            Self::String => write!(f, "String"),
+            Self::Char => write!(f, "Char"),
+            Self::Var(var) => {
+                let idx = names.iter().position(|v| v == var).unwrap_or(*var);
+                write!(f, "{}", var_name(idx))
+            }
+            Self::Array(inner) => {
+                write!(f, "Array<")?;
+                inner.write(f, names)?;
+                write!(f, ">")
+            }
+            Self::IO(..) => write!(f, "IO"),
+            Self::Con(name, args) => {
+                write!(f, "{name}")?;
+                for arg in args.iter() {
+                    write!(f, " ")?;
+                    match arg {
+                        Self::Con(_, args) if !args.is_empty() => {
+                            write!(f, "(")?;
+                            arg.write(f, names)?;
+                            write!(f, ")")?;
+                        }
+                        Self::Func(..) => {
+                            write!(f, "(")?;
+                            arg.write(f, names)?;
+                            write!(f, ")")?;
+                        }
+                        _ => arg.write(f, names)?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Func(param, ret) => {
+                if let Self::Func(..) = param.as_ref() {
+                    write!(f, "(")?;
+                    param.write(f, names)?;
+                    write!(f, ")")?;
+                } else {
+                    param.write(f, names)?;
+                }
+                write!(f, " -> ")?;
+                ret.write(f, names)
+            }
+        }
+    }
+}
+
+/// `a`, `b`, .. `z`, `a1`, `b1`, ..
+fn var_name(idx: usize) -> String {
+    let letter = (b'a' + (idx % 26) as u8) as char;
+    match idx / 26 {
+        0 => letter.to_string(),
+        n => format!("{letter}{n}"),
+    }
+}
+
+/// Type variables are named after the order they show up in, so the same
+/// type always prints the same way no matter how it was inferred.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &self.vars())
+    }
+}
+
+/// A type closed over its variables, `forall a b. (a -> b) -> Array<a> -> Array<b>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheme {
+    pub vars: Vec<TypeVar>,
+    /// Classes some of the variables have to be instances of, `elem` needs
+    /// `Eq a` to compare its argument with the items of the array.
+    pub constraints: Vec<(String, TypeVar)>,
+    /// Variables the function indexes, each an `Array` or a `String`, with
+    /// the type of what indexing gives when it isn't a slice. `at xs i =
+    /// xs[i]` works on both.
+    pub indexed: Vec<(TypeVar, Option<Type>)>,
+    pub ty: Type,
+}
+
+impl Scheme {
+    /// A type that can't be instantiated to anything else.
+    pub fn mono(ty: Type) -> Self {
+        Self {
+            vars: vec![],
+            constraints: vec![],
+            indexed: vec![],
+            ty,
+        }
+    }
+
+    /// Closes over every variable in `ty`.
+    pub fn generalize(ty: Type) -> Self {
+        Self {
+            vars: ty.vars(),
+            constraints: vec![],
+            indexed: vec![],
+            ty,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ty)
+    }
+}
@@ -1,4 +1,11 @@
 use clap::{crate_description, crate_name, crate_version, Arg, ColorChoice, Command};
+use snowc::{Level, Levels, LINTS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+}
 
 #[derive(Debug, Default)]
 pub struct Settings {
@@ -8,6 +15,20 @@ pub struct Settings {
     pub debug_graph: bool,
     pub option_no_type_check: bool,
     pub option_compile_string: bool,
+    pub emit: Vec<Emit>,
+    /// Run with lazy evaluation, a `--# lazy` pragma asks for it too.
+    pub lazy: bool,
+    /// Run on the VM instead of the tree-walker.
+    pub vm: bool,
+    /// Set by `--trace`, `--trace-only` or `--trace-file`.
+    pub trace: bool,
+    /// The functions `--trace-only` picked, every function when empty.
+    pub trace_only: Vec<String>,
+    /// Where the trace goes instead of stderr.
+    pub trace_file: Option<String>,
+    /// Set by `snowc lint`, the level of every lint after `--allow` and
+    /// `--deny`.
+    pub lint: Option<Levels>,
 }
 
 pub fn cargs() -> Settings {
@@ -38,13 +59,21 @@ pub fn cargs() -> Settings {
                 .action(clap::ArgAction::SetTrue)
                 .help("Turns AST into a visual graph"),
         )
+        .arg(
+            Arg::new("emit")
+                .long("emit")
+                .required(false)
+                .value_parser(["tokens-json", "ast-json"])
+                .action(clap::ArgAction::Append)
+                .help("Write compiler internals to stdout as JSON instead of running"),
+        )
         .arg(
             Arg::new("dynamic")
                 .long("dynamic")
                 .short('d')
                 .required(false)
                 .action(clap::ArgAction::SetTrue)
-                .help("turn the language in to garbage"),
+                .help("Skip type checking"),
         )
         .arg(
             Arg::new("from_string")
@@ -54,12 +83,66 @@ pub fn cargs() -> Settings {
                 .action(clap::ArgAction::SetTrue)
                 .help("takes in string to compile"),
         )
+        .arg(
+            Arg::new("lazy")
+                .long("lazy")
+                .required(false)
+                .action(clap::ArgAction::SetTrue)
+                .help("Only evaluate arguments and array items once they are needed"),
+        )
+        .arg(
+            Arg::new("vm")
+                .long("vm")
+                .required(false)
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["lazy", "trace", "trace-only", "trace-file"])
+                .help("Run on the VM instead of the tree-walker"),
+        )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .required(false)
+                .action(clap::ArgAction::SetTrue)
+                .help("Show every function call and what it gave back"),
+        )
+        .arg(
+            Arg::new("trace-only")
+                .long("trace-only")
+                .required(false)
+                .action(clap::ArgAction::Append)
+                .help("Only trace calls to this function, can be given more than once"),
+        )
+        .arg(
+            Arg::new("trace-file")
+                .long("trace-file")
+                .required(false)
+                .help("Write the trace to this file instead of stderr"),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Check a program for likely mistakes without running it")
+                .arg(Arg::new("filename").required(true))
+                .arg(lint_level("allow", "Don't report this lint"))
+                .arg(lint_level("deny", "Report this lint as an error")),
+        )
         .get_matches();
 
     let mut setting = Settings::default();
     if let Some(filename) = matches.get_one::<String>("filename") {
         setting.filename = Some(filename.to_string());
     }
+    if let Some(lint) = matches.subcommand_matches("lint") {
+        setting.filename = lint.get_one::<String>("filename").cloned();
+        let mut levels = Levels::default();
+        for (flag, level) in [("allow", Level::Allow), ("deny", Level::Deny)] {
+            for id in lint.get_many::<String>(flag).unwrap_or_default() {
+                levels
+                    .set(id, level)
+                    .expect("clap only accepts known lints");
+            }
+        }
+        setting.lint = Some(levels);
+    }
     setting.debug_token = *matches
         .get_one::<bool>("debug-token")
         .expect("debug-token failed");
@@ -74,5 +157,35 @@ pub fn cargs() -> Settings {
     setting.option_compile_string = *matches
         .get_one::<bool>("from_string")
         .expect("from_string failed");
+    setting.lazy = *matches.get_one::<bool>("lazy").expect("lazy failed");
+    setting.vm = *matches.get_one::<bool>("vm").expect("vm failed");
+    setting.trace_only = matches
+        .get_many::<String>("trace-only")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    setting.trace_file = matches.get_one::<String>("trace-file").cloned();
+    setting.trace = *matches.get_one::<bool>("trace").expect("trace failed")
+        || !setting.trace_only.is_empty()
+        || setting.trace_file.is_some();
+    setting.emit = matches
+        .get_many::<String>("emit")
+        .unwrap_or_default()
+        .map(|kind| match kind.as_str() {
+            "tokens-json" => Emit::Tokens,
+            "ast-json" => Emit::Ast,
+            _ => unreachable!("clap only accepts known emit kinds"),
+        })
+        .collect();
     setting
 }
+
+/// `--allow <lint>` or `--deny <lint>`, can be given more than once.
+fn lint_level(name: &'static str, help: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .required(false)
+        .value_parser(LINTS.iter().map(|lint| lint.id).collect::<Vec<_>>())
+        .action(clap::ArgAction::Append)
+        .help(help)
+}
@@ -0,0 +1,217 @@
+//! Rust values as Snow [`Value`]s and back, for [`crate::Engine`]. Snow has
+//! no tuples, a tuple is an array with an item for each of its fields.
+use crate::engine::EngineError;
+use snowc_parse::Span;
+use snowc_tree_walker::{Thunk, Value};
+
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, EngineError>;
+}
+
+/// The arguments of a call, `()` for none and a tuple for any other number,
+/// `(x,)` for one.
+pub trait IntoArgs {
+    fn into_args(self) -> Vec<Value>;
+}
+
+/// What kind of value `value` is, for conversion errors.
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Int(..) => "Int",
+        Value::Float(..) => "Float",
+        Value::Bool(..) => "Bool",
+        Value::String(..) => "String",
+        Value::Char(..) => "Char",
+        Value::Array(..) => "Array",
+        Value::Closure { .. } => "function",
+        Value::Variant(..) => "data",
+        Value::Thunk(..) => "thunk",
+    }
+}
+
+fn mismatch(expected: &'static str, value: &Value) -> EngineError {
+    EngineError::Conversion {
+        expected,
+        found: kind(value),
+    }
+}
+
+/// The value of a thunk that has run, anything else as it is.
+fn forced(value: Value) -> Value {
+    match value {
+        Value::Thunk(thunk) => match &*thunk.borrow() {
+            Thunk::Done(value) => value.clone(),
+            Thunk::Pending(..) => Value::Thunk(thunk.clone()),
+        },
+        value => value,
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, EngineError> {
+        Ok(value)
+    }
+}
+
+// every one of these fits in an Int
+macro_rules! into_int {
+    ($($int:ty),*) => {$(
+        impl IntoValue for $int {
+            fn into_value(self) -> Value {
+                Value::Int(self.into(), Span::default())
+            }
+        }
+    )*};
+}
+
+into_int!(i8, i16, i32, u8, u16);
+
+macro_rules! from_int {
+    ($($int:ty),*) => {$(
+        impl FromValue for $int {
+            fn from_value(value: Value) -> Result<Self, EngineError> {
+                match forced(value) {
+                    Value::Int(int, ..) => <$int>::try_from(int).map_err(|_| {
+                        EngineError::Conversion {
+                            expected: stringify!($int),
+                            found: "Int out of its range",
+                        }
+                    }),
+                    value => Err(mismatch("Int", &value)),
+                }
+            }
+        }
+    )*};
+}
+
+from_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self, Span::default())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self, EngineError> {
+        match forced(value) {
+            Value::Bool(b, ..) => Ok(b),
+            value => Err(mismatch("Bool", &value)),
+        }
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self) -> Value {
+        Value::Char(self, Span::default())
+    }
+}
+
+impl FromValue for char {
+    fn from_value(value: Value) -> Result<Self, EngineError> {
+        match forced(value) {
+            Value::Char(c, ..) => Ok(c),
+            value => Err(mismatch("Char", &value)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self, Span::default())
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        self.to_string().into_value()
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self, EngineError> {
+        match forced(value) {
+            Value::String(string, ..) => Ok(string),
+            value => Err(mismatch("String", &value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        let items = self.into_iter().map(IntoValue::into_value).collect();
+        Value::Array(items, Span::default())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, EngineError> {
+        match forced(value) {
+            Value::Array(items, ..) => items.into_iter().map(T::from_value).collect(),
+            value => Err(mismatch("Array", &value)),
+        }
+    }
+}
+
+impl IntoArgs for () {
+    fn into_args(self) -> Vec<Value> {
+        vec![]
+    }
+}
+
+impl IntoArgs for Vec<Value> {
+    fn into_args(self) -> Vec<Value> {
+        self
+    }
+}
+
+macro_rules! tuple {
+    ($len:literal: $($name:ident),*) => {
+        impl<$($name: IntoValue),*> IntoValue for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_value(self) -> Value {
+                let ($($name,)*) = self;
+                Value::Array(vec![$($name.into_value()),*], Span::default())
+            }
+        }
+
+        impl<$($name: FromValue),*> FromValue for ($($name,)*) {
+            fn from_value(value: Value) -> Result<Self, EngineError> {
+                match forced(value) {
+                    Value::Array(items, ..) if items.len() == $len => {
+                        let mut items = items.into_iter();
+                        Ok(($($name::from_value(
+                            items.next().expect("the length was checked"),
+                        )?,)*))
+                    }
+                    value => {
+                        Err(mismatch(concat!("Array of ", $len, " items"), &value))
+                    }
+                }
+            }
+        }
+
+        impl<$($name: IntoValue),*> IntoArgs for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<Value> {
+                let ($($name,)*) = self;
+                vec![$($name.into_value()),*]
+            }
+        }
+    };
+}
+
+tuple!(1: A);
+tuple!(2: A, B);
+tuple!(3: A, B, C);
+tuple!(4: A, B, C, D);
@@ -0,0 +1,485 @@
+//! Snow inside a Rust program. An [`Engine`] loads source files, checks
+//! them the way `snowc` does and calls what they define with Rust values.
+//! ```no_run
+//! let mut engine = snowc::Engine::new();
+//! engine.load("math.snow", "add x y = x + y").unwrap();
+//! let sum: i32 = engine.call("add", (1, 2)).unwrap();
+//! engine.set("limit", 10).unwrap();
+//! let limit: u32 = engine.get("limit").unwrap();
+//! ```
+use crate::convert::{FromValue, IntoArgs, IntoValue};
+use snowc_parse::{
+    error::Error, expr::App, parse_file, pragmas, validate_with, Atom, Expr, Span,
+    TokenPosition,
+};
+use snowc_tree_walker::{RuntimeError, Scope, Thunk, Value};
+use snowc_type_checker::{type_check_with, TypeError, Warning};
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EngineError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// Loading a file or setting a global didn't check, nothing of it was
+    /// kept.
+    #[error("{} has errors", .0.filename)]
+    Diagnostics(Box<Diagnostics>),
+    /// With the file the error happened in, which is where it is shown.
+    #[error("{error}")]
+    Runtime {
+        error: Box<RuntimeError>,
+        filename: String,
+        src: String,
+    },
+    #[error("expected {expected} but the value is {found}")]
+    Conversion {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl EngineError {
+    /// The error shown in its source the way `snowc` shows it, when it has
+    /// one.
+    pub fn report(&self) -> String {
+        match self {
+            Self::Diagnostics(diagnostics) => diagnostics.report(),
+            Self::Runtime {
+                error,
+                filename,
+                src,
+            } => error.report(filename, src),
+            error => error.to_string(),
+        }
+    }
+}
+
+/// What checking one source found. A global that was set is checked as a
+/// source named after it without text.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    pub filename: String,
+    pub src: String,
+    pub errors: Vec<Error>,
+    pub type_errors: Vec<TypeError>,
+    pub warnings: Vec<Warning>,
+    /// Every file loaded by then, an error in an earlier one is shown there.
+    files: Vec<(String, String)>,
+}
+
+impl Diagnostics {
+    fn new(filename: &str, src: &str, files: Vec<(String, String)>) -> Self {
+        Self {
+            filename: filename.into(),
+            src: src.into(),
+            files,
+            ..Self::default()
+        }
+    }
+
+    /// The name and text of the file `span` is in.
+    fn source(&self, span: Span) -> (&str, &str) {
+        match file(&self.files, span) {
+            Some((filename, src)) => (filename, src),
+            None => (&self.filename, &self.src),
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty() || !self.type_errors.is_empty()
+    }
+
+    /// Every error and then every warning, shown in the source the way
+    /// `snowc` shows them.
+    pub fn report(&self) -> String {
+        let errors = self.errors.iter().map(|error| {
+            let (filename, src) = self.source(error.span());
+            error.report(filename, src)
+        });
+        let type_errors = self.type_errors.iter().map(|error| {
+            let (filename, src) = self.source(error.span());
+            error.report(filename, src)
+        });
+        let warnings = self.warnings.iter().map(|warning| {
+            let (filename, src) = self.source(warning.span());
+            warning.report(filename, src)
+        });
+        errors
+            .chain(type_errors)
+            .chain(warnings)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn failed(self) -> EngineError {
+        EngineError::Diagnostics(Box::new(self))
+    }
+}
+
+/// Holds every definition loaded so far. Each load is checked together
+/// with the ones before it, so a file can use what an earlier one defines.
+///
+/// Calls run on the tree-walker. There is no VM backend, code generation
+/// doesn't lower a program so the VM has nothing of it to call.
+#[derive(Debug, Default)]
+pub struct Engine {
+    ast: Vec<Expr>,
+    scope: Scope,
+    /// Given by the last check, a load only reports the ones it adds.
+    warnings: Vec<Warning>,
+    /// The name and text of every file loaded, the spans of the n-th one
+    /// have `file` n + 1.
+    sources: Vec<(String, String)>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tree-walker's settings and natives, [`Scope::register`] adds a
+    /// function written in Rust.
+    pub fn scope_mut(&mut self) -> &mut Scope {
+        &mut self.scope
+    }
+
+    /// Checks `src` and adds its definitions. A file doesn't need a `main`.
+    /// Gives back the warnings it caused, and its errors when it doesn't
+    /// check. A file starting with `--# lazy` makes the engine lazy.
+    pub fn load(
+        &mut self,
+        filename: &str,
+        src: &str,
+    ) -> Result<Diagnostics, EngineError> {
+        let mut files = self.sources.clone();
+        files.push((filename.into(), src.into()));
+        let mut diagnostics = Diagnostics::new(filename, src, files);
+        let ast = match parse_file(src, self.sources.len() + 1) {
+            Ok(ast) => ast,
+            Err(errors) => {
+                diagnostics.errors = errors;
+                return Err(diagnostics.failed());
+            }
+        };
+        let mut program = self.ast.clone();
+        program.extend(ast.iter().cloned());
+        let externs = self.scope.externs();
+        if let Err(errors) = validate_with(&program, &externs) {
+            diagnostics.errors = errors
+                .into_iter()
+                .filter(|error| !matches!(error, Error::MissingMain(..)))
+                .collect();
+        }
+        if diagnostics.has_errors() {
+            return Err(diagnostics.failed());
+        }
+        let warnings = match type_check_with(&program, &externs) {
+            Ok(checked) => checked.warnings,
+            Err(errors) => {
+                diagnostics.type_errors = errors;
+                return Err(diagnostics.failed());
+            }
+        };
+        diagnostics.warnings = warnings
+            .iter()
+            .filter(|warning| !self.warnings.contains(warning))
+            .cloned()
+            .collect();
+        self.warnings = warnings;
+        if pragmas(src).contains(&"lazy") {
+            self.scope.lazy = true;
+        }
+        self.scope.define(&ast);
+        self.ast = program;
+        self.sources.push((filename.into(), src.into()));
+        Ok(diagnostics)
+    }
+
+    pub fn load_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Diagnostics, EngineError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)?;
+        self.load(&path.display().to_string(), &src)
+    }
+
+    /// Calls the function, constructor or native `name` with `args` and
+    /// converts what it gives back.
+    pub fn call<R: FromValue>(
+        &self,
+        name: &str,
+        args: impl IntoArgs,
+    ) -> Result<R, EngineError> {
+        let value = self
+            .scope
+            .call(name, args.into_args())
+            .map_err(|error| self.runtime_error(error))?;
+        R::from_value(value)
+    }
+
+    /// The value of the global `name`.
+    pub fn get<R: FromValue>(&self, name: &str) -> Result<R, EngineError> {
+        self.call(name, ())
+    }
+
+    /// Makes `name` a global with `value`, in place of a definition of the
+    /// same name. The program is checked again with it.
+    pub fn set(&mut self, name: &str, value: impl IntoValue) -> Result<(), EngineError> {
+        let func = Expr::Func(
+            name.into(),
+            vec![],
+            Box::new(value_expr(value.into_value())?),
+            Span::default(),
+        );
+        let mut program = self.ast.clone();
+        match program
+            .iter_mut()
+            .find(|expr| matches!(expr, Expr::Func(func, ..) if func == name))
+        {
+            Some(old) => *old = func.clone(),
+            None => program.push(func.clone()),
+        }
+        let externs = self.scope.externs();
+        let mut diagnostics = Diagnostics::new(name, "", self.sources.clone());
+        if let Err(errors) = validate_with(&program, &externs) {
+            diagnostics.errors = errors
+                .into_iter()
+                .filter(|error| !matches!(error, Error::MissingMain(..)))
+                .collect();
+        }
+        if diagnostics.has_errors() {
+            return Err(diagnostics.failed());
+        }
+        let checked = match type_check_with(&program, &externs) {
+            Ok(checked) => checked,
+            Err(errors) => {
+                diagnostics.type_errors = errors;
+                return Err(diagnostics.failed());
+            }
+        };
+        self.warnings = checked.warnings;
+        self.scope.define(std::slice::from_ref(&func));
+        self.ast = program;
+        Ok(())
+    }
+
+    /// Runs `main` and gives back its value.
+    pub fn run<R: FromValue>(&self) -> Result<R, EngineError> {
+        self.get("main")
+    }
+
+    /// `error` shown in the file it happened in. A global that was set has
+    /// no file.
+    fn runtime_error(&self, error: RuntimeError) -> EngineError {
+        let (filename, src) = file(&self.sources, error.span())
+            .cloned()
+            .unwrap_or_default();
+        EngineError::Runtime {
+            error: Box::new(error),
+            filename,
+            src,
+        }
+    }
+}
+
+/// The one of `files` that `span` is in.
+fn file(files: &[(String, String)], span: Span) -> Option<&(String, String)> {
+    files.get(span.file.checked_sub(1)?)
+}
+
+/// `value` written as the expression that gives it. Functions and values
+/// still being worked out have none.
+fn value_expr(value: Value) -> Result<Expr, EngineError> {
+    let pos = TokenPosition::Middle;
+    let span = Span::default();
+    let atom = |atom| Ok(Expr::Atom(atom));
+    let exprs = |values: Vec<Value>| {
+        values
+            .into_iter()
+            .map(value_expr)
+            .collect::<Result<Vec<_>, _>>()
+    };
+    match value {
+        Value::Int(int, ..) => atom(Atom::Int(int, pos, span)),
+        Value::Float(float, ..) => atom(Atom::Float(float, pos, span)),
+        Value::Bool(b, ..) => atom(Atom::Bool(b, pos, span)),
+        Value::String(string, ..) => atom(Atom::String(string, pos, span)),
+        Value::Char(c, ..) => atom(Atom::Char(c, pos, span)),
+        Value::Array(items, ..) => Ok(Expr::Array(exprs(items)?, pos, span)),
+        Value::Variant(name, fields, ..) if fields.is_empty() => {
+            atom(Atom::Id(name, pos, span))
+        }
+        Value::Variant(name, fields, ..) => Ok(Expr::App(App {
+            name: Box::new(Expr::Atom(Atom::Id(name, pos, span))),
+            args: exprs(fields)?,
+            pos,
+            span,
+        })),
+        Value::Thunk(thunk) => match thunk.borrow().clone() {
+            Thunk::Done(value) => value_expr(value),
+            Thunk::Pending(..) => Err(EngineError::Conversion {
+                expected: "a value",
+                found: "thunk",
+            }),
+        },
+        Value::Closure { .. } => Err(EngineError::Conversion {
+            expected: "a value",
+            found: "function",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snowc_tree_walker::Native;
+
+    const MATH: &str = r#"
+add x y = x + y
+swap pair = [nth pair 1, nth pair 0]
+names = ["snow", "lang"]
+"#;
+
+    #[test]
+    fn calls_with_rust_values() {
+        let mut engine = Engine::new();
+        engine.load("math.snow", MATH).unwrap();
+        assert_eq!(engine.call::<i32>("add", (1, 2)).unwrap(), 3);
+        assert_eq!(engine.call::<(u8, u8)>("swap", ((1, 2),)).unwrap(), (2, 1));
+        assert_eq!(
+            engine.get::<Vec<String>>("names").unwrap(),
+            ["snow".to_string(), "lang".to_string()]
+        );
+        assert!(matches!(
+            engine.call::<bool>("add", (1, 2)),
+            Err(EngineError::Conversion { found: "Int", .. })
+        ));
+    }
+
+    #[test]
+    fn later_files_use_earlier_ones() {
+        let mut engine = Engine::new();
+        engine.load("math.snow", MATH).unwrap();
+        engine.load("main.snow", "twice x = add x x").unwrap();
+        assert_eq!(engine.call::<i32>("twice", (21,)).unwrap(), 42);
+        let Err(EngineError::Diagnostics(diagnostics)) = engine.load("bad.snow", "x = y")
+        else {
+            panic!("expected an undefined name");
+        };
+        assert_eq!(diagnostics.errors.len(), 1);
+        assert!(engine.get::<i32>("x").is_err());
+    }
+
+    #[test]
+    fn set_globals_are_checked() {
+        let mut engine = Engine::new();
+        engine
+            .load("limit.snow", "limit = 1\nnext = limit + 1")
+            .unwrap();
+        engine.set("limit", 10).unwrap();
+        assert_eq!(engine.get::<i32>("next").unwrap(), 11);
+        assert!(matches!(
+            engine.set("limit", "ten"),
+            Err(EngineError::Diagnostics(..))
+        ));
+        assert_eq!(engine.get::<i32>("limit").unwrap(), 10);
+    }
+
+    #[test]
+    fn runs_main() {
+        let mut engine = Engine::new();
+        engine.load("math.snow", MATH).unwrap();
+        engine.load("main.snow", "main = add 40 2").unwrap();
+        assert_eq!(engine.run::<i32>().unwrap(), 42);
+    }
+
+    #[test]
+    fn runtime_errors_show_the_file() {
+        let mut engine = Engine::new();
+        let src = "first xs = head xs";
+        engine.load("list.snow", src).unwrap();
+        let error = engine
+            .call::<i32>("first", (Vec::<i32>::new(),))
+            .unwrap_err();
+        let EngineError::Runtime { filename, .. } = &error else {
+            panic!("expected a runtime error, got {error:?}");
+        };
+        assert_eq!(filename, "list.snow");
+        assert!(error.report().contains(src));
+    }
+
+    #[test]
+    fn loaded_files_call_host_natives() {
+        let mut engine = Engine::new();
+        let double = Native::new("double", 1, |args| match &args[0] {
+            Value::Int(i, span) => Ok(Value::Int(i * 2, *span)),
+            _ => Err(RuntimeError::InvalidArguments(Span::default())),
+        });
+        let scope = engine.scope_mut();
+        scope.register(double.signature("Int -> Int"));
+        scope.register(Native::new("first", 1, |args| Ok(args[0].clone())));
+        engine
+            .load(
+                "a.snow",
+                "quad x = double (double x)
+head2 xs = first xs",
+            )
+            .unwrap();
+        assert_eq!(engine.call::<i32>("quad", (3,)).unwrap(), 12);
+        assert_eq!(engine.call::<i32>("head2", (5,)).unwrap(), 5);
+        let Err(EngineError::Diagnostics(diagnostics)) =
+            engine.load("b.snow", "bad = double \"two\"")
+        else {
+            panic!("expected a type error");
+        };
+        assert_eq!(diagnostics.type_errors.len(), 1);
+    }
+
+    #[test]
+    fn builtins_and_natives_cannot_be_redefined() {
+        let mut engine = Engine::new();
+        engine
+            .scope_mut()
+            .register(Native::new("first", 1, |args| Ok(args[0].clone())));
+        for src in ["head x = x + 1", "first xs = 0"] {
+            let Err(EngineError::Diagnostics(diagnostics)) = engine.load("a.snow", src)
+            else {
+                panic!("expected {src} to be refused");
+            };
+            assert!(
+                matches!(diagnostics.errors[..], [Error::RedefinedBuiltin(..)]),
+                "{diagnostics:?}"
+            );
+        }
+        assert!(matches!(
+            engine.set("length", 1),
+            Err(EngineError::Diagnostics(..))
+        ));
+        assert_eq!(engine.call::<i32>("length", (vec![1, 2],)).unwrap(), 2);
+    }
+
+    #[test]
+    fn errors_show_the_file_they_happen_in() {
+        let mut engine = Engine::new();
+        let list = "-- lists\n\n-- the first item\nfirst xs = head xs";
+        engine.load("list.snow", list).unwrap();
+        engine.load("main.snow", "go x = first []").unwrap();
+        let error = engine.call::<i32>("go", (1,)).unwrap_err();
+        let EngineError::Runtime { filename, .. } = &error else {
+            panic!("expected a runtime error, got {error:?}");
+        };
+        assert_eq!(filename, "list.snow");
+        assert!(error.report().contains("first xs = head xs"));
+
+        engine
+            .load("limit.snow", "limit = 1\nnext = limit + 1")
+            .unwrap();
+        let Err(error) = engine.set("limit", "ten") else {
+            panic!("expected a type error");
+        };
+        let report = error.report();
+        assert!(report.contains("limit.snow"), "{report}");
+        assert!(report.contains("next = limit + 1"), "{report}");
+    }
+}
@@ -1,6 +1,12 @@
+mod convert;
+mod engine;
+
 // pub use snowc_byte_code_gen::*;
 pub use snowc_code_gen::*;
 pub use snowc_parse::*;
 pub use snowc_tree_walker::*;
 pub use snowc_vm::{debug_program, Machine};
-// pub use snowc_type_checker::*;
+pub use snowc_type_checker::*;
+pub use snowc_lint::{lint, Level, Levels, LINTS};
+pub use convert::{FromValue, IntoArgs, IntoValue};
+pub use engine::{Diagnostics, Engine, EngineError};
@@ -1,12 +1,18 @@
 mod args;
+use args::Emit;
 use snowc::error::Error;
-use snowc::{debug_program, gen_code, parse, walk, Expr, Machine, Scanner};
+use snowc::{
+    debug_program, gen_code, lint, parse, pragmas, type_check, validate, walk_with_scope,
+    EvalObserver, Expr, Level, Levels, Machine, Scanner, Scope, Token, Tracer, TypeError,
+    Warning,
+};
 use snowc_repl::repl;
+use std::{cell::RefCell, rc::Rc};
 #[derive(Debug)]
 enum CompilerError {
     NoFileGive,
     Parse(Vec<Error>),
-    Type(Vec<String>),
+    Type(Vec<TypeError>),
 }
 
 impl From<Vec<Error>> for CompilerError {
@@ -15,8 +21,8 @@ impl From<Vec<Error>> for CompilerError {
     }
 }
 
-impl From<Vec<String>> for CompilerError {
-    fn from(value: Vec<String>) -> Self {
+impl From<Vec<TypeError>> for CompilerError {
+    fn from(value: Vec<TypeError>) -> Self {
         Self::Type(value)
     }
 }
@@ -43,6 +49,30 @@ fn debug_ast(flag: bool) -> impl FnOnce(Vec<Expr>) -> Result<Vec<Expr>, Compiler
     }
 }
 
+fn emit_json<T: serde::Serialize + ?Sized>(value: &T) {
+    let json = serde_json::to_string_pretty(value).expect("failed to serialize to json");
+    println!("{json}");
+}
+
+fn emit_tokens(flag: bool) -> impl FnOnce(String) -> Result<String, CompilerError> {
+    move |src: String| {
+        if flag {
+            let tokens = Scanner::new(&src).collect::<Vec<Token>>();
+            emit_json(&tokens);
+        }
+        Ok(src)
+    }
+}
+
+fn emit_ast(flag: bool) -> impl FnOnce(Vec<Expr>) -> Result<Vec<Expr>, CompilerError> {
+    move |ast| {
+        if flag {
+            emit_json(&ast);
+        }
+        Ok(ast)
+    }
+}
+
 fn handle_compiler_errors(filename: impl Into<String>) -> impl FnOnce(CompilerError) {
     move |error_type| match error_type {
         CompilerError::Parse(ref errors) => {
@@ -53,11 +83,17 @@ fn handle_compiler_errors(filename: impl Into<String>) -> impl FnOnce(CompilerEr
                 let msg = error.report(&filename, &src);
                 eprintln!("{msg}");
             }
+            std::process::exit(1);
         }
-        CompilerError::Type(errors) => {
-            for error in errors {
-                eprintln!("{error}");
+        CompilerError::Type(ref errors) => {
+            let filename = filename.into();
+            let src = std::fs::read_to_string(&filename)
+                .expect("failed to get file source for error report");
+            for error in errors.iter() {
+                let msg = error.report(&filename, &src);
+                eprintln!("{msg}");
             }
+            std::process::exit(1);
         }
         CompilerError::NoFileGive => {
             println!("");
@@ -65,6 +101,16 @@ fn handle_compiler_errors(filename: impl Into<String>) -> impl FnOnce(CompilerEr
     }
 }
 
+fn report_warnings(filename: &str, warnings: &[Warning]) {
+    let Ok(src) = std::fs::read_to_string(filename) else {
+        warnings.iter().for_each(|warning| eprintln!("{warning}"));
+        return;
+    };
+    for warning in warnings.iter() {
+        eprintln!("{}", warning.report(filename, &src));
+    }
+}
+
 fn get_src(flag: bool) -> impl FnOnce(String) -> Result<String, CompilerError> {
     move |filename| {
         if flag {
@@ -76,60 +122,138 @@ fn get_src(flag: bool) -> impl FnOnce(String) -> Result<String, CompilerError> {
     }
 }
 
+/// `snowc lint`, reports what the lints find and fails when one of them is
+/// denied.
+fn lint_file(filename: &str, levels: &Levels) {
+    let Ok(src) = std::fs::read_to_string(filename) else {
+        return handle_compiler_errors(filename)(CompilerError::NoFileGive);
+    };
+    let parsed = parse(&src).and_then(|ast| {
+        validate(&ast)?;
+        Ok(ast)
+    });
+    let ast = match parsed {
+        Ok(ast) => ast,
+        Err(errors) => return handle_compiler_errors(filename)(errors.into()),
+    };
+    let diagnostics = lint(&ast, levels);
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{}", diagnostic.report(filename, &src));
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.level == Level::Deny)
+    {
+        std::process::exit(1);
+    }
+}
+
+/// Where `--trace` writes, `None` without it.
+fn tracer(setting: &args::Settings) -> std::io::Result<Option<Tracer>> {
+    if !setting.trace {
+        return Ok(None);
+    }
+    let only = setting.trace_only.clone();
+    let Some(path) = &setting.trace_file else {
+        return Ok(Some(Tracer::new(std::io::stderr(), only)));
+    };
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    Ok(Some(Tracer::new(file, only)))
+}
+
+/// Runs a program on the tree-walker, only it has thunks for lazy programs
+/// and tells an observer about calls.
+fn walk_file(
+    ast: &[Expr],
+    filename: &str,
+    src: &str,
+    lazy: bool,
+    tracer: Option<Tracer>,
+) {
+    let mut scope = Scope::default();
+    scope.lazy = lazy;
+    scope.observer = tracer
+        .map(|tracer| -> Rc<RefCell<dyn EvalObserver>> { Rc::new(RefCell::new(tracer)) });
+    let Err(errors) = walk_with_scope(ast, scope) else {
+        return;
+    };
+    for error in errors.iter() {
+        eprintln!("{}", error.report(filename, src));
+    }
+    std::process::exit(1);
+}
+
+/// `--vm`, runs what code generation gives for the program on the VM.
+fn run_vm(ast: Vec<Expr>, filename: &str, lazy: bool) {
+    if lazy {
+        eprintln!("{filename} asks to be lazy but the VM can't run lazy programs");
+        std::process::exit(1);
+    }
+    let program = gen_code(&ast);
+    debug_program(&program);
+    let mut vm = Machine::new(program, false);
+    vm.run();
+}
+
 fn main() {
     let setting = args::cargs();
+    if let Some(levels) = &setting.lint {
+        let filename = setting.filename.clone().unwrap_or_default();
+        return lint_file(&filename, levels);
+    }
     if setting.debug_graph {
         unimplemented!("graphviz is not working just yet");
     }
+    let tracer = match tracer(&setting) {
+        Ok(tracer) => tracer,
+        Err(err) => {
+            let path = setting.trace_file.unwrap_or_default();
+            eprintln!("failed to open trace file '{path}': {err}");
+            std::process::exit(1);
+        }
+    };
+    let emit = setting.emit.clone();
+    let origin = setting.filename.clone().unwrap_or_default();
     setting
         .filename
         .clone()
         .ok_or_else(|| {
-            let _ = repl();
+            let _ = repl(!setting.option_no_type_check);
             CompilerError::NoFileGive
         })
         .and_then(get_src(setting.option_compile_string))
         .and_then(debug_tokens(setting.debug_token))
+        .and_then(emit_tokens(emit.contains(&Emit::Tokens)))
         .and_then(|src| timer("Parsing", || parse(&src)).map_err(Into::into))
         .and_then(debug_ast(setting.debug_ast))
-        // .and_then(|ast| {
-        //     if !setting.option_no_type_check {
-        //         timer("Type Checking", || type_check(&ast))
-        //             .map_err(Into::<CompilerError>::into)?;
-        //     }
-        //     Ok(ast)
-        // })
-        // .map_or_else(
-        //     handle_compiler_errors(setting.filename.clone().unwrap_or_default()),
-        //     |ast| {
-        //         let msg = format_compiler_message("Running");
-        //         let filename = setting.filename.unwrap_or_default();
-        //         eprintln!("{msg} {filename}");
-        //         let result = walk(&ast);
-        //         match result {
-        //             Ok(_) => {}
-        //             Err(errors) => {
-        //                 let src =
-        //                     get_src(setting.option_compile_string)(filename.clone())
-        //                         .expect("failed to get file source for error report");
-        //                 for err in errors.iter() {
-        //                     let msg = err.report(&filename, &src);
-        //                     eprintln!("{msg}");
-        //                 }
-        //             }
-        //         }
-        //     },
-        // );
+        .and_then(emit_ast(emit.contains(&Emit::Ast)))
+        .and_then(|ast| {
+            validate(&ast)?;
+            Ok(ast)
+        })
+        .and_then(|ast| {
+            if !setting.option_no_type_check {
+                let checked = timer("Type Checking", || type_check(&ast))?;
+                report_warnings(&origin, &checked.warnings);
+            }
+            Ok(ast)
+        })
         .map_or_else(
             handle_compiler_errors(setting.filename.clone().unwrap_or_default()),
             |ast| {
+                if !emit.is_empty() {
+                    return;
+                }
                 let msg = format_compiler_message("Running");
                 let filename = setting.filename.unwrap_or_default();
                 eprintln!("{msg} {filename}");
-                let program = gen_code(&ast);
-                debug_program(&program);
-                let mut vm = Machine::new(program, false);
-                vm.run();
+                let src = get_src(setting.option_compile_string)(filename.clone())
+                    .unwrap_or_default();
+                let lazy = setting.lazy || pragmas(&src).contains(&"lazy");
+                if setting.vm {
+                    return run_vm(ast, &filename, lazy);
+                }
+                walk_file(&ast, &filename, &src, lazy, tracer);
             },
         );
 }